@@ -0,0 +1,559 @@
+//! Host-side tool to inspect, validate, process and convert recordings, and to check configs without hardware.
+//!
+//! This requires the cli feature, run `bgt60trxx-cli help` for the usage.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use bgt60trxx::{Variant, fifo_samples};
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::doppler::non_coherent_sum;
+use bgt60trxx::dsp::{Cfar, CfarKind, Detection, DopplerFft, RangeFft, Window};
+use bgt60trxx::error::Error;
+use bgt60trxx::infineon::{AntennaList, config_from_json_with, read_fusion_recording, read_raw, write_fusion_recording, write_raw};
+use bgt60trxx::npy::{FloatScale, read_frames, write_array, write_frames, write_npz, write_recording_npz};
+use bgt60trxx::recording::{Header, Recorder, RecordingReader};
+use bgt60trxx::register::CHIP_ID;
+use ndarray::Array3;
+
+const USAGE: &str = "\
+Usage: bgt60trxx-cli <command> [arguments] [options]
+
+Commands:
+  info <input>                  Print the config, the number of frames and the derived metrics of a recording
+  validate <input>              Check the frame count, frame counters, timestamps and sample values of a recording
+  process <input> <output>      Run range-Doppler processing on every frame, and export the CFAR detections
+                                (output.csv) or the range-Doppler maps summed over all antennas (output.npy)
+  convert <input> <output>      Convert a recording into another format
+  check-config <config.json>    Print the derived metrics of a config, and check that a frame fits into the FIFO
+  help                          Print this help
+
+Inputs:
+  <file>                        Recording of the `recording` module, detected by its magic bytes
+  <directory>                   Radar Fusion GUI recording (config.json and radar.npy)
+  <file>.npy                    Stacked frames with the shape [frame, rx, chirp, sample], requires --config
+  <file>.bin, <file>.raw        Raw little-endian u16 samples, with the config from --config or <file>.json
+
+Outputs of convert:
+  <file>.bgtr                   Recording of the `recording` module
+  <file>.npy, <file>.npz        NumPy arrays, the .npz with the config (and frame counters of recordings) alongside
+  <file>.bin, <file>.raw        Raw little-endian u16 samples, with the config written to <file>.json
+  <directory>                   Radar Fusion GUI recording, for any path without extension
+
+Options:
+  --config <config.json>        Config of inputs without one, in the JSON format of the bgt60-configurator-cli
+  --variant <tr13c|utr11aip>    Radar variant, for the FIFO size and for recordings written from other formats (default: tr13c)
+  --window <rectangular|hann|hamming|blackman-harris>
+                                Window of the range and Doppler FFTs (default: hann)
+  --float-scale <counts|normalized>
+                                Scale of floating-point samples in .npy inputs and Radar Fusion GUI recordings,
+                                ADC counts or normalized to the ADC full scale (default: normalized)
+  --antennas <numbers|count>    Antenna lists of the configs of check-config and --config: the numbers of the enabled
+                                antennas (e.g. [1, 2, 3]), or their count in a single entry (e.g. [3]), as in the
+                                templates of the bgt60-configurator-cli in config/ (default: numbers, with a warning
+                                if the lists hold a different count)
+";
+
+/// A recording loaded into memory, independent of its format.
+struct Capture {
+    format: &'static str,
+    variant: Option<Variant>,
+    chip_id: Option<CHIP_ID>,
+    config: Config,
+    frames: Vec<Array3<u16>>,
+    /// The frame counters and timestamps in microseconds, only stored in recordings of the `recording` module.
+    counters: Option<Vec<(u32, u64)>>,
+    /// The error which stopped reading, e.g. a recording that was cut off.
+    error: Option<Error>,
+}
+
+#[derive(Default)]
+struct Options {
+    arguments: Vec<String>,
+    config: Option<PathBuf>,
+    variant: Option<Variant>,
+    window: Option<Window>,
+    float_scale: Option<FloatScale>,
+    antenna_list: Option<AntennaList>,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match options.arguments.first().map(String::as_str) {
+        Some("info") => info(&options),
+        Some("validate") => validate(&options),
+        Some("process") => process(&options),
+        Some("convert") => convert(&options),
+        Some("check-config") => check_config(&options),
+        Some("help") | None => {
+            print!("{}", USAGE);
+            Ok(true)
+        }
+        Some(command) => Err(format!("unknown command `{}`, see `bgt60trxx-cli help`", command)),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value of {}", arg));
+        match arg.as_str() {
+            "--config" => options.config = Some(PathBuf::from(value()?)),
+            "--variant" => {
+                options.variant = Some(match value()?.to_lowercase().as_str() {
+                    "tr13c" | "bgt60tr13c" => Variant::BGT60TR13C,
+                    "utr11aip" | "bgt60utr11aip" => Variant::BGT60UTR11AIP,
+                    variant => return Err(format!("unknown variant `{}`", variant)),
+                })
+            }
+            "--window" => {
+                options.window = Some(match value()?.to_lowercase().as_str() {
+                    "rectangular" => Window::Rectangular,
+                    "hann" => Window::Hann,
+                    "hamming" => Window::Hamming,
+                    "blackman-harris" => Window::BlackmanHarris,
+                    window => return Err(format!("unknown window `{}`", window)),
+                })
+            }
+            "--float-scale" => {
+                options.float_scale = Some(match value()?.to_lowercase().as_str() {
+                    "counts" => FloatScale::AdcCounts,
+                    "normalized" => FloatScale::Normalized,
+                    scale => return Err(format!("unknown float scale `{}`", scale)),
+                })
+            }
+            "--antennas" => {
+                options.antenna_list = Some(match value()?.to_lowercase().as_str() {
+                    "numbers" => AntennaList::Numbers,
+                    "count" => AntennaList::Count,
+                    antennas => return Err(format!("unknown antenna list `{}`", antennas)),
+                })
+            }
+            "-h" | "--help" => options.arguments.insert(0, String::from("help")),
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+            _ => options.arguments.push(arg),
+        }
+    }
+    Ok(options)
+}
+
+/// Returns the positional argument after the command, or an error naming it.
+fn argument<'a>(options: &'a Options, index: usize, name: &str) -> Result<&'a Path, String> {
+    options
+        .arguments
+        .get(index)
+        .map(Path::new)
+        .ok_or_else(|| format!("missing {}, see `bgt60trxx-cli help`", name))
+}
+
+fn info(options: &Options) -> Result<bool, String> {
+    let capture = load(argument(options, 1, "input")?, options)?;
+
+    println!("Format: {}", capture.format);
+    if let Some(variant) = capture.variant {
+        println!("Variant: {:?}", variant);
+    }
+    if let Some(chip_id) = capture.chip_id {
+        println!("Chip ID: digital {}, RF {}", chip_id.digital_id(), chip_id.rf_id());
+    }
+    println!("{}", capture.config);
+    println!(
+        "Frames: {} ({:.2} s at the configured frame rate)",
+        capture.frames.len(),
+        capture.frames.len() as f64 * capture.config.frame_repetition_time_s
+    );
+    if let Some(error) = &capture.error {
+        println!("Reading stopped early: {}", error);
+    }
+    println!();
+    print_metrics(&capture.config, capture.variant.or(options.variant).unwrap_or(Variant::BGT60TR13C));
+
+    Ok(true)
+}
+
+fn validate(options: &Options) -> Result<bool, String> {
+    let capture = load(argument(options, 1, "input")?, options)?;
+    let config = &capture.config;
+    let mut issues = Vec::new();
+
+    println!("Frames: {}", capture.frames.len());
+    if capture.frames.is_empty() {
+        issues.push(String::from("the recording contains no frames"));
+    }
+    if let Some(error) = &capture.error {
+        issues.push(format!("reading stopped after {} frames: {}", capture.frames.len(), error));
+    }
+
+    let invalid_samples = capture.frames.iter().flatten().filter(|&&sample| sample > 0x0FFF).count();
+    if invalid_samples > 0 {
+        issues.push(format!("{} samples exceed the 12-bit range of the ADC", invalid_samples));
+    }
+
+    match &capture.counters {
+        Some(counters) if counters.len() > 1 => {
+            let mut missing = 0u64;
+            let mut out_of_order = 0;
+            let mut late = 0;
+            for pair in counters.windows(2) {
+                let ((counter, timestamp), (next_counter, next_timestamp)) = (pair[0], pair[1]);
+                match next_counter.wrapping_sub(counter) {
+                    1 => {}
+                    gap if gap < u32::MAX / 2 => missing += gap as u64 - 1,
+                    _ => out_of_order += 1,
+                }
+                if next_timestamp <= timestamp {
+                    out_of_order += 1;
+                } else if (next_timestamp - timestamp) as f64 * 1e-6 > 1.5 * config.frame_repetition_time_s {
+                    late += 1;
+                }
+            }
+
+            let (first, last) = (counters[0], counters[counters.len() - 1]);
+            let interval_s = (last.1.saturating_sub(first.1)) as f64 * 1e-6 / (counters.len() - 1) as f64;
+            println!("Frame counters: {} to {}", first.0, last.0);
+            println!(
+                "Mean frame interval: {:.3} ms (configured: {:.3} ms)",
+                interval_s * 1e3,
+                config.frame_repetition_time_s * 1e3
+            );
+
+            if missing > 0 {
+                issues.push(format!("{} frames are missing between the frame counters", missing));
+            }
+            if out_of_order > 0 {
+                issues.push(format!("{} frame counters or timestamps are out of order", out_of_order));
+            }
+            if late > 0 {
+                issues.push(format!("{} frame intervals exceed 1.5 times the frame repetition time", late));
+            }
+        }
+        Some(_) => {}
+        None => println!("Frame counters: not stored in the {} format", capture.format),
+    }
+
+    Ok(report(&issues))
+}
+
+fn process(options: &Options) -> Result<bool, String> {
+    let output = argument(options, 2, "output")?;
+    let capture = load(argument(options, 1, "input")?, options)?;
+    let config = &capture.config;
+    let window = options.window.unwrap_or(Window::Hann);
+
+    let range_fft = RangeFft::new(config, window).map_err(error)?;
+    let doppler_fft = DopplerFft::new(config, &range_fft, window);
+    let map_size = doppler_fft.map_size();
+
+    let mut maps = Vec::with_capacity(capture.frames.len() * map_size);
+    let mut map = vec![0.0f32; map_size];
+    for frame in &capture.frames {
+        let range_profiles = range_fft.process_frames(frame).map_err(error)?;
+        let range_doppler = doppler_fft.process_range_profiles(&range_profiles).map_err(error)?;
+        // Unwrap is safe, since the maps are returned in standard layout
+        non_coherent_sum(range_doppler.as_slice().unwrap(), map_size, &mut map).map_err(error)?;
+        maps.extend_from_slice(&map);
+    }
+
+    let mut writer = create(output)?;
+    match extension(output).as_deref() {
+        Some("npy") => {
+            let shape = [capture.frames.len(), doppler_fft.num_doppler_bins(), doppler_fft.num_range_bins()];
+            write_array(&mut writer, &shape, maps.iter().copied()).map_err(error)?;
+        }
+        Some("csv") => {
+            let cfar = Cfar::new(CfarKind::OrderedStatistic { rank: 0.75 }, 2, 4, 1e-4).map_err(error)?;
+            let mut scratch = vec![0.0; cfar.scratch_size_2d()];
+            let mut detections = vec![Detection::default(); 256];
+
+            writeln!(writer, "frame,range_bin,doppler_bin,range_m,velocity_mps,snr_db").map_err(io_error)?;
+            for (index, map) in maps.chunks_exact(map_size).enumerate() {
+                let frame = match &capture.counters {
+                    Some(counters) => counters[index].0 as usize,
+                    None => index,
+                };
+                let count = cfar.detect_2d(map, &range_fft, &doppler_fft, &mut scratch, &mut detections).map_err(error)?;
+                for detection in &detections[..count] {
+                    writeln!(
+                        writer,
+                        "{},{},{},{:.4},{:.4},{:.2}",
+                        frame,
+                        detection.range_bin,
+                        detection.doppler_bin,
+                        detection.range_m,
+                        detection.velocity_mps,
+                        detection.snr_db
+                    )
+                    .map_err(io_error)?;
+                }
+            }
+        }
+        _ => return Err(format!("unsupported output {}, expected a .csv or .npy file", output.display())),
+    }
+    writer.flush().map_err(io_error)?;
+
+    println!(
+        "Processed {} frames ({} Doppler x {} range bins) into {}",
+        capture.frames.len(),
+        doppler_fft.num_doppler_bins(),
+        doppler_fft.num_range_bins(),
+        output.display()
+    );
+    Ok(true)
+}
+
+fn convert(options: &Options) -> Result<bool, String> {
+    let input = argument(options, 1, "input")?;
+    let output = argument(options, 2, "output")?;
+    let capture = load(input, options)?;
+    let config = &capture.config;
+
+    match extension(output).as_deref() {
+        Some("bgtr") => {
+            let header = Header {
+                variant: capture.variant.or(options.variant).unwrap_or(Variant::BGT60TR13C),
+                chip_id: capture.chip_id.unwrap_or(CHIP_ID::from_bits(0)),
+                config: config.clone(),
+            };
+            let mut recorder = Recorder::new(Vec::new(), &header).map_err(error)?;
+            for (index, frame) in capture.frames.iter().enumerate() {
+                // Frames of other formats are numbered and timed by the configured frame rate
+                let (frame_counter, timestamp_us) = match &capture.counters {
+                    Some(counters) => counters[index],
+                    None => (index as u32, (index as f64 * config.frame_repetition_time_s * 1e6) as u64),
+                };
+                recorder.write_frame(frame_counter, timestamp_us, &fifo_samples(frame).collect::<Vec<_>>()).map_err(error)?;
+            }
+            fs::write(output, recorder.into_inner()).map_err(io_error)?;
+        }
+        Some("npy") => {
+            let mut writer = create(output)?;
+            write_frames(&mut writer, config, &capture.frames).map_err(error)?;
+            writer.flush().map_err(io_error)?;
+        }
+        Some("npz") => {
+            let mut writer = create(output)?;
+            if capture.counters.is_some() {
+                // Read the recording again, to store the frame counters and timestamps alongside
+                let reader = RecordingReader::new(BufReader::new(open(input)?)).map_err(error)?;
+                write_recording_npz(&mut writer, reader).map_err(error)?;
+            } else {
+                write_npz(&mut writer, config, &capture.frames).map_err(error)?;
+            }
+            writer.flush().map_err(io_error)?;
+        }
+        Some("bin") | Some("raw") => {
+            write_raw(output, output.with_extension("json"), config, &capture.frames).map_err(error)?;
+        }
+        None => write_fusion_recording(output, config, &capture.frames).map_err(error)?,
+        Some(extension) => return Err(format!("unsupported output format `.{}`, see `bgt60trxx-cli help`", extension)),
+    }
+
+    println!("Converted {} frames from {} to {}", capture.frames.len(), capture.format, output.display());
+    if let Some(error) = &capture.error {
+        println!("Reading stopped early: {}", error);
+    }
+    Ok(true)
+}
+
+fn check_config(options: &Options) -> Result<bool, String> {
+    let path = argument(options, 1, "config")?;
+    let config = read_config(path, options)?;
+    let variant = options.variant.unwrap_or(Variant::BGT60TR13C);
+
+    let mut issues = Vec::new();
+    if config.upper_frequency_hz <= config.lower_frequency_hz {
+        issues.push(String::from("the upper frequency must be above the lower frequency"));
+    }
+    if config.get_fifo_limit() == 0 || !config.get_fifo_limit().is_multiple_of(2) {
+        issues.push(format!("the frame size of {} samples must be a non-zero multiple of two", config.get_fifo_limit()));
+    }
+    if config.fifo_usage(variant) > 1.0 {
+        issues.push(format!("a frame does not fit into the FIFO of the {:?}", variant));
+    }
+    let chirps_s = config.num_chirps_per_frame as f64 * config.chirp_repetition_time_s;
+    if chirps_s > config.frame_repetition_time_s {
+        issues.push(format!(
+            "the chirps of a frame take {:.3} ms, longer than the frame repetition time of {:.3} ms",
+            chirps_s * 1e3,
+            config.frame_repetition_time_s * 1e3
+        ));
+    }
+    let sampling_s = config.num_samples_per_chirp as f64 / config.sample_rate_hz as f64;
+    if sampling_s > config.chirp_repetition_time_s {
+        issues.push(format!(
+            "sampling a chirp takes {:.2} us, longer than the chirp repetition time of {:.2} us",
+            sampling_s * 1e6,
+            config.chirp_repetition_time_s * 1e6
+        ));
+    }
+
+    println!("{}", config);
+    println!();
+    print_metrics(&config, variant);
+    println!();
+
+    Ok(report(&issues))
+}
+
+fn print_metrics(config: &Config, variant: Variant) {
+    println!("Bandwidth: {:.1} MHz", config.bandwidth_hz() as f64 * 1e-6);
+    println!("Range resolution: {:.3} m", config.range_resolution_m());
+    println!("Max range: {:.2} m", config.max_range_m());
+    println!("Velocity resolution: {:.3} m/s", config.velocity_resolution_mps());
+    println!("Max velocity: +/-{:.2} m/s", config.max_velocity_mps());
+    println!("Frame rate: {:.2} Hz", 1.0 / config.frame_repetition_time_s);
+    println!("Frame size: {} samples, {} bytes in the FIFO", config.get_fifo_limit(), config.get_u8_buffer_size());
+    println!(
+        "FIFO usage: {:.1} % of {} blocks ({:?})",
+        config.fifo_usage(variant) * 100.0,
+        variant.fifo_size(),
+        variant
+    );
+    println!(
+        "Data rate: {:.1} kB/s ({:.1} kB/s as u16 samples)",
+        config.data_rate_bytes_per_s() * 1e-3,
+        config.get_fifo_limit() as f64 * 2.0 / config.frame_repetition_time_s * 1e-3
+    );
+}
+
+/// Prints the issues or OK, and returns whether there were none.
+fn report(issues: &[String]) -> bool {
+    if issues.is_empty() {
+        println!("OK");
+    }
+    for issue in issues {
+        println!("Issue: {}", issue);
+    }
+    issues.is_empty()
+}
+
+/// Loads a recording, detecting its format by its magic bytes, extension or by being a directory.
+fn load(path: &Path, options: &Options) -> Result<Capture, String> {
+    if path.is_dir() {
+        let scale = options.float_scale.unwrap_or(FloatScale::Normalized);
+        let (config, frames) = read_fusion_recording(path, scale).map_err(|e| format!("{}: {}", path.display(), e))?;
+        return Ok(Capture::from_frames("Radar Fusion GUI recording", config, frames));
+    }
+
+    let mut magic = [0u8; 6];
+    let len = open(path)?.read(&mut magic).map_err(io_error)?;
+    let magic = &magic[..len];
+
+    if magic.starts_with(b"BGTR") {
+        let mut reader = RecordingReader::new(BufReader::new(open(path)?)).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let header = reader.header().clone();
+        let mut capture = Capture::from_frames("recording", header.config, Vec::new());
+        capture.variant = Some(header.variant);
+        capture.chip_id = Some(header.chip_id);
+
+        let mut counters = Vec::new();
+        for record in &mut reader {
+            match record {
+                Ok(record) => {
+                    counters.push((record.frame_counter, record.timestamp_us));
+                    capture.frames.push(record.frame);
+                }
+                Err(error) => {
+                    capture.error = Some(error);
+                    break;
+                }
+            }
+        }
+        capture.counters = Some(counters);
+        return Ok(capture);
+    }
+
+    if magic.starts_with(b"\x93NUMPY") {
+        let config_path = options.config.as_deref().ok_or("reading a .npy file requires --config")?;
+        let config = read_config(config_path, options)?;
+        let scale = options.float_scale.unwrap_or(FloatScale::Normalized);
+        let frames = read_frames(BufReader::new(open(path)?), &config, scale).map_err(|e| format!("{}: {}", path.display(), e))?;
+        return Ok(Capture::from_frames("NumPy", config, frames));
+    }
+
+    match extension(path).as_deref() {
+        Some("bin") | Some("raw") => {
+            let config_path = options.config.clone().unwrap_or_else(|| path.with_extension("json"));
+            let (config, frames) = read_raw(path, &config_path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            Ok(Capture::from_frames("raw", config, frames))
+        }
+        _ => Err(format!("{}: unknown format, see `bgt60trxx-cli help`", path.display())),
+    }
+}
+
+impl Capture {
+    fn from_frames(format: &'static str, config: Config, frames: Vec<Array3<u16>>) -> Self {
+        Capture {
+            format,
+            variant: None,
+            chip_id: None,
+            config,
+            frames,
+            counters: None,
+            error: None,
+        }
+    }
+}
+
+fn read_config(path: &Path, options: &Options) -> Result<Config, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let antenna_list = options.antenna_list.unwrap_or(AntennaList::Numbers);
+    let config = config_from_json_with(&json, antenna_list).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    // A single entry such as [3] is antenna RX3 as a number, but three antennas as a count (as in the templates)
+    let counted = match options.antenna_list {
+        None => config_from_json_with(&json, AntennaList::Count).ok(),
+        Some(_) => None,
+    };
+    if let Some(counted) = counted.filter(|counted| (counted.rx_antennas, counted.tx_antennas) != (config.rx_antennas, config.tx_antennas)) {
+        eprintln!(
+            "warning: {}: the antenna lists are read as {} RX and {} TX antennas by their numbers, \
+             but hold {} RX and {} TX antennas as a count, pass --antennas count for the templates in config/",
+            path.display(),
+            config.rx_antennas,
+            config.tx_antennas,
+            counted.rx_antennas,
+            counted.tx_antennas
+        );
+    }
+
+    Ok(config)
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension().map(|extension| extension.to_string_lossy().to_lowercase())
+}
+
+fn open(path: &Path) -> Result<File, String> {
+    File::open(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn create(path: &Path) -> Result<BufWriter<File>, String> {
+    File::create(path).map(BufWriter::new).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn error(error: Error) -> String {
+    error.to_string()
+}
+
+fn io_error(error: std::io::Error) -> String {
+    error.to_string()
+}
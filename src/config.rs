@@ -1,7 +1,16 @@
+use crate::Variant;
+
+/// Speed of light in m/s, used to derive the physical units of the configuration.
+pub const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+
+/// Full scale of the 12-bit ADC, used to normalize samples to `0.0..=1.0`.
+pub const ADC_FULL_SCALE: f32 = 4095.0;
+
 /// The configuration of the BGT60TR13C radar sensor, mostly used for reference only.
 /// The actual configuration is done via the generated register list.
 ///
 /// The fields of the configuration match the fields of the JSON required for the bgt60-configurator-cli.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Config {
     pub rx_antennas: u8,
     pub tx_antennas: u8,
@@ -86,7 +95,7 @@ impl core::fmt::Display for Config {
             \t frame_repetition_time_hz: {:.2e},\n\
             \t frame_shape: {:?},\n\
             \t frame_buffer_size_u12: {},\n\
-            \t frame_buffer_size_u8: {} + 4\n\
+            \t frame_buffer_size_u8: {}\n\
             \t registers: {:?},\n\
             }}",
             self.rx_antennas,
@@ -166,7 +175,7 @@ impl Config {
         let fifo_limit = self.num_samples_per_chirp as usize
             * self.num_chirps_per_frame as usize
             * self.rx_antennas as usize;
-        ((fifo_limit * 12) / 8) + 4 // 4 bytes for the burst command / GSR0 + padding
+        (fifo_limit * 12) / 8
     }
 
     // The FIFO limit is the number of samples per chirp * number of chirps per frame * number of RX antennas
@@ -175,4 +184,49 @@ impl Config {
             * self.num_chirps_per_frame as usize
             * self.rx_antennas as usize
     }
+
+    /// The bandwidth of a chirp (upper - lower frequency), zero if the upper frequency is not above the lower one
+    pub fn bandwidth_hz(&self) -> u64 {
+        self.upper_frequency_hz.saturating_sub(self.lower_frequency_hz)
+    }
+
+    /// The center frequency of a chirp
+    pub fn center_frequency_hz(&self) -> u64 {
+        (self.lower_frequency_hz + self.upper_frequency_hz) / 2
+    }
+
+    /// The wavelength at the center frequency
+    pub fn wavelength_m(&self) -> f64 {
+        SPEED_OF_LIGHT / self.center_frequency_hz() as f64
+    }
+
+    /// The range resolution, which only depends on the bandwidth: c / (2 * B)
+    pub fn range_resolution_m(&self) -> f64 {
+        SPEED_OF_LIGHT / (2.0 * self.bandwidth_hz() as f64)
+    }
+
+    /// The maximum unambiguous range, limited by the real-valued sampling of the IF signal (half of the samples per chirp become range bins)
+    pub fn max_range_m(&self) -> f64 {
+        self.range_resolution_m() * self.num_samples_per_chirp as f64 / 2.0
+    }
+
+    /// The velocity resolution of a Doppler FFT over all chirps of a frame: lambda / (2 * num_chirps * T_chirp)
+    pub fn velocity_resolution_mps(&self) -> f64 {
+        self.wavelength_m() / (2.0 * self.num_chirps_per_frame as f64 * self.chirp_repetition_time_s)
+    }
+
+    /// The maximum unambiguous (positive or negative) radial velocity: lambda / (4 * T_chirp)
+    pub fn max_velocity_mps(&self) -> f64 {
+        self.wavelength_m() / (4.0 * self.chirp_repetition_time_s)
+    }
+
+    /// The average data rate of the FIFO, with two 12-bit ADC results packed into three bytes: frame size / T_frame
+    pub fn data_rate_bytes_per_s(&self) -> f64 {
+        self.get_u8_buffer_size() as f64 / self.frame_repetition_time_s
+    }
+
+    /// The fraction of the FIFO of the variant used by a single frame, which must not exceed 1.0 (see [`crate::Radar::configure()`])
+    pub fn fifo_usage(&self, variant: Variant) -> f64 {
+        (self.get_fifo_limit() as f64 / 2.0) / variant.fifo_size() as f64
+    }
 }
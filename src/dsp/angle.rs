@@ -0,0 +1,177 @@
+use core::f32::consts::PI;
+
+use libm::{asinf, cosf, sinf};
+use num_complex::Complex32;
+
+use super::{Detection, DopplerFft};
+use crate::config::Config;
+use crate::error::Error;
+
+/// The maximum number of RX antennas of the BGT60TRxx family.
+pub const MAX_RX_ANTENNAS: usize = 3;
+
+/// The distance between the phase centers of adjacent RX antennas of the BGT60TR13C, in meters.
+///
+/// The antennas are part of the package, so this is the same on every board: half a wavelength at 60 GHz.
+pub const BGT60TR13C_SPACING_M: f32 = 0.0025;
+
+/// A pair of RX antennas along one axis of the array, used to estimate the angle from their phase difference.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AntennaPair {
+    /// Index of the reference antenna (0 for RX1).
+    pub reference: usize,
+    /// Index of the other antenna, whose phase is compared to the reference.
+    pub other: usize,
+    /// Distance between the phase centers of the antennas, in wavelengths of the center frequency.
+    /// Angles are unambiguous for spacings of up to half a wavelength.
+    pub spacing_wavelengths: f32,
+    /// Inverts the sign of the angle, e.g. if the board mounts the chip rotated or mirrored.
+    pub invert: bool,
+}
+
+impl AntennaPair {
+    /// Estimates the angle from the complex values of the antennas (after calibration), in radians.
+    fn angle_rad(&self, values: &[Complex32]) -> f32 {
+        let phase = (values[self.other] * values[self.reference].conj()).arg();
+        let sine = (phase / (2.0 * PI * self.spacing_wavelengths)).clamp(-1.0, 1.0);
+        let angle = asinf(sine);
+
+        if self.invert { -angle } else { angle }
+    }
+}
+
+/// Geometry of the RX antenna array of a board, with per-antenna phase calibration.
+///
+/// The default is the L-shaped array of the BGT60TR13C, with RX1 and RX3 along the azimuth axis,
+/// and RX2 and RX3 along the elevation axis, all spaced half a wavelength apart.
+///
+/// # Geometry of a board
+/// Since the antennas are part of the package, all boards with the same chip (e.g. the KIT CSK BGT60TR13C radar wing
+/// and the BGT60TR13C SHIELD) share the spacing of [`BGT60TR13C_SPACING_M`]. They only differ in how the chip is mounted:
+/// - The spacing in wavelengths depends on the center frequency of the chirps, use [`AntennaGeometry::with_spacing_m()`]
+///   to derive it from a config instead of the nominal half wavelength.
+/// - If the chip is rotated by 90° relative to the intended horizontal axis of the board, use [`AntennaGeometry::rotated()`].
+/// - If the chip is mounted mirrored or rotated by 180°, set the `invert` flags of the affected pairs.
+///
+/// To check the orientation of a board, place a reflector to the right of and then above boresight,
+/// and compare the signs of the estimated angles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AntennaGeometry {
+    /// The antenna pair along the horizontal axis, if any.
+    pub azimuth: Option<AntennaPair>,
+    /// The antenna pair along the vertical axis, if any.
+    pub elevation: Option<AntennaPair>,
+    /// Phase offsets of the antennas in radians, which are subtracted from the measured phases, e.g. from a calibration with a corner reflector.
+    pub phase_offsets_rad: [f32; MAX_RX_ANTENNAS],
+}
+
+impl Default for AntennaGeometry {
+    fn default() -> Self {
+        Self::bgt60tr13c()
+    }
+}
+
+impl AntennaGeometry {
+    /// The L-shaped array of the BGT60TR13C: azimuth from RX1/RX3, elevation from RX2/RX3, with a spacing of half a wavelength.
+    pub fn bgt60tr13c() -> Self {
+        AntennaGeometry {
+            azimuth: Some(AntennaPair {
+                reference: 2,
+                other: 0,
+                spacing_wavelengths: 0.5,
+                invert: false,
+            }),
+            elevation: Some(AntennaPair {
+                reference: 2,
+                other: 1,
+                spacing_wavelengths: 0.5,
+                invert: false,
+            }),
+            phase_offsets_rad: [0.0; MAX_RX_ANTENNAS],
+        }
+    }
+
+    /// Sets the spacing of both antenna pairs from their distance in meters, in wavelengths of the center frequency of the config.
+    pub fn with_spacing_m(mut self, spacing_m: f32, config: &Config) -> Self {
+        let spacing_wavelengths = (spacing_m as f64 / config.wavelength_m()) as f32;
+        for pair in self.azimuth.iter_mut().chain(self.elevation.iter_mut()) {
+            pair.spacing_wavelengths = spacing_wavelengths;
+        }
+        self
+    }
+
+    /// Swaps the azimuth and elevation pairs, for boards that mount the chip rotated by 90°.
+    ///
+    /// Depending on the direction of the rotation, one of the angles needs to be inverted as well.
+    pub fn rotated(mut self) -> Self {
+        core::mem::swap(&mut self.azimuth, &mut self.elevation);
+        self
+    }
+
+    /// Sets the phase offsets of the antennas, in radians.
+    pub fn with_phase_offsets(mut self, phase_offsets_rad: [f32; MAX_RX_ANTENNAS]) -> Self {
+        self.phase_offsets_rad = phase_offsets_rad;
+        self
+    }
+
+    /// Estimates the angle of arrival from the complex values of a single range(-Doppler) cell of each antenna.
+    ///
+    /// The values must be ordered by antenna (RX1, RX2, ...), and contain all antennas used by the geometry.
+    pub fn estimate(&self, values: &[Complex32]) -> Result<AngleOfArrival, Error> {
+        if values.len() > MAX_RX_ANTENNAS {
+            return Err(Error::BufferWrongSize(values.len(), MAX_RX_ANTENNAS));
+        }
+        for pair in self.azimuth.iter().chain(self.elevation.iter()) {
+            if pair.reference >= values.len() || pair.other >= values.len() || pair.spacing_wavelengths <= 0.0 {
+                return Err(Error::InvalidParameter);
+            }
+        }
+
+        let mut calibrated = [Complex32::new(0.0, 0.0); MAX_RX_ANTENNAS];
+        for ((value, offset), result) in values.iter().zip(self.phase_offsets_rad).zip(calibrated.iter_mut()) {
+            *result = value * Complex32::new(cosf(offset), -sinf(offset));
+        }
+
+        Ok(AngleOfArrival {
+            azimuth_rad: self.azimuth.map(|pair| pair.angle_rad(&calibrated)),
+            elevation_rad: self.elevation.map(|pair| pair.angle_rad(&calibrated)),
+        })
+    }
+
+    /// Estimates the angle of arrival of a detection, from the complex range-Doppler maps of all antennas,
+    /// as written by [`DopplerFft::process_frame()`] with the shape `[rx_antennas, num_doppler_bins, num_range_bins]`.
+    pub fn estimate_detection(
+        &self,
+        maps: &[Complex32],
+        doppler_fft: &DopplerFft,
+        detection: &Detection,
+    ) -> Result<AngleOfArrival, Error> {
+        let map_size = doppler_fft.map_size();
+        let rx_antennas = maps.len() / map_size;
+        if rx_antennas == 0 || rx_antennas > MAX_RX_ANTENNAS || !maps.len().is_multiple_of(map_size) {
+            return Err(Error::BufferWrongSize(maps.len(), map_size));
+        }
+        if detection.range_bin >= doppler_fft.num_range_bins() || detection.doppler_bin >= doppler_fft.num_doppler_bins() {
+            return Err(Error::InvalidParameter);
+        }
+
+        let cell = detection.doppler_bin * doppler_fft.num_range_bins() + detection.range_bin;
+        let mut values = [Complex32::new(0.0, 0.0); MAX_RX_ANTENNAS];
+        for (rx, value) in values.iter_mut().enumerate().take(rx_antennas) {
+            *value = maps[rx * map_size + cell];
+        }
+
+        self.estimate(&values[..rx_antennas])
+    }
+}
+
+/// The estimated angle of arrival of a target, in radians from boresight.
+///
+/// The angles are derived from the phase of the `other` antenna relative to the `reference` antenna of each pair.
+/// Which direction is positive depends on the board layout, use the `invert` flags of the pairs to match it.
+/// An angle is `None` if the geometry has no antenna pair along its axis.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AngleOfArrival {
+    pub azimuth_rad: Option<f32>,
+    pub elevation_rad: Option<f32>,
+}
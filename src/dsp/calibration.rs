@@ -0,0 +1,244 @@
+//! Calibration of the phase and gain mismatch between the RX channels, using a corner reflector.
+//!
+//! Place a corner reflector at boresight (0° azimuth and elevation) in a known distance, with no other strong reflectors nearby,
+//! and feed frames to a [`Calibrator`], either manually or with [`Calibrator::capture()`].
+//! The resulting [`Calibration`] can be stored with [`Calibration::to_bytes()`], and is applied to frames with
+//! [`RangeFft::with_calibration()`] or to angle estimation with [`AntennaGeometry::with_calibration()`].
+//!
+//! The chip temperature at the time of calibration has to be provided by the caller. The chip measures its temperature with its sensor ADC,
+//! but the conversion of the result into °C is not part of the register description this driver is based on, so the driver cannot read it.
+//! Use the temperature reported by the Infineon Radar SDK for the same chip, or an external sensor next to it.
+
+use libm::{cosf, sinf, sqrtf};
+use num_complex::Complex32;
+
+use super::angle::{AntennaGeometry, MAX_RX_ANTENNAS};
+use super::{Frame, RangeFft};
+use crate::FrameSource;
+use crate::error::Error;
+
+/// Magic bytes at the start of a serialized calibration.
+const MAGIC: [u8; 4] = *b"BGTC";
+
+/// Version of the serialized calibration format.
+const VERSION: u8 = 1;
+
+/// Per-RX phase and gain offsets relative to RX1, measured with a corner reflector at boresight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    /// Phase of each antenna relative to RX1, in radians.
+    pub phase_offsets_rad: [f32; MAX_RX_ANTENNAS],
+    /// Amplitude of each antenna relative to RX1.
+    pub gains: [f32; MAX_RX_ANTENNAS],
+    /// Chip temperature at the time of calibration in °C, if known (see the [module documentation](self)).
+    /// The phase offsets drift with temperature, so this can be used to decide when to recalibrate.
+    pub temperature_c: Option<f32>,
+}
+
+impl Default for Calibration {
+    /// A calibration without any corrections.
+    fn default() -> Self {
+        Calibration {
+            phase_offsets_rad: [0.0; MAX_RX_ANTENNAS],
+            gains: [1.0; MAX_RX_ANTENNAS],
+            temperature_c: None,
+        }
+    }
+}
+
+impl Calibration {
+    /// The size of a serialized calibration, in bytes.
+    pub const SIZE: usize = MAGIC.len() + 1 + 4 * (2 * MAX_RX_ANTENNAS + 1);
+
+    /// The factor that corrects the phase and gain of an antenna: `exp(-j * phase_offset) / gain`
+    ///
+    /// Returns `None` if the antenna index is not below [`MAX_RX_ANTENNAS`].
+    pub fn correction(&self, rx: usize) -> Option<Complex32> {
+        let phase = *self.phase_offsets_rad.get(rx)?;
+        let gain = if self.gains[rx] > 0.0 { self.gains[rx] } else { 1.0 };
+        Some(Complex32::new(cosf(phase), -sinf(phase)) / gain)
+    }
+
+    /// Applies the correction to the complex values of a single cell of each antenna, ordered by antenna.
+    ///
+    /// Values beyond [`MAX_RX_ANTENNAS`] are left unchanged.
+    pub fn apply(&self, values: &mut [Complex32]) {
+        for (rx, value) in values.iter_mut().enumerate() {
+            if let Some(correction) = self.correction(rx) {
+                *value *= correction;
+            }
+        }
+    }
+
+    /// Serializes the calibration into a little-endian byte array, e.g. for storage in flash.
+    ///
+    /// Layout: magic `BGTC`, version (u8), phase offsets (f32 per antenna), gains (f32 per antenna), temperature (f32, NaN if unknown).
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[..4].copy_from_slice(&MAGIC);
+        bytes[4] = VERSION;
+
+        let temperature = self.temperature_c.unwrap_or(f32::NAN);
+        let values = self.phase_offsets_rad.iter().chain(self.gains.iter()).chain(core::iter::once(&temperature));
+        for (chunk, value) in bytes[5..].chunks_exact_mut(4).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    /// Deserializes a calibration written by [`Calibration::to_bytes()`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != Self::SIZE {
+            return Err(Error::BufferWrongSize(bytes.len(), Self::SIZE));
+        }
+        if bytes[..4] != MAGIC || bytes[4] != VERSION {
+            return Err(Error::InvalidParameter);
+        }
+
+        let mut values = [0.0; 2 * MAX_RX_ANTENNAS + 1];
+        for (value, chunk) in values.iter_mut().zip(bytes[5..].chunks_exact(4)) {
+            // Unwrap is safe, since the chunks have exactly 4 bytes
+            *value = f32::from_le_bytes(chunk.try_into().unwrap());
+        }
+
+        let mut calibration = Calibration::default();
+        calibration.phase_offsets_rad.copy_from_slice(&values[..MAX_RX_ANTENNAS]);
+        calibration.gains.copy_from_slice(&values[MAX_RX_ANTENNAS..2 * MAX_RX_ANTENNAS]);
+        let temperature = values[2 * MAX_RX_ANTENNAS];
+        calibration.temperature_c = if temperature.is_nan() { None } else { Some(temperature) };
+
+        Ok(calibration)
+    }
+}
+
+/// Estimates a [`Calibration`] from frames with a corner reflector at boresight.
+///
+/// For every chirp, the reflector is located as the strongest range bin of RX1 within the search window around its expected distance,
+/// and the complex values of all antennas at that bin are accumulated.
+#[derive(Debug, Clone)]
+pub struct Calibrator {
+    range_fft: RangeFft,
+    first_bin: usize,
+    last_bin: usize,
+    cross: [Complex32; MAX_RX_ANTENNAS],
+    power: [f32; MAX_RX_ANTENNAS],
+    num_chirps: usize,
+}
+
+impl Calibrator {
+    /// Creates a calibrator for a reflector at the given distance, searching for it within `±tolerance_m`.
+    ///
+    /// Any calibration of the range FFT is ignored, since the raw channels are measured.
+    pub fn new(range_fft: &RangeFft, reflector_range_m: f32, tolerance_m: f32) -> Result<Self, Error> {
+        if range_fft.rx_antennas() > MAX_RX_ANTENNAS {
+            return Err(Error::InvalidParameter);
+        }
+
+        let max_bin = range_fft.num_bins().checked_sub(1).ok_or(Error::InvalidParameter)?;
+        let resolution = range_fft.range_resolution_m();
+        let first_bin = ((reflector_range_m - tolerance_m) / resolution).max(0.0) as usize;
+        let last_bin = (((reflector_range_m + tolerance_m) / resolution) as usize).min(max_bin);
+        if reflector_range_m <= 0.0 || tolerance_m < 0.0 || first_bin > last_bin {
+            return Err(Error::InvalidParameter);
+        }
+
+        Ok(Calibrator {
+            range_fft: range_fft.clone().without_calibration(),
+            first_bin,
+            last_bin,
+            cross: [Complex32::new(0.0, 0.0); MAX_RX_ANTENNAS],
+            power: [0.0; MAX_RX_ANTENNAS],
+            num_chirps: 0,
+        })
+    }
+
+    /// Discards all accumulated frames.
+    pub fn reset(&mut self) {
+        self.cross = [Complex32::new(0.0, 0.0); MAX_RX_ANTENNAS];
+        self.power = [0.0; MAX_RX_ANTENNAS];
+        self.num_chirps = 0;
+    }
+
+    /// The number of chirps accumulated so far.
+    pub fn num_chirps(&self) -> usize {
+        self.num_chirps
+    }
+
+    /// Accumulates all chirps of a frame. The scratch buffer must have the length of the FFT of the range FFT.
+    pub fn add_frame(&mut self, frame: &Frame, scratch: &mut [Complex32]) -> Result<(), Error> {
+        for chirp in 0..frame.num_chirps() {
+            self.range_fft.process_chirp(frame, 0, chirp, scratch)?;
+
+            // Unwrap is safe, since the search window is never empty
+            let bin = (self.first_bin..=self.last_bin)
+                .max_by(|&a, &b| scratch[a].norm_sqr().total_cmp(&scratch[b].norm_sqr()))
+                .unwrap();
+            let reference = scratch[bin];
+
+            for rx in 0..frame.rx_antennas() {
+                let value = if rx == 0 {
+                    reference
+                } else {
+                    self.range_fft.process_chirp(frame, rx, chirp, scratch)?;
+                    scratch[bin]
+                };
+                self.cross[rx] += value * reference.conj();
+                self.power[rx] += value.norm_sqr();
+            }
+            self.num_chirps += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the calibration estimated from all accumulated frames, with the chip temperature measured during the capture, if known
+    /// (see the [module documentation](self)).
+    pub fn calibration(&self, temperature_c: Option<f32>) -> Result<Calibration, Error> {
+        if self.num_chirps == 0 || self.power[0] <= 0.0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let mut calibration = Calibration {
+            temperature_c,
+            ..Calibration::default()
+        };
+        for rx in 0..self.range_fft.rx_antennas() {
+            calibration.phase_offsets_rad[rx] = self.cross[rx].arg();
+            calibration.gains[rx] = sqrtf(self.power[rx] / self.power[0]);
+        }
+
+        Ok(calibration)
+    }
+
+    /// Captures the given number of frames from a running radar, or any other [`FrameSource`] such as a replayed recording,
+    /// and returns the estimated calibration.
+    ///
+    /// The buffers are the same as for [`FrameSource::get_fifo_data()`], the scratch buffer must have the length of the FFT of the range FFT.
+    pub async fn capture(
+        &mut self,
+        radar: &mut impl FrameSource,
+        num_frames: usize,
+        buffer: &mut [u8],
+        output: &mut [u16],
+        scratch: &mut [Complex32],
+        temperature_c: Option<f32>,
+    ) -> Result<Calibration, Error> {
+        for _ in 0..num_frames {
+            radar.get_fifo_data(buffer, output).await?;
+            let config = radar.config().ok_or(Error::NoConfigSet)?;
+            self.add_frame(&Frame::new(output, config)?, scratch)?;
+        }
+
+        self.calibration(temperature_c)
+    }
+}
+
+impl AntennaGeometry {
+    /// Uses the phase offsets of a calibration for angle estimation.
+    ///
+    /// Don't use this if the calibration is already applied by [`RangeFft::with_calibration()`], since it would be corrected twice.
+    pub fn with_calibration(self, calibration: &Calibration) -> Self {
+        self.with_phase_offsets(calibration.phase_offsets_rad)
+    }
+}
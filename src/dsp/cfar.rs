@@ -0,0 +1,295 @@
+use libm::{log10f, powf};
+
+use super::{DopplerFft, RangeFft};
+use crate::error::Error;
+
+/// The method used to estimate the noise level from the training cells.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CfarKind {
+    /// Cell-averaging CFAR, using the mean of the training cells.
+    CellAveraging,
+    /// Ordered-statistic CFAR, using the training cell at the given rank (as a fraction of the sorted training cells, e.g. `0.75`).
+    /// More robust than cell-averaging when multiple targets are close to each other.
+    OrderedStatistic { rank: f32 },
+}
+
+/// A target detected by a [`Cfar`] detector.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Detection {
+    pub range_bin: usize,
+    /// The Doppler bin of the detection, always 0 for detections on a range profile.
+    pub doppler_bin: usize,
+    pub range_m: f32,
+    /// The radial velocity of the detection, always 0.0 for detections on a range profile.
+    pub velocity_mps: f32,
+    /// The power of the cell over the estimated noise power, in dB.
+    pub snr_db: f32,
+}
+
+/// Constant false-alarm rate (CFAR) detector for range profiles (1D) and range-Doppler maps (2D).
+///
+/// The detector expects magnitudes (as returned by [`super::magnitude()`] or [`super::doppler::non_coherent_sum()`]),
+/// which are squared internally, since the thresholds are derived for a square-law detector.
+///
+/// For each cell under test, the noise power is estimated from the training cells on both sides, skipping the guard cells next to the cell under test.
+/// A detection is reported if the power of the cell exceeds the noise power scaled with a factor derived from the false-alarm rate.
+///
+/// At the edges of the range axis, the window is shifted inwards so that every cell uses the same number of training cells.
+/// The Doppler axis of range-Doppler maps wraps around, since Doppler frequencies are periodic.
+#[derive(Debug, Clone)]
+pub struct Cfar {
+    kind: CfarKind,
+    guard_cells: usize,
+    training_cells: usize,
+    doppler_guard_cells: usize,
+    doppler_training_cells: usize,
+    false_alarm_rate: f32,
+}
+
+impl Cfar {
+    /// Creates a CFAR detector with the given number of guard and training cells on each side of the cell under test.
+    ///
+    /// For range-Doppler maps, the same number of cells is used along the Doppler axis, unless set with [`Cfar::with_doppler_cells()`].
+    pub fn new(
+        kind: CfarKind,
+        guard_cells: usize,
+        training_cells: usize,
+        false_alarm_rate: f32,
+    ) -> Result<Self, Error> {
+        if training_cells == 0 || !(false_alarm_rate > 0.0 && false_alarm_rate < 1.0) {
+            return Err(Error::InvalidParameter);
+        }
+        if let CfarKind::OrderedStatistic { rank } = kind
+            && !(rank > 0.0 && rank <= 1.0)
+        {
+            return Err(Error::InvalidParameter);
+        }
+
+        Ok(Cfar {
+            kind,
+            guard_cells,
+            training_cells,
+            doppler_guard_cells: guard_cells,
+            doppler_training_cells: training_cells,
+            false_alarm_rate,
+        })
+    }
+
+    /// Sets the number of guard and training cells on each side of the cell under test along the Doppler axis.
+    pub fn with_doppler_cells(mut self, guard_cells: usize, training_cells: usize) -> Self {
+        self.doppler_guard_cells = guard_cells;
+        self.doppler_training_cells = training_cells;
+        self
+    }
+
+    /// The size of the scratch buffer required for [`Cfar::detect_1d()`]: the number of training cells on both sides of the cell under test.
+    pub fn scratch_size_1d(&self) -> usize {
+        2 * self.training_cells
+    }
+
+    /// The size of the scratch buffer required for [`Cfar::detect_2d()`]: the number of training cells in the window around the cell under test,
+    /// i.e. the window of guard and training cells along both axes without the guard cells.
+    pub fn scratch_size_2d(&self) -> usize {
+        let range_window = 2 * (self.guard_cells + self.training_cells) + 1;
+        let doppler_window = 2 * (self.doppler_guard_cells + self.doppler_training_cells) + 1;
+        range_window * doppler_window
+            - (2 * self.guard_cells + 1) * (2 * self.doppler_guard_cells + 1)
+    }
+
+    /// Detects targets in a range profile (the magnitude of a range FFT), e.g. of a single chirp or averaged over all chirps of a frame.
+    ///
+    /// The scratch buffer must have the size returned by [`Cfar::scratch_size_1d()`].
+    /// Returns the number of detections written to the output, which is truncated if the output is full.
+    pub fn detect_1d(
+        &self,
+        profile: &[f32],
+        range_fft: &RangeFft,
+        scratch: &mut [f32],
+        detections: &mut [Detection],
+    ) -> Result<usize, Error> {
+        let num_bins = profile.len();
+        let window = 2 * (self.guard_cells + self.training_cells) + 1;
+        let num_training = self.scratch_size_1d();
+
+        if num_bins < window {
+            return Err(Error::BufferWrongSize(num_bins, window));
+        }
+        if scratch.len() != num_training {
+            return Err(Error::BufferWrongSize(scratch.len(), num_training));
+        }
+
+        let scale = self.scale(num_training);
+        let mut count = 0;
+
+        for cell in 0..num_bins {
+            if count == detections.len() {
+                break;
+            }
+
+            let start = window_start(cell, self.guard_cells + self.training_cells, num_bins);
+            let training = (start..start + window)
+                .filter(|&bin| bin.abs_diff(cell) > self.guard_cells)
+                .take(num_training);
+            for (value, bin) in scratch.iter_mut().zip(training) {
+                *value = profile[bin] * profile[bin];
+            }
+
+            let power = profile[cell] * profile[cell];
+            let noise = self.noise(scratch);
+
+            if power > scale * noise {
+                detections[count] = Detection {
+                    range_bin: cell,
+                    doppler_bin: 0,
+                    range_m: range_fft.bin_to_range_m(cell),
+                    velocity_mps: 0.0,
+                    snr_db: snr_db(power, noise),
+                };
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Detects targets in a range-Doppler map with the shape `[num_doppler_bins, num_range_bins]`,
+    /// e.g. the non-coherent sum of all antennas returned by [`DopplerFft::process_frame()`].
+    ///
+    /// The scratch buffer must have the size returned by [`Cfar::scratch_size_2d()`].
+    /// Returns the number of detections written to the output, which is truncated if the output is full.
+    pub fn detect_2d(
+        &self,
+        map: &[f32],
+        range_fft: &RangeFft,
+        doppler_fft: &DopplerFft,
+        scratch: &mut [f32],
+        detections: &mut [Detection],
+    ) -> Result<usize, Error> {
+        let num_range_bins = doppler_fft.num_range_bins();
+        let num_doppler_bins = doppler_fft.num_doppler_bins();
+        let range_window = 2 * (self.guard_cells + self.training_cells) + 1;
+        let doppler_reach = self.doppler_guard_cells + self.doppler_training_cells;
+        let num_training = self.scratch_size_2d();
+
+        if map.len() != doppler_fft.map_size() {
+            return Err(Error::BufferWrongSize(map.len(), doppler_fft.map_size()));
+        }
+        if num_range_bins < range_window || num_doppler_bins < 2 * doppler_reach + 1 {
+            return Err(Error::BufferWrongSize(map.len(), range_window * (2 * doppler_reach + 1)));
+        }
+        if scratch.len() != num_training {
+            return Err(Error::BufferWrongSize(scratch.len(), num_training));
+        }
+
+        let scale = self.scale(num_training);
+        let mut count = 0;
+
+        for doppler in 0..num_doppler_bins {
+            for range in 0..num_range_bins {
+                if count == detections.len() {
+                    return Ok(count);
+                }
+
+                let start = window_start(range, self.guard_cells + self.training_cells, num_range_bins);
+                let training = (0..2 * doppler_reach + 1)
+                    .flat_map(|row| (start..start + range_window).map(move |column| (row, column)))
+                    .filter(|&(row, column)| {
+                        row.abs_diff(doppler_reach) > self.doppler_guard_cells
+                            || column.abs_diff(range) > self.guard_cells
+                    })
+                    .take(num_training);
+
+                for (value, (row, column)) in scratch.iter_mut().zip(training) {
+                    // The Doppler axis wraps around
+                    let row = (doppler + num_doppler_bins + row - doppler_reach) % num_doppler_bins;
+                    let cell = map[row * num_range_bins + column];
+                    *value = cell * cell;
+                }
+
+                let cell = map[doppler * num_range_bins + range];
+                let power = cell * cell;
+                let noise = self.noise(scratch);
+
+                if power > scale * noise {
+                    detections[count] = Detection {
+                        range_bin: range,
+                        doppler_bin: doppler,
+                        range_m: range_fft.bin_to_range_m(range),
+                        velocity_mps: doppler_fft.bin_to_velocity_mps(doppler),
+                        snr_db: snr_db(power, noise),
+                    };
+                    count += 1;
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Estimates the noise power from the training cells, the order of the cells is changed for ordered-statistic CFAR.
+    fn noise(&self, training: &mut [f32]) -> f32 {
+        match self.kind {
+            CfarKind::CellAveraging => training.iter().sum::<f32>() / training.len() as f32,
+            CfarKind::OrderedStatistic { .. } => {
+                let index = self.rank(training.len()) - 1;
+                *training.select_nth_unstable_by(index, f32::total_cmp).1
+            }
+        }
+    }
+
+    /// The 1-based rank of the training cell used by ordered-statistic CFAR.
+    fn rank(&self, num_training: usize) -> usize {
+        match self.kind {
+            CfarKind::OrderedStatistic { rank } => {
+                ((rank * num_training as f32) as usize).clamp(1, num_training)
+            }
+            CfarKind::CellAveraging => num_training,
+        }
+    }
+
+    /// The threshold factor for the given number of training cells, so that the false-alarm rate is met for exponentially distributed noise power.
+    fn scale(&self, num_training: usize) -> f32 {
+        let n = num_training as f32;
+        match self.kind {
+            CfarKind::CellAveraging => n * (powf(self.false_alarm_rate, -1.0 / n) - 1.0),
+            CfarKind::OrderedStatistic { .. } => {
+                // The false-alarm rate of OS-CFAR is prod_{i=0}^{k-1} (N - i) / (N - i + alpha),
+                // which is monotonically decreasing in alpha, so we solve for alpha with a bisection.
+                let k = self.rank(num_training);
+                let false_alarm_rate = |alpha: f32| {
+                    (0..k)
+                        .map(|i| (n - i as f32) / (n - i as f32 + alpha))
+                        .product::<f32>()
+                };
+
+                let mut low = 0.0;
+                let mut high = 1.0;
+                while false_alarm_rate(high) > self.false_alarm_rate && high < 1e9 {
+                    high *= 2.0;
+                }
+                for _ in 0..64 {
+                    let mid = 0.5 * (low + high);
+                    if false_alarm_rate(mid) > self.false_alarm_rate {
+                        low = mid;
+                    } else {
+                        high = mid;
+                    }
+                }
+                high
+            }
+        }
+    }
+}
+
+/// The first bin of a window with the given reach on each side of the cell, shifted inwards at the edges.
+fn window_start(cell: usize, reach: usize, len: usize) -> usize {
+    cell.saturating_sub(reach).min(len - (2 * reach + 1))
+}
+
+fn snr_db(power: f32, noise: f32) -> f32 {
+    if noise > 0.0 {
+        10.0 * log10f(power / noise)
+    } else {
+        f32::INFINITY
+    }
+}
@@ -0,0 +1,188 @@
+//! Removal of static clutter (walls, furniture, ...) from range profiles, also known as moving target indication (MTI).
+//!
+//! All filters work on the output of [`RangeFft::process_frame()`], with the shape `[rx_antennas, num_chirps_per_frame, num_bins]`,
+//! and modify it in place. Filters working across frames keep their state in a caller-provided buffer,
+//! and need to be [reset](ExponentialMti::reset) whenever the radar is (re-)configured.
+
+use num_complex::Complex32;
+
+use super::RangeFft;
+use crate::error::Error;
+
+/// The shape of the range FFT output: `(rx_antennas, num_chirps_per_frame, num_bins)`
+fn range_shape(range_fft: &RangeFft) -> (usize, usize, usize) {
+    (range_fft.rx_antennas(), range_fft.num_chirps(), range_fft.num_bins())
+}
+
+/// Subtracts the mean over all chirps of a frame from each range bin, removing everything that does not move during the frame.
+///
+/// This needs at least two chirps per frame, with a single chirp the output is zero.
+pub fn remove_mean_chirp(range_fft: &RangeFft, range: &mut [Complex32]) -> Result<(), Error> {
+    let (_, num_chirps, num_bins) = range_shape(range_fft);
+    if range.len() != range_fft.output_size() {
+        return Err(Error::BufferWrongSize(range.len(), range_fft.output_size()));
+    }
+
+    for antenna in range.chunks_exact_mut(num_chirps * num_bins) {
+        for bin in 0..num_bins {
+            let mean = chirp_mean(antenna, num_chirps, num_bins, bin);
+            for chirp in 0..num_chirps {
+                antenna[chirp * num_bins + bin] -= mean;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The mean of a range bin over all chirps of a single antenna.
+fn chirp_mean(antenna: &[Complex32], num_chirps: usize, num_bins: usize, bin: usize) -> Complex32 {
+    (0..num_chirps)
+        .map(|chirp| antenna[chirp * num_bins + bin])
+        .sum::<Complex32>()
+        / num_chirps as f32
+}
+
+/// Exponential background subtraction across frames.
+///
+/// The background of each range bin and antenna is estimated as an exponential moving average of the mean over the chirps of each frame,
+/// `background = (1 - alpha) * background + alpha * mean`, and subtracted from every chirp before the update.
+/// Small values of `alpha` adapt slowly, so that even slowly moving targets are kept,
+/// while large values adapt quickly to changes of the scene (e.g. moved furniture).
+///
+/// The background is initialized with the first frame after creation or a reset.
+/// The buffer must hold at least [`ExponentialMti::state_size()`] values.
+#[derive(Debug)]
+pub struct ExponentialMti<'a> {
+    background: &'a mut [Complex32],
+    shape: (usize, usize, usize),
+    alpha: f32,
+    initialized: bool,
+}
+
+impl<'a> ExponentialMti<'a> {
+    pub fn new(range_fft: &RangeFft, alpha: f32, background: &'a mut [Complex32]) -> Result<Self, Error> {
+        if !(alpha > 0.0 && alpha <= 1.0) {
+            return Err(Error::InvalidParameter);
+        }
+
+        let mut mti = ExponentialMti {
+            background,
+            shape: (0, 0, 0),
+            alpha,
+            initialized: false,
+        };
+        mti.reset(range_fft)?;
+        Ok(mti)
+    }
+
+    /// The size of the state buffer required for the range FFT: `rx_antennas * num_bins`
+    pub fn state_size(range_fft: &RangeFft) -> usize {
+        range_fft.rx_antennas() * range_fft.num_bins()
+    }
+
+    /// Clears the background, and adapts the filter to the shape of the (new) range FFT.
+    pub fn reset(&mut self, range_fft: &RangeFft) -> Result<(), Error> {
+        let state_size = Self::state_size(range_fft);
+        if self.background.len() < state_size {
+            return Err(Error::BufferWrongSize(self.background.len(), state_size));
+        }
+
+        self.shape = range_shape(range_fft);
+        self.initialized = false;
+        Ok(())
+    }
+
+    /// Removes the background from the range FFT output of a frame, and updates the background.
+    pub fn process(&mut self, range: &mut [Complex32]) -> Result<(), Error> {
+        let (rx_antennas, num_chirps, num_bins) = self.shape;
+        let input_size = rx_antennas * num_chirps * num_bins;
+        if range.len() != input_size {
+            return Err(Error::BufferWrongSize(range.len(), input_size));
+        }
+
+        let background = &mut self.background[..rx_antennas * num_bins];
+        for (antenna, background) in range
+            .chunks_exact_mut(num_chirps * num_bins)
+            .zip(background.chunks_exact_mut(num_bins))
+        {
+            for (bin, background) in background.iter_mut().enumerate() {
+                let mean = chirp_mean(antenna, num_chirps, num_bins, bin);
+                if !self.initialized {
+                    *background = mean;
+                }
+
+                for chirp in 0..num_chirps {
+                    antenna[chirp * num_bins + bin] -= *background;
+                }
+                *background += (mean - *background) * self.alpha;
+            }
+        }
+
+        self.initialized = true;
+        Ok(())
+    }
+}
+
+/// Two-pulse canceller across frames, subtracting the previous frame from the current one (`y[n] = x[n] - x[n - 1]`).
+///
+/// Each chirp is compared with the same chirp of the previous frame, which removes everything that did not move between the frames.
+/// Since the canceller has a zero at DC and a gain of two at half the frame rate, it also emphasizes fast targets over slow ones.
+///
+/// The output of the first frame after creation or a reset is zero, since there is no previous frame.
+/// The buffer must hold at least [`TwoPulseCanceller::state_size()`] values.
+#[derive(Debug)]
+pub struct TwoPulseCanceller<'a> {
+    previous: &'a mut [Complex32],
+    size: usize,
+    initialized: bool,
+}
+
+impl<'a> TwoPulseCanceller<'a> {
+    pub fn new(range_fft: &RangeFft, previous: &'a mut [Complex32]) -> Result<Self, Error> {
+        let mut canceller = TwoPulseCanceller {
+            previous,
+            size: 0,
+            initialized: false,
+        };
+        canceller.reset(range_fft)?;
+        Ok(canceller)
+    }
+
+    /// The size of the state buffer required for the range FFT, which is the size of its output.
+    pub fn state_size(range_fft: &RangeFft) -> usize {
+        range_fft.output_size()
+    }
+
+    /// Forgets the previous frame, and adapts the filter to the shape of the (new) range FFT.
+    pub fn reset(&mut self, range_fft: &RangeFft) -> Result<(), Error> {
+        let state_size = Self::state_size(range_fft);
+        if self.previous.len() < state_size {
+            return Err(Error::BufferWrongSize(self.previous.len(), state_size));
+        }
+
+        self.size = state_size;
+        self.initialized = false;
+        Ok(())
+    }
+
+    /// Subtracts the previous frame from the range FFT output of a frame, and stores the frame for the next call.
+    pub fn process(&mut self, range: &mut [Complex32]) -> Result<(), Error> {
+        if range.len() != self.size {
+            return Err(Error::BufferWrongSize(range.len(), self.size));
+        }
+
+        for (value, previous) in range.iter_mut().zip(self.previous[..self.size].iter_mut()) {
+            let current = *value;
+            *value = if self.initialized {
+                current - *previous
+            } else {
+                Complex32::new(0.0, 0.0)
+            };
+            *previous = current;
+        }
+
+        self.initialized = true;
+        Ok(())
+    }
+}
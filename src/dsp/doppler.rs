@@ -0,0 +1,208 @@
+use num_complex::Complex32;
+
+#[cfg(feature = "alloc")]
+use ndarray::Array3;
+
+use super::fft::{fft, fft_shift};
+use super::window::WindowTable;
+use super::{RangeFft, Window};
+use crate::config::Config;
+use crate::error::Error;
+
+/// Computes range-Doppler maps from the output of a [`RangeFft`], by computing an FFT across the chirps of a frame for each range bin.
+///
+/// The maps are stored in row-major order with the shape `[num_doppler_bins, num_range_bins]`, with the zero velocity at `num_doppler_bins / 2`.
+/// Doppler bin `k` corresponds to a radial velocity of `(k - num_doppler_bins / 2) * velocity_resolution_mps()`,
+/// derived from the `chirp_repetition_time_s` and the center frequency of the config.
+///
+/// The window coefficients are computed once on creation (see [`WindowTable`]).
+#[derive(Debug, Clone)]
+pub struct DopplerFft {
+    window: WindowTable,
+    rx_antennas: usize,
+    num_chirps: usize,
+    num_range_bins: usize,
+    fft_len: usize,
+    velocity_resolution_mps: f32,
+}
+
+impl DopplerFft {
+    /// Creates a Doppler FFT without zero-padding (the FFT length is the number of chirps per frame, rounded up to the next power of two).
+    pub fn new(config: &Config, range_fft: &RangeFft, window: Window) -> Self {
+        let fft_len = (config.num_chirps_per_frame as usize).next_power_of_two();
+        // Unwrap is safe, since the FFT length is a power of two and at least the number of chirps
+        Self::with_fft_len(config, range_fft, window, fft_len).unwrap()
+    }
+
+    /// Creates a Doppler FFT with zero-padding to the given FFT length.
+    ///
+    /// The FFT length must be a power of two, and at least the number of chirps per frame.
+    pub fn with_fft_len(
+        config: &Config,
+        range_fft: &RangeFft,
+        window: Window,
+        fft_len: usize,
+    ) -> Result<Self, Error> {
+        let num_chirps = config.num_chirps_per_frame as usize;
+
+        if !fft_len.is_power_of_two() {
+            return Err(Error::NotAPowerOfTwo);
+        }
+        if fft_len < num_chirps {
+            return Err(Error::BufferWrongSize(fft_len, num_chirps));
+        }
+
+        let velocity_resolution_mps =
+            (config.velocity_resolution_mps() * num_chirps as f64 / fft_len as f64) as f32;
+
+        Ok(DopplerFft {
+            // Unwrap is safe, since the number of chirps per frame (at most 255) is below the maximum window length
+            window: WindowTable::new(window, num_chirps).unwrap(),
+            rx_antennas: config.rx_antennas as usize,
+            num_chirps,
+            num_range_bins: range_fft.num_bins(),
+            fft_len,
+            velocity_resolution_mps,
+        })
+    }
+
+    /// The length of the FFT, which is also the size of the scratch buffer required for processing.
+    pub fn fft_len(&self) -> usize {
+        self.fft_len
+    }
+
+    pub fn num_doppler_bins(&self) -> usize {
+        self.fft_len
+    }
+
+    pub fn num_range_bins(&self) -> usize {
+        self.num_range_bins
+    }
+
+    /// The size of a single range-Doppler map: `num_doppler_bins * num_range_bins`
+    pub fn map_size(&self) -> usize {
+        self.fft_len * self.num_range_bins
+    }
+
+    /// The velocity covered by a single Doppler bin, in m/s.
+    pub fn velocity_resolution_mps(&self) -> f32 {
+        self.velocity_resolution_mps
+    }
+
+    /// The radial velocity of a Doppler bin, in m/s.
+    pub fn bin_to_velocity_mps(&self, bin: usize) -> f32 {
+        (bin as f32 - (self.fft_len / 2) as f32) * self.velocity_resolution_mps
+    }
+
+    /// Returns the labels of all Doppler bins, in m/s.
+    pub fn velocity_bins(&self) -> impl Iterator<Item = f32> + '_ {
+        (0..self.fft_len).map(|bin| self.bin_to_velocity_mps(bin))
+    }
+
+    /// Computes the complex range-Doppler map of a single antenna.
+    ///
+    /// The input is the range FFT output of one antenna, with the shape `[num_chirps_per_frame, num_range_bins]`.
+    /// The scratch buffer must have the length of the FFT, the output must have the size of a map (see [`DopplerFft::map_size()`]).
+    pub fn process_antenna(
+        &self,
+        range: &[Complex32],
+        scratch: &mut [Complex32],
+        output: &mut [Complex32],
+    ) -> Result<(), Error> {
+        let input_size = self.num_chirps * self.num_range_bins;
+        if range.len() != input_size {
+            return Err(Error::BufferWrongSize(range.len(), input_size));
+        }
+        if scratch.len() != self.fft_len {
+            return Err(Error::BufferWrongSize(scratch.len(), self.fft_len));
+        }
+        if output.len() != self.map_size() {
+            return Err(Error::OutputWrongSize(output.len(), self.map_size()));
+        }
+
+        for bin in 0..self.num_range_bins {
+            for ((chirp, coefficient), value) in self.window.coefficients().iter().enumerate().zip(scratch.iter_mut()) {
+                *value = range[chirp * self.num_range_bins + bin] * *coefficient;
+            }
+            scratch[self.num_chirps..].fill(Complex32::new(0.0, 0.0));
+
+            fft(scratch)?;
+            fft_shift(scratch);
+
+            for (doppler, value) in scratch.iter().enumerate() {
+                output[doppler * self.num_range_bins + bin] = *value;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Computes the complex range-Doppler maps of all antennas, and the non-coherent sum of their magnitudes.
+    ///
+    /// The input is the output of [`RangeFft::process_frame()`], with the shape `[rx_antennas, num_chirps_per_frame, num_range_bins]`.
+    /// The maps are written with the shape `[rx_antennas, num_doppler_bins, num_range_bins]`,
+    /// and the magnitude sum with the shape `[num_doppler_bins, num_range_bins]`.
+    pub fn process_frame(
+        &self,
+        range: &[Complex32],
+        scratch: &mut [Complex32],
+        maps: &mut [Complex32],
+        magnitude_sum: &mut [f32],
+    ) -> Result<(), Error> {
+        let input_size = self.rx_antennas * self.num_chirps * self.num_range_bins;
+        if range.len() != input_size {
+            return Err(Error::BufferWrongSize(range.len(), input_size));
+        }
+        if maps.len() != self.rx_antennas * self.map_size() {
+            return Err(Error::OutputWrongSize(maps.len(), self.rx_antennas * self.map_size()));
+        }
+
+        let antenna_size = self.num_chirps * self.num_range_bins;
+        for (input, output) in range
+            .chunks_exact(antenna_size)
+            .zip(maps.chunks_exact_mut(self.map_size()))
+        {
+            self.process_antenna(input, scratch, output)?;
+        }
+
+        non_coherent_sum(maps, self.map_size(), magnitude_sum)
+    }
+
+    /// Computes the complex range-Doppler maps of the output of [`RangeFft::process_frames()`],
+    /// returning an array with the shape `[rx_antennas, num_doppler_bins, num_range_bins]`.
+    ///
+    /// This function requires the alloc feature, since it dynamically allocates memory for the output.
+    #[cfg(feature = "alloc")]
+    pub fn process_range_profiles(&self, range: &Array3<Complex32>) -> Result<Array3<Complex32>, Error> {
+        if range.dim() != (self.rx_antennas, self.num_chirps, self.num_range_bins) {
+            return Err(Error::ShapeMismatch);
+        }
+
+        let mut scratch = alloc::vec![Complex32::new(0.0, 0.0); self.fft_len];
+        let mut maps = Array3::from_elem((self.rx_antennas, self.fft_len, self.num_range_bins), Complex32::new(0.0, 0.0));
+
+        for (input, mut output) in range.outer_iter().zip(maps.outer_iter_mut()) {
+            // Unwrap is safe, since the input is copied into standard layout if needed, and the maps are created in standard layout
+            let input = input.as_standard_layout();
+            self.process_antenna(input.as_slice().unwrap(), &mut scratch, output.as_slice_mut().unwrap())?;
+        }
+
+        Ok(maps)
+    }
+}
+
+/// Computes the non-coherent sum of the magnitudes of multiple maps of the same size (e.g. the range-Doppler maps of all antennas).
+pub fn non_coherent_sum(maps: &[Complex32], map_size: usize, output: &mut [f32]) -> Result<(), Error> {
+    if output.len() != map_size {
+        return Err(Error::OutputWrongSize(output.len(), map_size));
+    }
+
+    output.fill(0.0);
+    for map in maps.chunks_exact(map_size) {
+        for (value, sum) in map.iter().zip(output.iter_mut()) {
+            *sum += value.norm();
+        }
+    }
+
+    Ok(())
+}
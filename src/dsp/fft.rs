@@ -0,0 +1,57 @@
+use core::f32::consts::PI;
+
+use libm::{cosf, sinf};
+use num_complex::Complex32;
+
+use crate::error::Error;
+
+/// Computes an in-place, radix-2 FFT (decimation in time).
+///
+/// The length of the buffer must be a power of two.
+pub fn fft(buffer: &mut [Complex32]) -> Result<(), Error> {
+    let n = buffer.len();
+    if !n.is_power_of_two() {
+        return Err(Error::NotAPowerOfTwo);
+    }
+    if n == 1 {
+        return Ok(());
+    }
+
+    // Reorder the input into bit-reversed order
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if j > i {
+            buffer.swap(i, j);
+        }
+    }
+
+    // Butterflies, the twiddle factors are computed by recurrence within each stage
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        let angle = -2.0 * PI / len as f32;
+        let w_len = Complex32::new(cosf(angle), sinf(angle));
+
+        for start in (0..n).step_by(len) {
+            let mut w = Complex32::new(1.0, 0.0);
+            for k in 0..half {
+                let a = buffer[start + k];
+                let b = buffer[start + k + half] * w;
+                buffer[start + k] = a + b;
+                buffer[start + k + half] = a - b;
+                w *= w_len;
+            }
+        }
+
+        len <<= 1;
+    }
+
+    Ok(())
+}
+
+/// Swaps the two halves of a spectrum, so that the zero frequency is moved to the center (at index `len / 2`).
+pub fn fft_shift<T>(buffer: &mut [T]) {
+    let len = buffer.len();
+    buffer.rotate_left(len / 2);
+}
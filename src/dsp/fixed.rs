@@ -0,0 +1,425 @@
+//! Fixed-point (Q15 / Q31) versions of the windowing, range FFT and Doppler FFT, for MCUs without a hardware FPU.
+//!
+//! ### Number formats
+//! - Q15: `i16` values representing `value / 32768` in the range `-1.0..1.0`
+//! - Q31: `u32` power values (`re² + im²` of Q15 values) representing `value / 2^31`, saturating at `u32::MAX`
+//!
+//! ### Scaling
+//! - The 12-bit ADC samples are converted to Q15 by removing the mean of the chirp and shifting left by 3 bits,
+//!   so that a full-scale sample swing maps to (almost) the full Q15 range.
+//! - Every stage of the FFT halves its results to prevent overflows, so an FFT of length `N` computes `DFT(x) / N`.
+//!
+//! As a result, the output of [`RangeFftQ15`] (interpreted as a fraction) matches the output of [`super::RangeFft`] divided by the FFT length,
+//! and the output of [`DopplerFftQ15`] matches the output of [`super::DopplerFft`] divided by both FFT lengths (within 0.03%, plus rounding errors).
+//!
+//! No floating-point operations are performed during processing. The twiddle factors are stored in a table that is computed at compile time,
+//! and only the window coefficients are computed once in floating-point (see [`window_q15()`]).
+
+use num_complex::Complex;
+
+use super::Window;
+use super::fft::fft_shift;
+use crate::config::Config;
+use crate::error::Error;
+
+/// A complex value with Q15 real and imaginary parts.
+pub type ComplexQ15 = Complex<i16>;
+
+/// The maximum supported FFT length.
+pub const MAX_FFT_LEN: usize = 1024;
+
+/// A quarter of a sine period in Q15, with `MAX_FFT_LEN / 4 + 1` entries: `sin(2 * pi * k / MAX_FFT_LEN)`
+const SINE_TABLE_Q15: [i16; MAX_FFT_LEN / 4 + 1] = sine_table();
+
+const fn sine_table() -> [i16; MAX_FFT_LEN / 4 + 1] {
+    let mut table = [0i16; MAX_FFT_LEN / 4 + 1];
+    let mut k = 0;
+    while k < table.len() {
+        let x = 2.0 * core::f64::consts::PI * k as f64 / MAX_FFT_LEN as f64;
+
+        // Taylor series of the sine, which is accurate to well below one Q15 LSB within 0..=pi/2
+        let mut term = x;
+        let mut sum = x;
+        let mut n = 1;
+        while n < 12 {
+            term *= -x * x / ((2 * n) * (2 * n + 1)) as f64;
+            sum += term;
+            n += 1;
+        }
+
+        let value = sum * 32767.0 + 0.5;
+        table[k] = value as i16;
+        k += 1;
+    }
+    table
+}
+
+/// Returns `(cos, sin)` of `2 * pi * k / MAX_FFT_LEN` in Q15, for `k` in `0..MAX_FFT_LEN / 2`.
+fn twiddle(k: usize) -> (i16, i16) {
+    let quarter = MAX_FFT_LEN / 4;
+    if k <= quarter {
+        (SINE_TABLE_Q15[quarter - k], SINE_TABLE_Q15[k])
+    } else {
+        (-SINE_TABLE_Q15[k - quarter], SINE_TABLE_Q15[2 * quarter - k])
+    }
+}
+
+/// Multiplies two Q15 values, rounding to nearest.
+fn mul_q15(a: i16, b: i16) -> i32 {
+    (a as i32 * b as i32 + (1 << 14)) >> 15
+}
+
+/// Computes the Q15 coefficients of a window with the length of the output.
+///
+/// This uses floating-point math, and is meant to be called once during initialization.
+pub fn window_q15(window: Window, output: &mut [i16]) {
+    let len = output.len();
+    for (n, coefficient) in output.iter_mut().enumerate() {
+        let value = window.coefficient(n, len) * 32767.0 + 0.5;
+        *coefficient = value as i16;
+    }
+}
+
+/// Computes an in-place, radix-2 FFT (decimation in time) in Q15, scaled by `1 / N`.
+///
+/// The length of the buffer must be a power of two, and must not exceed [`MAX_FFT_LEN`].
+/// Returns [`Error::InvalidParameter`] if the length exceeds [`MAX_FFT_LEN`].
+pub fn fft_q15(buffer: &mut [ComplexQ15]) -> Result<(), Error> {
+    let n = buffer.len();
+    if !n.is_power_of_two() {
+        return Err(Error::NotAPowerOfTwo);
+    }
+    if n > MAX_FFT_LEN {
+        return Err(Error::InvalidParameter);
+    }
+    if n == 1 {
+        return Ok(());
+    }
+
+    // Reorder the input into bit-reversed order
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if j > i {
+            buffer.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        let step = MAX_FFT_LEN / len;
+
+        for start in (0..n).step_by(len) {
+            for k in 0..half {
+                // w = cos - j * sin
+                let (cos, sin) = twiddle(k * step);
+                let a = buffer[start + k];
+                let b = buffer[start + k + half];
+
+                let t_re = mul_q15(b.re, cos) + mul_q15(b.im, sin);
+                let t_im = mul_q15(b.im, cos) - mul_q15(b.re, sin);
+
+                // Halve the results to prevent overflows
+                buffer[start + k] = ComplexQ15::new(
+                    ((a.re as i32 + t_re) >> 1) as i16,
+                    ((a.im as i32 + t_im) >> 1) as i16,
+                );
+                buffer[start + k + half] = ComplexQ15::new(
+                    ((a.re as i32 - t_re) >> 1) as i16,
+                    ((a.im as i32 - t_im) >> 1) as i16,
+                );
+            }
+        }
+
+        len <<= 1;
+    }
+
+    Ok(())
+}
+
+/// Computes the power (`re² + im²`) of each Q15 value in Q31.
+pub fn power_q31(input: &[ComplexQ15], output: &mut [u32]) -> Result<(), Error> {
+    if input.len() != output.len() {
+        return Err(Error::OutputWrongSize(output.len(), input.len()));
+    }
+
+    for (value, result) in input.iter().zip(output.iter_mut()) {
+        *result = power(value);
+    }
+
+    Ok(())
+}
+
+/// The power of a Q15 value in Q31, the product of two Q15 values is in Q30.
+fn power(value: &ComplexQ15) -> u32 {
+    let power = (value.re as i32 * value.re as i32) as u32 + (value.im as i32 * value.im as i32) as u32;
+    power.saturating_mul(2)
+}
+
+/// Fixed-point version of [`super::RangeFft`], working directly on the unpacked 12-bit samples written by [`crate::Radar::get_fifo_data()`].
+///
+/// See the [module documentation](self) for the scaling of the output.
+#[derive(Debug)]
+pub struct RangeFftQ15<'a> {
+    window: &'a [i16],
+    shape: (usize, usize, usize),
+    fft_len: usize,
+}
+
+impl<'a> RangeFftQ15<'a> {
+    /// Creates a fixed-point range FFT with the given FFT length.
+    ///
+    /// The window must have the length of the number of samples per chirp (see [`window_q15()`]),
+    /// and the FFT length must be a power of two between the number of samples per chirp and [`MAX_FFT_LEN`].
+    /// Returns [`Error::InvalidParameter`] if the FFT length exceeds [`MAX_FFT_LEN`].
+    pub fn new(config: &Config, window: &'a [i16], fft_len: usize) -> Result<Self, Error> {
+        let num_samples = config.num_samples_per_chirp as usize;
+
+        if window.len() != num_samples {
+            return Err(Error::BufferWrongSize(window.len(), num_samples));
+        }
+        if !fft_len.is_power_of_two() {
+            return Err(Error::NotAPowerOfTwo);
+        }
+        if fft_len < num_samples {
+            return Err(Error::BufferWrongSize(fft_len, num_samples));
+        }
+        if fft_len > MAX_FFT_LEN {
+            return Err(Error::InvalidParameter);
+        }
+
+        Ok(RangeFftQ15 {
+            window,
+            shape: (
+                config.rx_antennas as usize,
+                config.num_chirps_per_frame as usize,
+                num_samples,
+            ),
+            fft_len,
+        })
+    }
+
+    /// The length of the FFT, which is also the size of the scratch buffer required for processing.
+    pub fn fft_len(&self) -> usize {
+        self.fft_len
+    }
+
+    /// The number of range bins per chirp (half of the FFT length).
+    pub fn num_bins(&self) -> usize {
+        self.fft_len / 2
+    }
+
+    /// The size of the output of [`RangeFftQ15::process_frame()`]: `rx_antennas * num_chirps_per_frame * num_bins`
+    pub fn output_size(&self) -> usize {
+        self.shape.0 * self.shape.1 * self.num_bins()
+    }
+
+    /// Computes the range FFT of a single chirp of one antenna, from the raw FIFO data of a frame.
+    ///
+    /// The buffer must have the length of the FFT, the range bins are written to `buffer[..num_bins]`.
+    pub fn process_chirp(
+        &self,
+        samples: &[u16],
+        rx: usize,
+        chirp: usize,
+        buffer: &mut [ComplexQ15],
+    ) -> Result<(), Error> {
+        let (rx_antennas, num_chirps, num_samples) = self.shape;
+        let frame_size = rx_antennas * num_chirps * num_samples;
+
+        if samples.len() != frame_size {
+            return Err(Error::BufferWrongSize(samples.len(), frame_size));
+        }
+        if buffer.len() != self.fft_len {
+            return Err(Error::BufferWrongSize(buffer.len(), self.fft_len));
+        }
+
+        // The samples of all antennas are interleaved, see Radar::get_frames()
+        let offset = chirp * rx_antennas * num_samples + rx;
+        let chirp_samples = || (0..num_samples).map(|n| samples[offset + n * rx_antennas] as i32);
+
+        let mean = chirp_samples().sum::<i32>() / num_samples as i32;
+
+        for ((sample, coefficient), value) in chirp_samples().zip(self.window).zip(buffer.iter_mut()) {
+            // 12-bit to Q15, the difference to the mean is at most 4095, so the shift cannot overflow
+            let x = ((sample - mean) << 3) as i16;
+            *value = ComplexQ15::new(mul_q15(x, *coefficient) as i16, 0);
+        }
+        buffer[num_samples..].fill(ComplexQ15::new(0, 0));
+
+        fft_q15(buffer)
+    }
+
+    /// Computes the range FFT of all chirps of all antennas of a frame, from the raw FIFO data.
+    ///
+    /// The scratch buffer must have the length of the FFT.
+    /// The output has the shape `[rx_antennas, num_chirps_per_frame, num_bins]` in row-major order.
+    pub fn process_frame(
+        &self,
+        samples: &[u16],
+        scratch: &mut [ComplexQ15],
+        output: &mut [ComplexQ15],
+    ) -> Result<(), Error> {
+        let output_size = self.output_size();
+        if output.len() != output_size {
+            return Err(Error::OutputWrongSize(output.len(), output_size));
+        }
+
+        let num_bins = self.num_bins();
+        let mut chunks = output.chunks_exact_mut(num_bins);
+        for rx in 0..self.shape.0 {
+            for chirp in 0..self.shape.1 {
+                self.process_chirp(samples, rx, chirp, scratch)?;
+                // Unwrap is safe, since the output size has been checked
+                chunks.next().unwrap().copy_from_slice(&scratch[..num_bins]);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Fixed-point version of [`super::DopplerFft`], computing range-Doppler maps from the output of [`RangeFftQ15`].
+///
+/// The maps have the shape `[num_doppler_bins, num_range_bins]`, with the zero velocity at `num_doppler_bins / 2`.
+/// See the [module documentation](self) for the scaling of the output.
+#[derive(Debug)]
+pub struct DopplerFftQ15<'a> {
+    window: &'a [i16],
+    rx_antennas: usize,
+    num_chirps: usize,
+    num_range_bins: usize,
+    fft_len: usize,
+}
+
+impl<'a> DopplerFftQ15<'a> {
+    /// Creates a fixed-point Doppler FFT with the given FFT length.
+    ///
+    /// The window must have the length of the number of chirps per frame (see [`window_q15()`]),
+    /// and the FFT length must be a power of two between the number of chirps per frame and [`MAX_FFT_LEN`].
+    /// Returns [`Error::InvalidParameter`] if the FFT length exceeds [`MAX_FFT_LEN`].
+    pub fn new(
+        config: &Config,
+        range_fft: &RangeFftQ15,
+        window: &'a [i16],
+        fft_len: usize,
+    ) -> Result<Self, Error> {
+        let num_chirps = config.num_chirps_per_frame as usize;
+
+        if window.len() != num_chirps {
+            return Err(Error::BufferWrongSize(window.len(), num_chirps));
+        }
+        if !fft_len.is_power_of_two() {
+            return Err(Error::NotAPowerOfTwo);
+        }
+        if fft_len < num_chirps {
+            return Err(Error::BufferWrongSize(fft_len, num_chirps));
+        }
+        if fft_len > MAX_FFT_LEN {
+            return Err(Error::InvalidParameter);
+        }
+
+        Ok(DopplerFftQ15 {
+            window,
+            rx_antennas: config.rx_antennas as usize,
+            num_chirps,
+            num_range_bins: range_fft.num_bins(),
+            fft_len,
+        })
+    }
+
+    /// The length of the FFT, which is also the size of the scratch buffer required for processing.
+    pub fn fft_len(&self) -> usize {
+        self.fft_len
+    }
+
+    /// The size of a single range-Doppler map: `num_doppler_bins * num_range_bins`
+    pub fn map_size(&self) -> usize {
+        self.fft_len * self.num_range_bins
+    }
+
+    /// Computes the range-Doppler map of a single antenna.
+    ///
+    /// The input is the range FFT output of one antenna, with the shape `[num_chirps_per_frame, num_range_bins]`.
+    /// The scratch buffer must have the length of the FFT, the output must have the size of a map.
+    pub fn process_antenna(
+        &self,
+        range: &[ComplexQ15],
+        scratch: &mut [ComplexQ15],
+        output: &mut [ComplexQ15],
+    ) -> Result<(), Error> {
+        let input_size = self.num_chirps * self.num_range_bins;
+        if range.len() != input_size {
+            return Err(Error::BufferWrongSize(range.len(), input_size));
+        }
+        if scratch.len() != self.fft_len {
+            return Err(Error::BufferWrongSize(scratch.len(), self.fft_len));
+        }
+        if output.len() != self.map_size() {
+            return Err(Error::OutputWrongSize(output.len(), self.map_size()));
+        }
+
+        for bin in 0..self.num_range_bins {
+            for (chirp, value) in scratch.iter_mut().enumerate() {
+                *value = if chirp < self.num_chirps {
+                    let x = range[chirp * self.num_range_bins + bin];
+                    let w = self.window[chirp];
+                    ComplexQ15::new(mul_q15(x.re, w) as i16, mul_q15(x.im, w) as i16)
+                } else {
+                    ComplexQ15::new(0, 0)
+                };
+            }
+
+            fft_q15(scratch)?;
+            fft_shift(scratch);
+
+            for (doppler, value) in scratch.iter().enumerate() {
+                output[doppler * self.num_range_bins + bin] = *value;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Computes the range-Doppler maps of all antennas, and the sum of their power in Q31 (saturating).
+    ///
+    /// The input is the output of [`RangeFftQ15::process_frame()`], with the shape `[rx_antennas, num_chirps_per_frame, num_range_bins]`.
+    /// The maps are written with the shape `[rx_antennas, num_doppler_bins, num_range_bins]`,
+    /// and the power sum with the shape `[num_doppler_bins, num_range_bins]`.
+    pub fn process_frame(
+        &self,
+        range: &[ComplexQ15],
+        scratch: &mut [ComplexQ15],
+        maps: &mut [ComplexQ15],
+        power_sum: &mut [u32],
+    ) -> Result<(), Error> {
+        let input_size = self.rx_antennas * self.num_chirps * self.num_range_bins;
+        if range.len() != input_size {
+            return Err(Error::BufferWrongSize(range.len(), input_size));
+        }
+        if maps.len() != self.rx_antennas * self.map_size() {
+            return Err(Error::OutputWrongSize(maps.len(), self.rx_antennas * self.map_size()));
+        }
+        if power_sum.len() != self.map_size() {
+            return Err(Error::OutputWrongSize(power_sum.len(), self.map_size()));
+        }
+
+        let antenna_size = self.num_chirps * self.num_range_bins;
+        for (input, output) in range
+            .chunks_exact(antenna_size)
+            .zip(maps.chunks_exact_mut(self.map_size()))
+        {
+            self.process_antenna(input, scratch, output)?;
+        }
+
+        power_sum.fill(0);
+        for map in maps.chunks_exact(self.map_size()) {
+            for (value, sum) in map.iter().zip(power_sum.iter_mut()) {
+                *sum = sum.saturating_add(power(value));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,319 @@
+//! Gesture recognition for touchless controls, designed for short frames with multiple chirps and all three RX antennas
+//! (such as [`Config::high_framerate_preset()`]).
+//!
+//! Recognition is split into three steps, so that each can be replaced:
+//! - [`FeatureExtractor`]: computes the [`GestureFeatures`] of the strongest moving target (the hand) in each frame.
+//! - [`GestureSegmenter`]: collects the features of consecutive frames with enough energy into a gesture window.
+//! - [`GestureClassifier`]: classifies a gesture window, e.g. with the [`RuleBasedClassifier`] or a custom (small) model.
+
+use libm::fabsf;
+use num_complex::Complex32;
+
+use super::angle::AntennaGeometry;
+use super::clutter::remove_mean_chirp;
+use super::{Detection, DopplerFft, Frame, RangeFft};
+use crate::config::Config;
+use crate::error::Error;
+
+/// The features of the strongest moving target of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GestureFeatures {
+    pub range_m: f32,
+    pub velocity_mps: f32,
+    pub azimuth_rad: f32,
+    pub elevation_rad: f32,
+    /// The power of the target summed over all antennas, normalized to the ADC full scale and the frame size.
+    pub energy: f32,
+}
+
+/// Extracts [`GestureFeatures`] from frames, using a range FFT, a Doppler FFT and the antenna geometry.
+///
+/// Static reflections are removed with [`remove_mean_chirp()`] before the Doppler FFT, so only moving targets remain.
+#[derive(Debug, Clone)]
+pub struct FeatureExtractor {
+    range_fft: RangeFft,
+    doppler_fft: DopplerFft,
+    geometry: AntennaGeometry,
+    first_bin: usize,
+    last_bin: usize,
+    normalization: f32,
+}
+
+impl FeatureExtractor {
+    /// Creates a feature extractor that searches for the hand between `min_range_m` and `max_range_m`.
+    pub fn new(
+        config: &Config,
+        range_fft: &RangeFft,
+        doppler_fft: &DopplerFft,
+        geometry: AntennaGeometry,
+        min_range_m: f32,
+        max_range_m: f32,
+    ) -> Result<Self, Error> {
+        let max_bin = range_fft.num_bins().checked_sub(1).ok_or(Error::InvalidParameter)?;
+        let first_bin = (min_range_m / range_fft.range_resolution_m()) as usize;
+        let last_bin = ((max_range_m / range_fft.range_resolution_m()) as usize).min(max_bin);
+        if first_bin > last_bin || range_fft.num_chirps() < 2 {
+            return Err(Error::InvalidParameter);
+        }
+
+        let frame_size = config.num_samples_per_chirp as f32 * config.num_chirps_per_frame as f32;
+
+        Ok(FeatureExtractor {
+            range_fft: range_fft.clone(),
+            doppler_fft: doppler_fft.clone(),
+            geometry,
+            first_bin,
+            last_bin,
+            normalization: 1.0 / (frame_size * frame_size),
+        })
+    }
+
+    /// The size of the workspace required for [`FeatureExtractor::extract()`]:
+    /// a scratch buffer for the FFTs, the range FFT output and the range-Doppler maps of all antennas.
+    pub fn workspace_size(&self) -> usize {
+        self.scratch_size() + self.range_fft.output_size() + self.range_fft.rx_antennas() * self.doppler_fft.map_size()
+    }
+
+    fn scratch_size(&self) -> usize {
+        self.range_fft.fft_len().max(self.doppler_fft.fft_len())
+    }
+
+    /// Extracts the features of the strongest moving target of a frame.
+    ///
+    /// The workspace must have the size returned by [`FeatureExtractor::workspace_size()`].
+    /// Angles are 0.0 if the geometry has no antenna pair along their axis.
+    pub fn extract(&self, frame: &Frame, workspace: &mut [Complex32]) -> Result<GestureFeatures, Error> {
+        if workspace.len() != self.workspace_size() {
+            return Err(Error::BufferWrongSize(workspace.len(), self.workspace_size()));
+        }
+
+        let (scratch, rest) = workspace.split_at_mut(self.scratch_size());
+        let (range, maps) = rest.split_at_mut(self.range_fft.output_size());
+
+        self.range_fft.process_frame(frame, &mut scratch[..self.range_fft.fft_len()], range)?;
+        remove_mean_chirp(&self.range_fft, range)?;
+
+        let map_size = self.doppler_fft.map_size();
+        let antenna_size = self.range_fft.num_chirps() * self.range_fft.num_bins();
+        for (input, output) in range.chunks_exact(antenna_size).zip(maps.chunks_exact_mut(map_size)) {
+            self.doppler_fft
+                .process_antenna(input, &mut scratch[..self.doppler_fft.fft_len()], output)?;
+        }
+
+        // Strongest cell within the range window, summed over all antennas
+        let num_range_bins = self.doppler_fft.num_range_bins();
+        let mut strongest = (0, self.first_bin, 0.0);
+        for doppler in 0..self.doppler_fft.num_doppler_bins() {
+            for bin in self.first_bin..=self.last_bin {
+                let cell = doppler * num_range_bins + bin;
+                let power: f32 = maps.chunks_exact(map_size).map(|map| map[cell].norm_sqr()).sum();
+                if power > strongest.2 {
+                    strongest = (doppler, bin, power);
+                }
+            }
+        }
+        let (doppler_bin, range_bin, power) = strongest;
+
+        let detection = Detection {
+            range_bin,
+            doppler_bin,
+            ..Default::default()
+        };
+        let angle = self.geometry.estimate_detection(maps, &self.doppler_fft, &detection)?;
+
+        Ok(GestureFeatures {
+            range_m: self.range_fft.bin_to_range_m(range_bin),
+            velocity_mps: self.doppler_fft.bin_to_velocity_mps(doppler_bin),
+            azimuth_rad: angle.azimuth_rad.unwrap_or(0.0),
+            elevation_rad: angle.elevation_rad.unwrap_or(0.0),
+            energy: power * self.normalization,
+        })
+    }
+}
+
+/// Settings of a [`GestureSegmenter`], the durations are converted to frames with the `frame_repetition_time_s` of the config.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmenterSettings {
+    /// Frames with at least this energy belong to a gesture.
+    pub energy_threshold: f32,
+    /// Shorter windows are discarded as noise.
+    pub min_duration_s: f32,
+    /// A window ends after this time without energy.
+    pub max_gap_s: f32,
+}
+
+impl Default for SegmenterSettings {
+    fn default() -> Self {
+        SegmenterSettings {
+            energy_threshold: 1e-6,
+            min_duration_s: 0.1,
+            max_gap_s: 0.1,
+        }
+    }
+}
+
+/// Collects the features of consecutive active frames into gesture windows, stored in a caller-provided buffer.
+///
+/// The length of the buffer limits the duration of a gesture, longer gestures are truncated (e.g. 300 frames for 1.5 s at 5 ms per frame).
+#[derive(Debug)]
+pub struct GestureSegmenter<'a> {
+    settings: SegmenterSettings,
+    min_frames: usize,
+    max_gap_frames: usize,
+    window: &'a mut [GestureFeatures],
+    len: usize,
+    gap: usize,
+}
+
+impl<'a> GestureSegmenter<'a> {
+    pub fn new(config: &Config, settings: SegmenterSettings, window: &'a mut [GestureFeatures]) -> Result<Self, Error> {
+        let frame_time_s = config.frame_repetition_time_s as f32;
+        let min_frames = ((settings.min_duration_s / frame_time_s) as usize).max(1);
+        if window.len() < min_frames {
+            return Err(Error::BufferWrongSize(window.len(), min_frames));
+        }
+
+        Ok(GestureSegmenter {
+            settings,
+            min_frames,
+            max_gap_frames: (settings.max_gap_s / frame_time_s) as usize,
+            window,
+            len: 0,
+            gap: 0,
+        })
+    }
+
+    /// Discards the current window.
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.gap = 0;
+    }
+
+    /// Adds the features of a frame, and returns the window once a gesture has ended.
+    pub fn push(&mut self, features: GestureFeatures) -> Option<&[GestureFeatures]> {
+        if features.energy >= self.settings.energy_threshold {
+            if self.len < self.window.len() {
+                self.window[self.len] = features;
+                self.len += 1;
+            }
+            self.gap = 0;
+            return None;
+        }
+
+        if self.len == 0 {
+            return None;
+        }
+
+        self.gap += 1;
+        if self.gap <= self.max_gap_frames {
+            return None;
+        }
+
+        let len = self.len;
+        self.reset();
+        (len >= self.min_frames).then_some(&self.window[..len])
+    }
+}
+
+/// The recognized gestures. Directions refer to the axes of the [`AntennaGeometry`] used for feature extraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    SwipeLeft,
+    SwipeRight,
+    SwipeUp,
+    SwipeDown,
+    /// Towards the sensor.
+    Push,
+    /// Away from the sensor.
+    Pull,
+}
+
+/// Classifies a gesture window, implement this for custom classifiers (e.g. a small neural network).
+pub trait GestureClassifier {
+    /// Returns the gesture of the window, or `None` if it does not match any gesture.
+    fn classify(&mut self, window: &[GestureFeatures]) -> Option<Gesture>;
+}
+
+/// A lightweight classifier, comparing the change of azimuth, elevation and range between the start and the end of a window.
+///
+/// The start and end are the energy-weighted averages of the first and last quarter of the window.
+/// Swipes right and up are increasing angles, so the sign conventions of the antenna geometry apply.
+/// Since the range resolution is often too coarse for the short distances of a push or pull,
+/// the mean radial velocity is used as well, which is negative towards the sensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RuleBasedClassifier {
+    /// The minimum change of the angle for a swipe, in radians.
+    pub min_angle_change_rad: f32,
+    /// The minimum change of the range for a push or pull, in meters.
+    pub min_range_change_m: f32,
+    /// The minimum mean radial velocity for a push or pull, in m/s.
+    pub min_velocity_mps: f32,
+    /// The range change that is considered equal to an angle change of one radian, to compare both kinds of motion.
+    pub range_per_rad_m: f32,
+}
+
+impl Default for RuleBasedClassifier {
+    fn default() -> Self {
+        RuleBasedClassifier {
+            min_angle_change_rad: 0.3,
+            min_range_change_m: 0.05,
+            min_velocity_mps: 0.3,
+            range_per_rad_m: 0.15,
+        }
+    }
+}
+
+impl GestureClassifier for RuleBasedClassifier {
+    fn classify(&mut self, window: &[GestureFeatures]) -> Option<Gesture> {
+        if window.len() < 4 {
+            return None;
+        }
+
+        let quarter = window.len() / 4;
+        let start = weighted_mean(&window[..quarter])?;
+        let end = weighted_mean(&window[window.len() - quarter..])?;
+
+        let azimuth = end.azimuth_rad - start.azimuth_rad;
+        let elevation = end.elevation_rad - start.elevation_rad;
+        let range = end.range_m - start.range_m;
+        // Unwrap is safe, since the first and last quarter already have energy
+        let velocity = weighted_mean(window).unwrap().velocity_mps;
+
+        // Pick the dominant motion, with the range change scaled to be comparable to the angles
+        let scaled_range = range / self.range_per_rad_m;
+        let swipe = fabsf(azimuth).max(fabsf(elevation)) >= self.min_angle_change_rad;
+        if fabsf(scaled_range) > fabsf(azimuth).max(fabsf(elevation)) && fabsf(range) >= self.min_range_change_m {
+            Some(if range < 0.0 { Gesture::Push } else { Gesture::Pull })
+        } else if swipe && fabsf(azimuth) >= fabsf(elevation) {
+            Some(if azimuth > 0.0 { Gesture::SwipeRight } else { Gesture::SwipeLeft })
+        } else if swipe {
+            Some(if elevation > 0.0 { Gesture::SwipeUp } else { Gesture::SwipeDown })
+        } else if fabsf(velocity) >= self.min_velocity_mps {
+            Some(if velocity < 0.0 { Gesture::Push } else { Gesture::Pull })
+        } else {
+            None
+        }
+    }
+}
+
+/// The energy-weighted mean of range and angles.
+fn weighted_mean(features: &[GestureFeatures]) -> Option<GestureFeatures> {
+    let energy: f32 = features.iter().map(|feature| feature.energy).sum();
+    if energy <= 0.0 {
+        return None;
+    }
+
+    let mut mean = GestureFeatures {
+        energy,
+        ..Default::default()
+    };
+    for feature in features {
+        let weight = feature.energy / energy;
+        mean.range_m += feature.range_m * weight;
+        mean.velocity_mps += feature.velocity_mps * weight;
+        mean.azimuth_rad += feature.azimuth_rad * weight;
+        mean.elevation_rad += feature.elevation_rad * weight;
+    }
+
+    Some(mean)
+}
@@ -0,0 +1,141 @@
+//! Signal processing of the frames returned by [`crate::Radar::get_frames()`] and [`crate::Radar::get_fifo_data()`].
+//!
+//! All processing works on `no_std` without allocations, using caller-provided buffers.
+//! With the `alloc` feature, convenience methods returning ndarrays are available as well.
+//! For MCUs without a hardware FPU, fixed-point versions of the FFTs are available in [`fixed`].
+//!
+//! The processing chain is: ADC samples -> mean removal -> window -> range FFT -> clutter removal -> Doppler FFT -> range-Doppler map -> CFAR detection -> angle of arrival -> tracking -> zones
+
+pub mod angle;
+pub mod calibration;
+pub mod cfar;
+pub mod clutter;
+pub mod doppler;
+pub mod fft;
+pub mod fixed;
+pub mod gesture;
+pub mod pointcloud;
+pub mod presence;
+pub mod range;
+pub mod spectrogram;
+pub mod tracking;
+pub mod vitals;
+pub mod window;
+pub mod zones;
+
+pub use angle::{AngleOfArrival, AntennaGeometry, AntennaPair};
+pub use calibration::{Calibration, Calibrator};
+pub use cfar::{Cfar, CfarKind, Detection};
+pub use doppler::DopplerFft;
+pub use num_complex::Complex32;
+pub use pointcloud::{MountingTransform, Point};
+pub use presence::{PresenceDetector, PresenceEvent, PresenceSettings, PresenceState};
+pub use range::RangeFft;
+pub use spectrogram::{SlowTime, Spectrogram, SpectrogramSettings};
+pub use vitals::{VitalSigns, VitalSignsEstimate, VitalSignsSettings};
+pub use window::Window;
+
+#[cfg(feature = "alloc")]
+use ndarray::Array3;
+
+use crate::config::Config;
+use crate::error::Error;
+
+pub use crate::config::ADC_FULL_SCALE;
+
+/// A read-only view of a single frame of ADC samples, with the shape `[rx_antennas, num_chirps_per_frame, num_samples_per_chirp]`.
+///
+/// The view supports both the interleaved layout of the FIFO (as written by [`crate::Radar::get_fifo_data()`]),
+/// and any ndarray with non-negative strides (such as the ones returned by [`crate::Radar::get_frames()`]).
+#[derive(Debug, Clone, Copy)]
+pub struct Frame<'a> {
+    data: &'a [u16],
+    shape: (usize, usize, usize),
+    strides: (usize, usize, usize),
+}
+
+impl<'a> Frame<'a> {
+    /// Creates a view of the raw FIFO data of a single frame, as written by [`crate::Radar::get_fifo_data()`].
+    pub fn new(data: &'a [u16], config: &Config) -> Result<Self, Error> {
+        let fifo_limit = config.get_fifo_limit();
+        if data.len() != fifo_limit {
+            return Err(Error::BufferWrongSize(data.len(), fifo_limit));
+        }
+
+        let rx_antennas = config.rx_antennas as usize;
+        let num_samples = config.num_samples_per_chirp as usize;
+
+        // The samples of all antennas are interleaved, see Radar::get_frames()
+        Ok(Frame {
+            data,
+            shape: (rx_antennas, config.num_chirps_per_frame as usize, num_samples),
+            strides: (1, rx_antennas * num_samples, rx_antennas),
+        })
+    }
+
+    /// Creates a view of a frame returned by [`crate::Radar::get_frames()`], or any other contiguous 3D array.
+    #[cfg(feature = "alloc")]
+    pub fn from_array(frames: &'a Array3<u16>) -> Result<Self, Error> {
+        let data = frames.as_slice_memory_order().ok_or(Error::ShapeMismatch)?;
+        let strides = frames.strides();
+
+        if strides.iter().any(|&stride| stride < 0) {
+            return Err(Error::ShapeMismatch);
+        }
+
+        Ok(Frame {
+            data,
+            shape: frames.dim(),
+            strides: (strides[0] as usize, strides[1] as usize, strides[2] as usize),
+        })
+    }
+
+    /// The shape of the frame: `(rx_antennas, num_chirps_per_frame, num_samples_per_chirp)`
+    pub fn shape(&self) -> (usize, usize, usize) {
+        self.shape
+    }
+
+    pub fn rx_antennas(&self) -> usize {
+        self.shape.0
+    }
+
+    pub fn num_chirps(&self) -> usize {
+        self.shape.1
+    }
+
+    pub fn num_samples(&self) -> usize {
+        self.shape.2
+    }
+
+    /// Returns a single ADC sample.
+    ///
+    /// Panics if the indices are out of bounds.
+    pub fn sample(&self, rx: usize, chirp: usize, sample: usize) -> u16 {
+        assert!(rx < self.shape.0 && chirp < self.shape.1 && sample < self.shape.2);
+        self.data[rx * self.strides.0 + chirp * self.strides.1 + sample * self.strides.2]
+    }
+
+    /// Returns an iterator over the ADC samples of a single chirp of one antenna.
+    ///
+    /// Panics if the indices are out of bounds.
+    pub fn chirp(&self, rx: usize, chirp: usize) -> impl Iterator<Item = u16> + 'a {
+        assert!(rx < self.shape.0 && chirp < self.shape.1);
+        let data = self.data;
+        let offset = rx * self.strides.0 + chirp * self.strides.1;
+        let stride = self.strides.2;
+        (0..self.shape.2).map(move |sample| data[offset + sample * stride])
+    }
+}
+
+/// Computes the magnitude of each complex value.
+pub fn magnitude(input: &[Complex32], output: &mut [f32]) -> Result<(), Error> {
+    if input.len() != output.len() {
+        return Err(Error::OutputWrongSize(output.len(), input.len()));
+    }
+
+    for (value, result) in input.iter().zip(output.iter_mut()) {
+        *result = value.norm();
+    }
+
+    Ok(())
+}
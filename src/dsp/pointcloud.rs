@@ -0,0 +1,219 @@
+//! 3D point clouds of detections, e.g. for fusion with camera and lidar data.
+//!
+//! Points are given in the sensor frame, with `y` along the boresight, `x` to the side of positive azimuth and `z` towards positive elevation
+//! (the same horizontal axes as the [`tracking`](super::tracking) module), and can be moved into another frame with a [`MountingTransform`].
+//! A point list can be serialized into a compact little-endian format with [`encode()`], e.g. to stream it from an MCU.
+
+use libm::{cosf, sinf};
+use num_complex::Complex32;
+
+use super::angle::{AngleOfArrival, AntennaGeometry};
+use super::{Detection, DopplerFft};
+use crate::error::Error;
+
+/// Magic bytes at the start of a serialized point cloud.
+const MAGIC: [u8; 4] = *b"BGTP";
+
+/// Version of the serialized point cloud format.
+const VERSION: u8 = 1;
+
+/// The size of the header of a serialized point cloud, in bytes: magic, version (u8), frame number (u32), number of points (u16).
+pub const HEADER_SIZE: usize = MAGIC.len() + 1 + 4 + 2;
+
+/// The size of a serialized point, in bytes: x, y, z, radial velocity and SNR (f32 each).
+pub const POINT_SIZE: usize = 5 * 4;
+
+/// A detection in 3D Cartesian coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x_m: f32,
+    pub y_m: f32,
+    pub z_m: f32,
+    /// The radial velocity relative to the sensor, negative towards the sensor.
+    pub velocity_mps: f32,
+    pub snr_db: f32,
+    /// The number of the frame the point was detected in, counted by the caller.
+    pub frame: u32,
+}
+
+impl Point {
+    /// Converts a detection and its angle of arrival into a point in the sensor frame.
+    ///
+    /// Missing angles (if the antenna geometry has no pair along their axis) are treated as 0.0, so the point lies in the plane of the other angle.
+    pub fn from_detection(detection: &Detection, angle: &AngleOfArrival, frame: u32) -> Self {
+        let azimuth = angle.azimuth_rad.unwrap_or(0.0);
+        let elevation = angle.elevation_rad.unwrap_or(0.0);
+        let horizontal = detection.range_m * cosf(elevation);
+
+        Point {
+            x_m: horizontal * sinf(azimuth),
+            y_m: horizontal * cosf(azimuth),
+            z_m: detection.range_m * sinf(elevation),
+            velocity_mps: detection.velocity_mps,
+            snr_db: detection.snr_db,
+            frame,
+        }
+    }
+}
+
+/// The pose of the sensor in another frame (e.g. of a vehicle or a room), applied as `rotation * point + translation`.
+///
+/// The radial velocity is not changed, since it is measured along the line of sight of the sensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MountingTransform {
+    /// Row-major rotation matrix from the sensor frame into the target frame.
+    pub rotation: [[f32; 3]; 3],
+    /// The position of the sensor in the target frame, in meters.
+    pub translation: [f32; 3],
+}
+
+impl Default for MountingTransform {
+    /// No rotation or translation.
+    fn default() -> Self {
+        MountingTransform {
+            rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            translation: [0.0; 3],
+        }
+    }
+}
+
+impl MountingTransform {
+    /// Creates a transform from the orientation of the sensor and its position, with angles in radians, applied in the order roll, pitch, yaw:
+    /// - `roll_rad`: rotation around the boresight (`y`).
+    /// - `pitch_rad`: rotation around `x`, positive tilts the boresight upwards.
+    /// - `yaw_rad`: rotation around `z`, positive turns the boresight towards negative `x`.
+    pub fn from_euler(roll_rad: f32, pitch_rad: f32, yaw_rad: f32, translation: [f32; 3]) -> Self {
+        let (sr, cr) = (sinf(roll_rad), cosf(roll_rad));
+        let (sp, cp) = (sinf(pitch_rad), cosf(pitch_rad));
+        let (sy, cy) = (sinf(yaw_rad), cosf(yaw_rad));
+
+        // R = Rz(yaw) * Rx(pitch) * Ry(roll)
+        let rotation = [
+            [cy * cr - sy * sp * sr, -sy * cp, cy * sr + sy * sp * cr],
+            [sy * cr + cy * sp * sr, cy * cp, sy * sr - cy * sp * cr],
+            [-cp * sr, sp, cp * cr],
+        ];
+
+        MountingTransform { rotation, translation }
+    }
+
+    /// Moves a point from the sensor frame into the target frame.
+    pub fn apply(&self, point: &Point) -> Point {
+        let input = [point.x_m, point.y_m, point.z_m];
+        let mut output = self.translation;
+        for (value, row) in output.iter_mut().zip(&self.rotation) {
+            *value += row.iter().zip(&input).map(|(r, p)| r * p).sum::<f32>();
+        }
+
+        Point {
+            x_m: output[0],
+            y_m: output[1],
+            z_m: output[2],
+            ..*point
+        }
+    }
+}
+
+/// Converts the detections of a frame into points, estimating the angle of arrival of each detection from the range-Doppler maps
+/// (see [`AntennaGeometry::estimate_detection()`]) and applying the mounting transform.
+///
+/// Returns the number of points written, detections beyond the length of the output are dropped.
+pub fn detections_to_points(
+    maps: &[Complex32],
+    doppler_fft: &DopplerFft,
+    geometry: &AntennaGeometry,
+    detections: &[Detection],
+    frame: u32,
+    transform: &MountingTransform,
+    output: &mut [Point],
+) -> Result<usize, Error> {
+    let mut count = 0;
+    for (detection, point) in detections.iter().zip(output.iter_mut()) {
+        let angle = geometry.estimate_detection(maps, doppler_fft, detection)?;
+        *point = transform.apply(&Point::from_detection(detection, &angle, frame));
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// The size of a serialized point cloud with the given number of points, in bytes.
+pub fn encoded_size(num_points: usize) -> usize {
+    HEADER_SIZE + num_points * POINT_SIZE
+}
+
+/// Serializes the points of a frame into the output, and returns the number of bytes written.
+///
+/// Layout: magic `BGTP`, version (u8), frame number (u32), number of points (u16), then x, y, z, radial velocity and SNR (f32 each) per point.
+/// The frame number is stored once, so all points must belong to the given frame.
+pub fn encode(frame: u32, points: &[Point], output: &mut [u8]) -> Result<usize, Error> {
+    let num_points = u16::try_from(points.len()).map_err(|_| Error::InvalidParameter)?;
+    if points.iter().any(|point| point.frame != frame) {
+        return Err(Error::InvalidParameter);
+    }
+    let size = encoded_size(points.len());
+    if output.len() < size {
+        return Err(Error::OutputWrongSize(output.len(), size));
+    }
+
+    output[..4].copy_from_slice(&MAGIC);
+    output[4] = VERSION;
+    output[5..9].copy_from_slice(&frame.to_le_bytes());
+    output[9..11].copy_from_slice(&num_points.to_le_bytes());
+
+    for (point, bytes) in points.iter().zip(output[HEADER_SIZE..size].chunks_exact_mut(POINT_SIZE)) {
+        let values = [point.x_m, point.y_m, point.z_m, point.velocity_mps, point.snr_db];
+        for (chunk, value) in bytes.chunks_exact_mut(4).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    Ok(size)
+}
+
+/// Reads the header of a serialized point cloud, and returns the frame number and the number of points.
+pub fn decode_header(bytes: &[u8]) -> Result<(u32, usize), Error> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(Error::BufferWrongSize(bytes.len(), HEADER_SIZE));
+    }
+    if bytes[..4] != MAGIC || bytes[4] != VERSION {
+        return Err(Error::InvalidParameter);
+    }
+
+    // Unwrap is safe, since the header has been checked to be long enough
+    let frame = u32::from_le_bytes(bytes[5..9].try_into().unwrap());
+    let num_points = u16::from_le_bytes(bytes[9..11].try_into().unwrap()) as usize;
+
+    Ok((frame, num_points))
+}
+
+/// Deserializes a point cloud written by [`encode()`], and returns the frame number and the number of points written to the output.
+pub fn decode(bytes: &[u8], output: &mut [Point]) -> Result<(u32, usize), Error> {
+    let (frame, num_points) = decode_header(bytes)?;
+    let size = encoded_size(num_points);
+    if bytes.len() != size {
+        return Err(Error::BufferWrongSize(bytes.len(), size));
+    }
+    if output.len() < num_points {
+        return Err(Error::OutputWrongSize(output.len(), num_points));
+    }
+
+    for (point, chunk) in output.iter_mut().zip(bytes[HEADER_SIZE..].chunks_exact(POINT_SIZE)) {
+        let mut values = [0.0; 5];
+        for (value, bytes) in values.iter_mut().zip(chunk.chunks_exact(4)) {
+            // Unwrap is safe, since the chunks have exactly 4 bytes
+            *value = f32::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        *point = Point {
+            x_m: values[0],
+            y_m: values[1],
+            z_m: values[2],
+            velocity_mps: values[3],
+            snr_db: values[4],
+            frame,
+        };
+    }
+
+    Ok((frame, num_points))
+}
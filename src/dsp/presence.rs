@@ -0,0 +1,272 @@
+//! Presence detection of people from macro motion (e.g. walking) and micro motion (e.g. sitting still, typing, breathing).
+//!
+//! Both kinds of motion are detected per range gate (range bin), by comparing the range profile of each frame to a background:
+//! - Macro motion: the change against a fast background, which follows the scene within a fraction of a second.
+//! - Micro motion: the change against a slow background, low-pass filtered over a few seconds to reject noise.
+//!
+//! All time constants and hold times are given in seconds and converted with the `frame_repetition_time_s` of the config.
+//! Use [`PresenceSettings::for_config()`] to adapt the defaults to the frame rate and frame shape of a config,
+//! e.g. of the presets in `config/`.
+
+use libm::{ceilf, expf, sqrtf};
+use num_complex::Complex32;
+
+use super::{Frame, RangeFft};
+use crate::config::Config;
+use crate::error::Error;
+
+/// Tuning parameters of a [`PresenceDetector`].
+///
+/// Thresholds are given as magnitudes of the range profile, normalized to the ADC full scale and the number of samples per chirp,
+/// so they do not depend on the FFT length. The defaults are starting points for indoor installations up to a few meters,
+/// for frames of 16 chirps with 128 samples each at frame rates of 10 Hz and more, as in the presets of this crate.
+/// They likely need to be tuned for the TX power and IF gain of the config.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresenceSettings {
+    /// The distance of the first range gate, to skip the direct coupling between TX and RX antennas.
+    pub min_range_m: f32,
+    pub macro_threshold: f32,
+    pub micro_threshold: f32,
+    /// A range gate stays active until its motion drops below `threshold * hysteresis`.
+    pub hysteresis: f32,
+    /// The time macro motion is still reported after it was last detected.
+    pub macro_hold_s: f32,
+    /// The time micro motion is still reported after it was last detected.
+    pub micro_hold_s: f32,
+    /// The time constant of the fast background used for macro motion.
+    pub macro_time_constant_s: f32,
+    /// The time constant of the slow background used for micro motion.
+    pub micro_time_constant_s: f32,
+    /// The time constant of the low-pass filter applied to micro motion.
+    pub micro_smoothing_s: f32,
+}
+
+impl Default for PresenceSettings {
+    fn default() -> Self {
+        PresenceSettings {
+            min_range_m: 0.2,
+            macro_threshold: 2e-3,
+            micro_threshold: 5e-4,
+            hysteresis: 0.7,
+            macro_hold_s: 1.0,
+            micro_hold_s: 10.0,
+            macro_time_constant_s: 0.2,
+            micro_time_constant_s: 5.0,
+            micro_smoothing_s: 1.0,
+        }
+    }
+}
+
+impl PresenceSettings {
+    /// The number of ADC samples per frame the default thresholds are chosen for: 16 chirps with 128 samples each.
+    const DEFAULT_SAMPLES_PER_FRAME: f32 = 16.0 * 128.0;
+
+    /// Adapts the defaults to the frame rate and frame shape of a config.
+    ///
+    /// - The thresholds are scaled with the noise of the range profile, which is averaged over all samples of a frame,
+    ///   so frames with fewer chirps or samples than the default of 16 chirps with 128 samples need higher thresholds.
+    ///   E.g. the single chirp of [`Config::test_preset()`] quadruples them.
+    /// - The time constants of the fast and slow background and of the micro motion filter span at least 2, 20 and 5 frames,
+    ///   and the hold times at least 3 frames, so that slow frame rates still filter over multiple frames.
+    ///
+    /// For the presets with a frame rate of 10 Hz and more and 16 chirps of 128 samples (e.g. [`Config::high_framerate_preset()`]
+    /// and `radar_low_framerate_config.json`), this returns the defaults.
+    pub fn for_config(config: &Config) -> Self {
+        let defaults = Self::default();
+        let frame_time_s = config.frame_repetition_time_s as f32;
+        let samples_per_frame = config.num_chirps_per_frame as f32 * config.num_samples_per_chirp as f32;
+        let noise_scale = sqrtf(Self::DEFAULT_SAMPLES_PER_FRAME / samples_per_frame.max(1.0)).max(1.0);
+
+        PresenceSettings {
+            macro_threshold: defaults.macro_threshold * noise_scale,
+            micro_threshold: defaults.micro_threshold * noise_scale,
+            macro_hold_s: defaults.macro_hold_s.max(3.0 * frame_time_s),
+            micro_hold_s: defaults.micro_hold_s.max(3.0 * frame_time_s),
+            macro_time_constant_s: defaults.macro_time_constant_s.max(2.0 * frame_time_s),
+            micro_time_constant_s: defaults.micro_time_constant_s.max(20.0 * frame_time_s),
+            micro_smoothing_s: defaults.micro_smoothing_s.max(5.0 * frame_time_s),
+            ..defaults
+        }
+    }
+}
+
+/// The presence state reported by a [`PresenceDetector`], macro motion takes precedence over micro motion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PresenceState {
+    #[default]
+    Absent,
+    MicroMotion,
+    MacroMotion,
+}
+
+/// A change of the presence state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresenceEvent {
+    pub state: PresenceState,
+    /// The distance of the closest range gate with motion, `None` if absent or only held.
+    pub distance_m: Option<f32>,
+}
+
+/// Presence detector for `BINS` range gates, starting at [`PresenceSettings::min_range_m`].
+///
+/// Only the first antenna is used, since presence detection does not need angular information.
+/// The detector needs no buffers besides the scratch buffer of the range FFT, its state is stored inline.
+#[derive(Debug, Clone)]
+pub struct PresenceDetector<const BINS: usize> {
+    range_fft: RangeFft,
+    settings: PresenceSettings,
+    first_bin: usize,
+    num_samples: f32,
+    macro_alpha: f32,
+    micro_alpha: f32,
+    smoothing_alpha: f32,
+    macro_hold_frames: u32,
+    micro_hold_frames: u32,
+    fast_background: [Complex32; BINS],
+    slow_background: [Complex32; BINS],
+    micro_motion: [f32; BINS],
+    macro_active: [bool; BINS],
+    micro_active: [bool; BINS],
+    macro_hold: u32,
+    micro_hold: u32,
+    initialized: bool,
+    state: PresenceState,
+    distance_m: Option<f32>,
+}
+
+impl<const BINS: usize> PresenceDetector<BINS> {
+    /// Creates a presence detector for frames of the given config, processed with the range FFT.
+    ///
+    /// Returns [`Error::InvalidParameter`] if the range gates exceed the range bins of the FFT.
+    pub fn new(config: &Config, range_fft: &RangeFft, settings: PresenceSettings) -> Result<Self, Error> {
+        let first_bin = ceilf(settings.min_range_m / range_fft.range_resolution_m()) as usize;
+        if BINS == 0 || first_bin + BINS > range_fft.num_bins() || !(settings.hysteresis > 0.0 && settings.hysteresis <= 1.0) {
+            return Err(Error::InvalidParameter);
+        }
+
+        let frame_time_s = config.frame_repetition_time_s as f32;
+        // Exponential smoothing factor for a time constant, and number of frames for a hold time
+        let alpha = |time_constant_s: f32| 1.0 - expf(-frame_time_s / time_constant_s.max(f32::EPSILON));
+        let frames = |hold_s: f32| ceilf(hold_s / frame_time_s) as u32;
+
+        Ok(PresenceDetector {
+            range_fft: range_fft.clone(),
+            settings,
+            first_bin,
+            num_samples: config.num_samples_per_chirp as f32,
+            macro_alpha: alpha(settings.macro_time_constant_s),
+            micro_alpha: alpha(settings.micro_time_constant_s),
+            smoothing_alpha: alpha(settings.micro_smoothing_s),
+            macro_hold_frames: frames(settings.macro_hold_s),
+            micro_hold_frames: frames(settings.micro_hold_s),
+            fast_background: [Complex32::new(0.0, 0.0); BINS],
+            slow_background: [Complex32::new(0.0, 0.0); BINS],
+            micro_motion: [0.0; BINS],
+            macro_active: [false; BINS],
+            micro_active: [false; BINS],
+            macro_hold: 0,
+            micro_hold: 0,
+            initialized: false,
+            state: PresenceState::Absent,
+            distance_m: None,
+        })
+    }
+
+    pub fn settings(&self) -> &PresenceSettings {
+        &self.settings
+    }
+
+    pub fn state(&self) -> PresenceState {
+        self.state
+    }
+
+    /// The distance of the closest range gate with motion in the last frame.
+    pub fn distance_m(&self) -> Option<f32> {
+        self.distance_m
+    }
+
+    /// The distance of each range gate, in meters.
+    pub fn range_gates(&self) -> impl Iterator<Item = f32> + '_ {
+        (self.first_bin..self.first_bin + BINS).map(|bin| self.range_fft.bin_to_range_m(bin))
+    }
+
+    /// Returns to the absent state and relearns the background, e.g. after the radar has been reconfigured.
+    pub fn reset(&mut self) {
+        self.macro_active = [false; BINS];
+        self.micro_active = [false; BINS];
+        self.micro_motion = [0.0; BINS];
+        self.macro_hold = 0;
+        self.micro_hold = 0;
+        self.initialized = false;
+        self.state = PresenceState::Absent;
+        self.distance_m = None;
+    }
+
+    /// Processes a frame, and returns an event if the presence state has changed.
+    ///
+    /// The scratch buffer must have the length of the FFT of the range FFT.
+    pub fn process_frame(&mut self, frame: &Frame, scratch: &mut [Complex32]) -> Result<Option<PresenceEvent>, Error> {
+        // Range profile of the first antenna, averaged over all chirps of the frame
+        let mut profile = [Complex32::new(0.0, 0.0); BINS];
+        let scale = 1.0 / (frame.num_chirps() as f32 * self.num_samples);
+        for chirp in 0..frame.num_chirps() {
+            self.range_fft.process_chirp(frame, 0, chirp, scratch)?;
+            for (value, bin) in profile.iter_mut().zip(&scratch[self.first_bin..]) {
+                *value += bin * scale;
+            }
+        }
+
+        if !self.initialized {
+            self.fast_background = profile;
+            self.slow_background = profile;
+            self.initialized = true;
+        }
+
+        let settings = &self.settings;
+        let mut macro_detected = false;
+        let mut micro_detected = false;
+        let mut distance_m = None;
+
+        for (gate, &value) in profile.iter().enumerate() {
+            let macro_motion = (value - self.fast_background[gate]).norm();
+            let micro_motion = (value - self.slow_background[gate]).norm();
+            self.micro_motion[gate] += (micro_motion - self.micro_motion[gate]) * self.smoothing_alpha;
+
+            self.fast_background[gate] += (value - self.fast_background[gate]) * self.macro_alpha;
+            self.slow_background[gate] += (value - self.slow_background[gate]) * self.micro_alpha;
+
+            self.macro_active[gate] = active(self.macro_active[gate], macro_motion, settings.macro_threshold, settings.hysteresis);
+            self.micro_active[gate] = active(self.micro_active[gate], self.micro_motion[gate], settings.micro_threshold, settings.hysteresis);
+
+            macro_detected |= self.macro_active[gate];
+            micro_detected |= self.micro_active[gate];
+            if distance_m.is_none() && (self.macro_active[gate] || self.micro_active[gate]) {
+                distance_m = Some(self.range_fft.bin_to_range_m(self.first_bin + gate));
+            }
+        }
+
+        self.macro_hold = if macro_detected { self.macro_hold_frames } else { self.macro_hold.saturating_sub(1) };
+        self.micro_hold = if micro_detected { self.micro_hold_frames } else { self.micro_hold.saturating_sub(1) };
+        self.distance_m = distance_m;
+
+        let state = if macro_detected || self.macro_hold > 0 {
+            PresenceState::MacroMotion
+        } else if micro_detected || self.micro_hold > 0 {
+            PresenceState::MicroMotion
+        } else {
+            PresenceState::Absent
+        };
+
+        if state == self.state {
+            return Ok(None);
+        }
+
+        self.state = state;
+        Ok(Some(PresenceEvent { state, distance_m }))
+    }
+}
+
+/// Threshold with hysteresis: a gate becomes active above the threshold, and inactive below `threshold * hysteresis`.
+fn active(active: bool, motion: f32, threshold: f32, hysteresis: f32) -> bool {
+    if active { motion >= threshold * hysteresis } else { motion > threshold }
+}
@@ -0,0 +1,211 @@
+use num_complex::Complex32;
+
+#[cfg(feature = "alloc")]
+use ndarray::Array3;
+
+use super::calibration::Calibration;
+use super::fft::fft;
+use super::window::WindowTable;
+use super::{ADC_FULL_SCALE, Frame, Window};
+use crate::config::Config;
+use crate::error::Error;
+
+/// Computes the range FFT of each chirp of a frame.
+///
+/// For each chirp, the ADC samples are normalized to `0.0..=1.0` of the 12-bit full scale, the mean of the chirp is removed,
+/// the window is applied, and the samples are zero-padded to the FFT length.
+/// Since the input is real-valued, only the first half of the spectrum (`fft_len / 2` bins) is kept.
+///
+/// Range bin `k` corresponds to a distance of `k * range_resolution_m()`, where the resolution is derived from the bandwidth of the config
+/// (`upper_frequency_hz - lower_frequency_hz`), and is refined by zero-padding.
+///
+/// The window coefficients are computed once on creation (see [`WindowTable`]),
+/// so the number of samples per chirp must not exceed [`super::window::MAX_WINDOW_LEN`].
+///
+/// With a [`Calibration`], the phase and gain mismatch between the antennas is corrected in the output.
+#[derive(Debug, Clone)]
+pub struct RangeFft {
+    window: WindowTable,
+    shape: (usize, usize, usize),
+    fft_len: usize,
+    range_resolution_m: f32,
+    calibration: Option<Calibration>,
+}
+
+impl RangeFft {
+    /// Creates a range FFT without zero-padding (the FFT length is the number of samples per chirp, rounded up to the next power of two).
+    ///
+    /// Returns [`Error::InvalidParameter`] if the number of samples per chirp exceeds [`super::window::MAX_WINDOW_LEN`].
+    pub fn new(config: &Config, window: Window) -> Result<Self, Error> {
+        let fft_len = (config.num_samples_per_chirp as usize).next_power_of_two();
+        Self::with_fft_len(config, window, fft_len)
+    }
+
+    /// Creates a range FFT with zero-padding to the given FFT length.
+    ///
+    /// The FFT length must be a power of two, and at least the number of samples per chirp.
+    /// Returns [`Error::InvalidParameter`] if the number of samples per chirp exceeds [`super::window::MAX_WINDOW_LEN`].
+    pub fn with_fft_len(config: &Config, window: Window, fft_len: usize) -> Result<Self, Error> {
+        let num_samples = config.num_samples_per_chirp as usize;
+
+        if !fft_len.is_power_of_two() {
+            return Err(Error::NotAPowerOfTwo);
+        }
+        if fft_len < num_samples {
+            return Err(Error::BufferWrongSize(fft_len, num_samples));
+        }
+
+        // Zero-padding interpolates the spectrum, so each bin covers a fraction of the native resolution
+        let range_resolution_m =
+            (config.range_resolution_m() * num_samples as f64 / fft_len as f64) as f32;
+
+        Ok(RangeFft {
+            window: WindowTable::new(window, num_samples)?,
+            shape: (
+                config.rx_antennas as usize,
+                config.num_chirps_per_frame as usize,
+                num_samples,
+            ),
+            fft_len,
+            range_resolution_m,
+            calibration: None,
+        })
+    }
+
+    /// Applies the phase and gain corrections of a calibration to the output of each antenna.
+    pub fn with_calibration(mut self, calibration: Calibration) -> Self {
+        self.calibration = Some(calibration);
+        self
+    }
+
+    pub(crate) fn without_calibration(mut self) -> Self {
+        self.calibration = None;
+        self
+    }
+
+    pub fn calibration(&self) -> Option<&Calibration> {
+        self.calibration.as_ref()
+    }
+
+    pub fn window(&self) -> Window {
+        self.window.window()
+    }
+
+    pub fn rx_antennas(&self) -> usize {
+        self.shape.0
+    }
+
+    pub fn num_chirps(&self) -> usize {
+        self.shape.1
+    }
+
+    /// The length of the FFT, which is also the size of the scratch buffer required for processing.
+    pub fn fft_len(&self) -> usize {
+        self.fft_len
+    }
+
+    /// The number of range bins per chirp (half of the FFT length).
+    pub fn num_bins(&self) -> usize {
+        self.fft_len / 2
+    }
+
+    /// The size of the output of [`RangeFft::process_frame()`]: `rx_antennas * num_chirps_per_frame * num_bins`
+    pub fn output_size(&self) -> usize {
+        self.shape.0 * self.shape.1 * self.num_bins()
+    }
+
+    /// The distance covered by a single range bin, in meters.
+    pub fn range_resolution_m(&self) -> f32 {
+        self.range_resolution_m
+    }
+
+    /// The distance of a range bin, in meters.
+    pub fn bin_to_range_m(&self, bin: usize) -> f32 {
+        bin as f32 * self.range_resolution_m
+    }
+
+    /// Returns the labels of all range bins, in meters.
+    pub fn range_bins(&self) -> impl Iterator<Item = f32> + '_ {
+        (0..self.num_bins()).map(|bin| self.bin_to_range_m(bin))
+    }
+
+    /// Computes the range FFT of a single chirp of one antenna.
+    ///
+    /// The buffer must have the length of the FFT, the range bins are written to `buffer[..num_bins]`.
+    pub fn process_chirp(
+        &self,
+        frame: &Frame,
+        rx: usize,
+        chirp: usize,
+        buffer: &mut [Complex32],
+    ) -> Result<(), Error> {
+        if frame.shape() != self.shape {
+            return Err(Error::ShapeMismatch);
+        }
+        if buffer.len() != self.fft_len {
+            return Err(Error::BufferWrongSize(buffer.len(), self.fft_len));
+        }
+
+        let num_samples = self.shape.2;
+        let mean = frame.chirp(rx, chirp).map(|sample| sample as f32).sum::<f32>()
+            / (num_samples as f32 * ADC_FULL_SCALE);
+
+        for ((sample, coefficient), value) in frame.chirp(rx, chirp).zip(self.window.coefficients()).zip(buffer.iter_mut()) {
+            let normalized = sample as f32 / ADC_FULL_SCALE - mean;
+            *value = Complex32::new(normalized * coefficient, 0.0);
+        }
+        buffer[num_samples..].fill(Complex32::new(0.0, 0.0));
+
+        fft(buffer)?;
+
+        if let Some(correction) = self.calibration.as_ref().and_then(|calibration| calibration.correction(rx)) {
+            buffer.iter_mut().for_each(|value| *value *= correction);
+        }
+
+        Ok(())
+    }
+
+    /// Computes the range FFT of all chirps of all antennas of a frame.
+    ///
+    /// The scratch buffer must have the length of the FFT.
+    /// The output has the shape `[rx_antennas, num_chirps_per_frame, num_bins]` in row-major order, see [`RangeFft::output_size()`].
+    pub fn process_frame(
+        &self,
+        frame: &Frame,
+        scratch: &mut [Complex32],
+        output: &mut [Complex32],
+    ) -> Result<(), Error> {
+        let output_size = self.output_size();
+        if output.len() != output_size {
+            return Err(Error::OutputWrongSize(output.len(), output_size));
+        }
+
+        let num_bins = self.num_bins();
+        let mut chunks = output.chunks_exact_mut(num_bins);
+        for rx in 0..self.shape.0 {
+            for chirp in 0..self.shape.1 {
+                self.process_chirp(frame, rx, chirp, scratch)?;
+                // Unwrap is safe, since the output size has been checked
+                chunks.next().unwrap().copy_from_slice(&scratch[..num_bins]);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Computes the range FFT of a frame returned by [`crate::Radar::get_frames()`],
+    /// returning an array with the shape `[rx_antennas, num_chirps_per_frame, num_bins]`.
+    ///
+    /// This function requires the alloc feature, since it dynamically allocates memory for the output.
+    #[cfg(feature = "alloc")]
+    pub fn process_frames(&self, frames: &Array3<u16>) -> Result<Array3<Complex32>, Error> {
+        let frame = Frame::from_array(frames)?;
+        let mut scratch = alloc::vec![Complex32::new(0.0, 0.0); self.fft_len];
+        let mut output = alloc::vec![Complex32::new(0.0, 0.0); self.output_size()];
+
+        self.process_frame(&frame, &mut scratch, &mut output)?;
+
+        // We can ignore the error, since we know the output vector is the correct size
+        Ok(Array3::from_shape_vec((self.shape.0, self.shape.1, self.num_bins()), output).unwrap())
+    }
+}
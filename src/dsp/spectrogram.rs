@@ -0,0 +1,317 @@
+//! Micro-Doppler spectrograms of a single range gate, e.g. to classify targets (people, pets, fans) from their motion signature.
+//!
+//! The slow-time signal of the range gate (the range bin of the first antenna) is collected across chirps and frames,
+//! and a short-time FFT over the last `fft_len` samples is computed for each frame.
+//! The resulting velocity spectra are stored as the columns of a rolling time x velocity matrix in a caller-provided ring buffer.
+//!
+//! The range gate is fixed on creation, and can be moved to follow a target (e.g. a [`Track`](super::tracking::Track)) with
+//! [`Spectrogram::set_range_m()`]. The collected samples are kept when the gate moves, so the spectrogram stays continuous.
+
+use libm::log10f;
+use num_complex::Complex32;
+
+#[cfg(feature = "alloc")]
+use ndarray::Array2;
+
+use super::fft::{fft, fft_shift};
+use super::window::WindowTable;
+use super::{Frame, RangeFft, Window};
+use crate::config::Config;
+use crate::error::Error;
+
+/// The samples of the slow-time signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlowTime {
+    /// Every chirp is a sample, spaced by the `chirp_repetition_time_s` of the config.
+    ///
+    /// If the FFT is longer than the chirps of a frame, consecutive frames are concatenated,
+    /// which is only accurate if the frames follow each other closely (no long pause between frames).
+    Chirps,
+    /// The average of all chirps of a frame is a sample, spaced by the `frame_repetition_time_s` of the config.
+    ///
+    /// This suits configs with few chirps, but only covers slow motion (e.g. about ±1.25 cm/s at 10 Hz frames).
+    Frames,
+}
+
+/// Settings of a [`Spectrogram`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectrogramSettings {
+    pub slow_time: SlowTime,
+    /// The length of the short-time FFT, which must be a power of two of at most [`super::window::MAX_WINDOW_LEN`].
+    /// This is also the number of velocity bins.
+    pub fft_len: usize,
+    pub window: Window,
+    /// Removes the mean of the slow-time signal before the FFT, to suppress static reflections at zero velocity.
+    pub remove_static: bool,
+    /// Stores the power in dB instead of linear power.
+    pub decibel: bool,
+}
+
+impl Default for SpectrogramSettings {
+    fn default() -> Self {
+        SpectrogramSettings {
+            slow_time: SlowTime::Chirps,
+            fft_len: 16,
+            window: Window::Hann,
+            remove_static: true,
+            decibel: true,
+        }
+    }
+}
+
+/// Builds a rolling micro-Doppler spectrogram of a range gate, with one column (velocity spectrum) per frame.
+///
+/// The slow-time samples and the matrix are stored in caller-provided buffers:
+/// - `samples` must have the length of the FFT.
+/// - `matrix` must hold a whole number of columns of `fft_len` values each, which is the length of the spectrogram in frames.
+///   Once it is full, the oldest column is overwritten.
+///
+/// Velocity bin `k` corresponds to a radial velocity of `(k - fft_len / 2) * velocity_resolution_mps()`,
+/// with the same sign convention as the [`DopplerFft`](super::DopplerFft) (negative towards the sensor).
+///
+/// The window coefficients are computed once on creation (see [`WindowTable`]),
+/// so the FFT length must not exceed [`super::window::MAX_WINDOW_LEN`], otherwise [`Error::InvalidParameter`] is returned.
+#[derive(Debug)]
+pub struct Spectrogram<'a> {
+    range_fft: RangeFft,
+    settings: SpectrogramSettings,
+    window: WindowTable,
+    range_bin: usize,
+    velocity_resolution_mps: f32,
+    normalization: f32,
+    samples: &'a mut [Complex32],
+    num_samples: usize,
+    matrix: &'a mut [f32],
+    head: usize,
+    num_columns: usize,
+}
+
+impl<'a> Spectrogram<'a> {
+    pub fn new(
+        config: &Config,
+        range_fft: &RangeFft,
+        settings: SpectrogramSettings,
+        range_bin: usize,
+        samples: &'a mut [Complex32],
+        matrix: &'a mut [f32],
+    ) -> Result<Self, Error> {
+        let fft_len = settings.fft_len;
+        if !fft_len.is_power_of_two() {
+            return Err(Error::NotAPowerOfTwo);
+        }
+        if range_bin >= range_fft.num_bins() {
+            return Err(Error::InvalidParameter);
+        }
+        if samples.len() != fft_len {
+            return Err(Error::BufferWrongSize(samples.len(), fft_len));
+        }
+        if matrix.is_empty() || !matrix.len().is_multiple_of(fft_len) {
+            return Err(Error::BufferWrongSize(matrix.len(), fft_len));
+        }
+
+        let window = WindowTable::new(settings.window, fft_len)?;
+
+        let sample_time_s = match settings.slow_time {
+            SlowTime::Chirps => config.chirp_repetition_time_s,
+            SlowTime::Frames => config.frame_repetition_time_s,
+        };
+        let velocity_resolution_mps = (config.wavelength_m() / (2.0 * fft_len as f64 * sample_time_s)) as f32;
+
+        // Normalize to the ADC full scale, independent of the number of samples per chirp and the FFT length
+        let scale = config.num_samples_per_chirp as f32 * fft_len as f32;
+
+        samples.fill(Complex32::new(0.0, 0.0));
+        matrix.fill(0.0);
+
+        Ok(Spectrogram {
+            range_fft: range_fft.clone(),
+            settings,
+            window,
+            range_bin,
+            velocity_resolution_mps,
+            normalization: 1.0 / (scale * scale),
+            samples,
+            num_samples: 0,
+            matrix,
+            head: 0,
+            num_columns: 0,
+        })
+    }
+
+    pub fn settings(&self) -> &SpectrogramSettings {
+        &self.settings
+    }
+
+    /// The size of the workspace required for [`Spectrogram::process_frame()`]: the range FFT scratch buffer and the short-time FFT.
+    pub fn workspace_size(&self) -> usize {
+        self.range_fft.fft_len() + self.settings.fft_len
+    }
+
+    pub fn range_bin(&self) -> usize {
+        self.range_bin
+    }
+
+    pub fn range_m(&self) -> f32 {
+        self.range_fft.bin_to_range_m(self.range_bin)
+    }
+
+    /// Moves the range gate to another range bin.
+    pub fn set_range_bin(&mut self, range_bin: usize) -> Result<(), Error> {
+        if range_bin >= self.range_fft.num_bins() {
+            return Err(Error::InvalidParameter);
+        }
+        self.range_bin = range_bin;
+        Ok(())
+    }
+
+    /// Moves the range gate to the range bin closest to a distance, e.g. the range of a tracked target.
+    pub fn set_range_m(&mut self, range_m: f32) -> Result<(), Error> {
+        if range_m.is_nan() || range_m < 0.0 {
+            return Err(Error::InvalidParameter);
+        }
+        self.set_range_bin((range_m / self.range_fft.range_resolution_m() + 0.5) as usize)
+    }
+
+    pub fn num_velocity_bins(&self) -> usize {
+        self.settings.fft_len
+    }
+
+    /// The velocity covered by a single velocity bin, in m/s.
+    pub fn velocity_resolution_mps(&self) -> f32 {
+        self.velocity_resolution_mps
+    }
+
+    /// The radial velocity of a velocity bin, in m/s.
+    pub fn bin_to_velocity_mps(&self, bin: usize) -> f32 {
+        (bin as f32 - (self.settings.fft_len / 2) as f32) * self.velocity_resolution_mps
+    }
+
+    /// Returns the labels of all velocity bins, in m/s.
+    pub fn velocity_bins(&self) -> impl Iterator<Item = f32> + '_ {
+        (0..self.settings.fft_len).map(|bin| self.bin_to_velocity_mps(bin))
+    }
+
+    /// The maximum number of columns (frames) of the spectrogram.
+    pub fn capacity(&self) -> usize {
+        self.matrix.len() / self.settings.fft_len
+    }
+
+    /// The number of columns computed so far, up to the capacity.
+    pub fn num_columns(&self) -> usize {
+        self.num_columns
+    }
+
+    /// Discards all samples and columns, e.g. when switching to another target.
+    pub fn reset(&mut self) {
+        self.samples.fill(Complex32::new(0.0, 0.0));
+        self.matrix.fill(0.0);
+        self.num_samples = 0;
+        self.head = 0;
+        self.num_columns = 0;
+    }
+
+    /// Adds the samples of a frame, and returns the new column once enough samples for the FFT have been collected.
+    ///
+    /// The workspace must have the size returned by [`Spectrogram::workspace_size()`].
+    pub fn process_frame(&mut self, frame: &Frame, workspace: &mut [Complex32]) -> Result<Option<&[f32]>, Error> {
+        if workspace.len() != self.workspace_size() {
+            return Err(Error::BufferWrongSize(workspace.len(), self.workspace_size()));
+        }
+
+        let (scratch, spectrum) = workspace.split_at_mut(self.range_fft.fft_len());
+        let fft_len = self.settings.fft_len;
+
+        match self.settings.slow_time {
+            SlowTime::Chirps => {
+                for chirp in 0..frame.num_chirps() {
+                    self.range_fft.process_chirp(frame, 0, chirp, scratch)?;
+                    self.samples[self.num_samples % fft_len] = scratch[self.range_bin];
+                    self.num_samples += 1;
+                }
+            }
+            SlowTime::Frames => {
+                let mut mean = Complex32::new(0.0, 0.0);
+                for chirp in 0..frame.num_chirps() {
+                    self.range_fft.process_chirp(frame, 0, chirp, scratch)?;
+                    mean += scratch[self.range_bin];
+                }
+                self.samples[self.num_samples % fft_len] = mean / frame.num_chirps() as f32;
+                self.num_samples += 1;
+            }
+        }
+
+        if self.num_samples < fft_len {
+            return Ok(None);
+        }
+        // Keep the counter bounded, without changing the position in the ring buffer
+        self.num_samples = fft_len + self.num_samples % fft_len;
+
+        // Oldest sample first
+        let start = self.num_samples % fft_len;
+        let mean = if self.settings.remove_static {
+            self.samples.iter().sum::<Complex32>() / fft_len as f32
+        } else {
+            Complex32::new(0.0, 0.0)
+        };
+        for ((n, coefficient), value) in self.window.coefficients().iter().enumerate().zip(spectrum.iter_mut()) {
+            *value = (self.samples[(start + n) % fft_len] - mean) * *coefficient;
+        }
+
+        fft(spectrum)?;
+        fft_shift(spectrum);
+
+        let column = &mut self.matrix[self.head * fft_len..(self.head + 1) * fft_len];
+        for (output, value) in column.iter_mut().zip(spectrum.iter()) {
+            let power = value.norm_sqr() * self.normalization;
+            *output = if self.settings.decibel { 10.0 * log10f(power.max(1e-20)) } else { power };
+        }
+
+        let capacity = self.capacity();
+        self.head = (self.head + 1) % capacity;
+        self.num_columns = (self.num_columns + 1).min(capacity);
+
+        let newest = (self.head + capacity - 1) % capacity;
+        Ok(Some(&self.matrix[newest * fft_len..(newest + 1) * fft_len]))
+    }
+
+    /// Returns a column of the spectrogram, with `0` being the oldest one.
+    ///
+    /// Panics if the column has not been computed yet.
+    pub fn column(&self, index: usize) -> &[f32] {
+        assert!(index < self.num_columns);
+        let capacity = self.capacity();
+        let column = (self.head + capacity - self.num_columns + index) % capacity;
+        &self.matrix[column * self.settings.fft_len..(column + 1) * self.settings.fft_len]
+    }
+
+    /// Returns an iterator over the columns of the spectrogram, from the oldest to the newest.
+    pub fn columns(&self) -> impl Iterator<Item = &[f32]> + '_ {
+        (0..self.num_columns).map(|index| self.column(index))
+    }
+
+    /// Copies the spectrogram in time order (oldest first) to the output, with the shape `[num_columns, num_velocity_bins]`,
+    /// e.g. as input for feature extraction. Returns the number of columns copied.
+    pub fn copy_to(&self, output: &mut [f32]) -> Result<usize, Error> {
+        let size = self.num_columns * self.settings.fft_len;
+        if output.len() < size {
+            return Err(Error::OutputWrongSize(output.len(), size));
+        }
+
+        for (column, chunk) in self.columns().zip(output.chunks_exact_mut(self.settings.fft_len)) {
+            chunk.copy_from_slice(column);
+        }
+
+        Ok(self.num_columns)
+    }
+
+    /// Returns the spectrogram in time order (oldest first) as an array with the shape `[num_columns, num_velocity_bins]`.
+    ///
+    /// This function requires the alloc feature, since it dynamically allocates memory for the output.
+    #[cfg(feature = "alloc")]
+    pub fn to_array(&self) -> Array2<f32> {
+        let mut output = Array2::zeros((self.num_columns, self.settings.fft_len));
+        // Unwrap is safe, since the array is created in standard layout with the size of the spectrogram
+        self.copy_to(output.as_slice_mut().unwrap()).unwrap();
+        output
+    }
+}
@@ -0,0 +1,430 @@
+//! Multi-target tracking of detections in the horizontal plane.
+//!
+//! The processing chain per frame is:
+//! 1. Convert the CFAR detections with their azimuth into [`Measurement`]s.
+//! 2. Cluster the measurements with [`dbscan()`], since a single person usually causes multiple detections,
+//!    and reduce each cluster to its centroid with [`cluster_centroids()`].
+//! 3. Update the [`Tracker`] with the centroids, which associates them to tracks within a gate, and filters each track with a
+//!    constant-velocity Kalman filter.
+//!
+//! The time between updates is the `frame_repetition_time_s` of the config.
+
+use libm::{cosf, powf, sinf, sqrtf};
+
+use super::Detection;
+use crate::config::Config;
+use crate::error::Error;
+
+/// A detection in Cartesian coordinates, with `y` along the boresight and `x` to the side of positive azimuth.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Measurement {
+    pub x_m: f32,
+    pub y_m: f32,
+    pub velocity_mps: f32,
+    pub snr_db: f32,
+}
+
+impl Measurement {
+    /// Converts a detection and its azimuth angle (see [`super::AntennaGeometry::estimate_detection()`]) into Cartesian coordinates.
+    pub fn from_detection(detection: &Detection, azimuth_rad: f32) -> Self {
+        Measurement {
+            x_m: detection.range_m * sinf(azimuth_rad),
+            y_m: detection.range_m * cosf(azimuth_rad),
+            velocity_mps: detection.velocity_mps,
+            snr_db: detection.snr_db,
+        }
+    }
+}
+
+/// Settings of the DBSCAN clustering.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClusterSettings {
+    /// The maximum distance between neighbors, in meters.
+    pub epsilon_m: f32,
+    /// The distance in meters that corresponds to a velocity difference of 1 m/s, 0.0 to ignore the velocity.
+    pub velocity_scale_m: f32,
+    /// The minimum number of neighbors (including the point itself) of a core point.
+    pub min_points: usize,
+}
+
+impl Default for ClusterSettings {
+    fn default() -> Self {
+        ClusterSettings {
+            epsilon_m: 0.4,
+            velocity_scale_m: 0.2,
+            min_points: 2,
+        }
+    }
+}
+
+impl ClusterSettings {
+    fn neighbors(&self, a: &Measurement, b: &Measurement) -> bool {
+        let dx = a.x_m - b.x_m;
+        let dy = a.y_m - b.y_m;
+        let dv = (a.velocity_mps - b.velocity_mps) * self.velocity_scale_m;
+        dx * dx + dy * dy + dv * dv <= self.epsilon_m * self.epsilon_m
+    }
+
+    fn is_core(&self, points: &[Measurement], index: usize) -> bool {
+        points.iter().filter(|point| self.neighbors(&points[index], point)).count() >= self.min_points
+    }
+}
+
+/// Clusters the measurements with DBSCAN, and returns the number of clusters.
+///
+/// The cluster of each measurement is written to the labels, `None` for noise.
+/// Clusters are expanded without a queue or a buffer of core flags: every pass over a cluster of `k` points recomputes whether
+/// each of them is a core point (`O(n)` each), and adds their neighbors, until a pass adds no point.
+/// A cluster costs `O(n * k^2)` in the worst case (a chain of points, where each pass adds a single point), so `O(n^3)` in total.
+/// This is fine for the few dozen detections of a frame, but not for large point clouds.
+pub fn dbscan(points: &[Measurement], settings: &ClusterSettings, labels: &mut [Option<usize>]) -> Result<usize, Error> {
+    if labels.len() != points.len() {
+        return Err(Error::OutputWrongSize(labels.len(), points.len()));
+    }
+
+    labels.fill(None);
+    let mut num_clusters = 0;
+
+    for seed in 0..points.len() {
+        if labels[seed].is_some() || !settings.is_core(points, seed) {
+            continue;
+        }
+
+        let cluster = num_clusters;
+        num_clusters += 1;
+        labels[seed] = Some(cluster);
+
+        // Add the neighbors of all core points of the cluster, until the cluster does not grow anymore
+        let mut grown = true;
+        while grown {
+            grown = false;
+            for core in 0..points.len() {
+                if labels[core] != Some(cluster) || !settings.is_core(points, core) {
+                    continue;
+                }
+                for (point, label) in points.iter().zip(labels.iter_mut()) {
+                    if label.is_none() && settings.neighbors(&points[core], point) {
+                        *label = Some(cluster);
+                        grown = true;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(num_clusters)
+}
+
+/// Computes the SNR-weighted centroid of each cluster, and returns the number of centroids written to the output.
+///
+/// The SNR of a centroid is the highest SNR of the cluster. Clusters beyond the length of the output are dropped.
+pub fn cluster_centroids(
+    points: &[Measurement],
+    labels: &[Option<usize>],
+    num_clusters: usize,
+    output: &mut [Measurement],
+) -> Result<usize, Error> {
+    if labels.len() != points.len() {
+        return Err(Error::BufferWrongSize(labels.len(), points.len()));
+    }
+
+    let count = num_clusters.min(output.len());
+    for (cluster, centroid) in output.iter_mut().enumerate().take(count) {
+        let mut weight_sum = 0.0;
+        *centroid = Measurement {
+            snr_db: f32::NEG_INFINITY,
+            ..Default::default()
+        };
+
+        for (point, _) in points.iter().zip(labels).filter(|(_, label)| **label == Some(cluster)) {
+            // Weight by linear SNR, so that the strongest detections dominate
+            let weight = powf(10.0, point.snr_db / 10.0).max(f32::MIN_POSITIVE);
+            centroid.x_m += point.x_m * weight;
+            centroid.y_m += point.y_m * weight;
+            centroid.velocity_mps += point.velocity_mps * weight;
+            centroid.snr_db = centroid.snr_db.max(point.snr_db);
+            weight_sum += weight;
+        }
+
+        centroid.x_m /= weight_sum;
+        centroid.y_m /= weight_sum;
+        centroid.velocity_mps /= weight_sum;
+    }
+
+    Ok(count)
+}
+
+/// Settings of a [`Tracker`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackerSettings {
+    /// Standard deviation of the acceleration of targets, in m/s², the process noise of the Kalman filter.
+    pub acceleration_std_mps2: f32,
+    /// Standard deviation of the measured positions, in meters, the measurement noise of the Kalman filter.
+    pub measurement_std_m: f32,
+    /// The maximum squared Mahalanobis distance of a measurement to a track (9.21 is the 99% gate for two dimensions).
+    pub gate: f32,
+    /// The number of consecutive hits after which a tentative track is confirmed.
+    pub confirm_hits: u16,
+    /// The number of consecutive misses after which a confirmed track is deleted, tentative tracks are deleted on the first miss.
+    pub max_misses: u16,
+}
+
+impl Default for TrackerSettings {
+    fn default() -> Self {
+        TrackerSettings {
+            acceleration_std_mps2: 2.0,
+            measurement_std_m: 0.15,
+            gate: 9.21,
+            confirm_hits: 3,
+            max_misses: 5,
+        }
+    }
+}
+
+/// The lifecycle state of a track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackState {
+    /// A new track, which is not yet reported until confirmed.
+    Tentative,
+    Confirmed,
+}
+
+/// Constant-velocity Kalman filter of one axis, with the state `[position, velocity]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AxisFilter {
+    position: f32,
+    velocity: f32,
+    covariance: [[f32; 2]; 2],
+}
+
+impl AxisFilter {
+    fn new(position: f32, position_variance: f32, velocity_variance: f32) -> Self {
+        AxisFilter {
+            position,
+            velocity: 0.0,
+            covariance: [[position_variance, 0.0], [0.0, velocity_variance]],
+        }
+    }
+
+    /// Predicts the state after `dt` with white-noise acceleration of the given variance.
+    fn predict(&mut self, dt: f32, acceleration_variance: f32) {
+        let [[p00, p01], [p10, p11]] = self.covariance;
+        self.position += self.velocity * dt;
+
+        let q00 = acceleration_variance * dt * dt * dt * dt / 4.0;
+        let q01 = acceleration_variance * dt * dt * dt / 2.0;
+        let q11 = acceleration_variance * dt * dt;
+        self.covariance = [
+            [p00 + dt * (p10 + p01) + dt * dt * p11 + q00, p01 + dt * p11 + q01],
+            [p10 + dt * p11 + q01, p11 + q11],
+        ];
+    }
+
+    /// The innovation and its variance for a measured position.
+    fn innovation(&self, measurement: f32, measurement_variance: f32) -> (f32, f32) {
+        (measurement - self.position, self.covariance[0][0] + measurement_variance)
+    }
+
+    fn update(&mut self, measurement: f32, measurement_variance: f32) {
+        let (innovation, variance) = self.innovation(measurement, measurement_variance);
+        let [[p00, p01], [p10, p11]] = self.covariance;
+        let gain = [p00 / variance, p10 / variance];
+
+        self.position += gain[0] * innovation;
+        self.velocity += gain[1] * innovation;
+        self.covariance = [
+            [(1.0 - gain[0]) * p00, (1.0 - gain[0]) * p01],
+            [p10 - gain[1] * p00, p11 - gain[1] * p01],
+        ];
+    }
+}
+
+/// A tracked target, with the smoothed position and velocity of its Kalman filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Track {
+    id: u32,
+    state: TrackState,
+    x: AxisFilter,
+    y: AxisFilter,
+    hits: u16,
+    misses: u16,
+    age: u32,
+}
+
+impl Track {
+    /// A unique ID, which stays the same for the lifetime of the track.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn state(&self) -> TrackState {
+        self.state
+    }
+
+    pub fn x_m(&self) -> f32 {
+        self.x.position
+    }
+
+    pub fn y_m(&self) -> f32 {
+        self.y.position
+    }
+
+    pub fn vx_mps(&self) -> f32 {
+        self.x.velocity
+    }
+
+    pub fn vy_mps(&self) -> f32 {
+        self.y.velocity
+    }
+
+    /// The distance from the sensor, in meters.
+    pub fn range_m(&self) -> f32 {
+        sqrtf(self.x.position * self.x.position + self.y.position * self.y.position)
+    }
+
+    /// The number of frames since the birth of the track.
+    pub fn age(&self) -> u32 {
+        self.age
+    }
+
+    /// The number of consecutive frames without an associated measurement.
+    pub fn misses(&self) -> u16 {
+        self.misses
+    }
+
+    /// The squared Mahalanobis distance of a measurement to the predicted position of the track.
+    fn distance(&self, measurement: &Measurement, measurement_variance: f32) -> f32 {
+        let (dx, sx) = self.x.innovation(measurement.x_m, measurement_variance);
+        let (dy, sy) = self.y.innovation(measurement.y_m, measurement_variance);
+        dx * dx / sx + dy * dy / sy
+    }
+}
+
+/// Tracks up to `MAX_TRACKS` targets, with all tracks stored inline.
+///
+/// Every update predicts all tracks by one frame, associates the measurements to the tracks greedily by the smallest Mahalanobis distance within the gate,
+/// and starts a tentative track for every measurement that is not associated. New tracks are dropped if all slots are in use.
+#[derive(Debug, Clone)]
+pub struct Tracker<const MAX_TRACKS: usize> {
+    settings: TrackerSettings,
+    frame_time_s: f32,
+    tracks: [Option<Track>; MAX_TRACKS],
+    next_id: u32,
+}
+
+impl<const MAX_TRACKS: usize> Tracker<MAX_TRACKS> {
+    pub fn new(config: &Config, settings: TrackerSettings) -> Self {
+        Tracker {
+            settings,
+            frame_time_s: config.frame_repetition_time_s as f32,
+            tracks: [None; MAX_TRACKS],
+            next_id: 0,
+        }
+    }
+
+    pub fn settings(&self) -> &TrackerSettings {
+        &self.settings
+    }
+
+    /// Deletes all tracks.
+    pub fn reset(&mut self) {
+        self.tracks = [None; MAX_TRACKS];
+    }
+
+    /// Returns the confirmed tracks.
+    pub fn tracks(&self) -> impl Iterator<Item = &Track> {
+        self.tracks
+            .iter()
+            .flatten()
+            .filter(|track| track.state == TrackState::Confirmed)
+    }
+
+    /// Returns all tracks, including tentative ones.
+    pub fn all_tracks(&self) -> impl Iterator<Item = &Track> {
+        self.tracks.iter().flatten()
+    }
+
+    /// Updates the tracks with the measurements (e.g. cluster centroids) of a frame.
+    ///
+    /// All measurements are considered, but at most one per track, and new tracks only as long as slots are free.
+    pub fn update(&mut self, measurements: &[Measurement]) {
+        let settings = self.settings;
+        let acceleration_variance = settings.acceleration_std_mps2 * settings.acceleration_std_mps2;
+        let measurement_variance = settings.measurement_std_m * settings.measurement_std_m;
+
+        for track in self.tracks.iter_mut().flatten() {
+            track.x.predict(self.frame_time_s, acceleration_variance);
+            track.y.predict(self.frame_time_s, acceleration_variance);
+            track.age = track.age.saturating_add(1);
+        }
+
+        // Greedy nearest-neighbor association, one measurement per track.
+        // The measurement of each track is stored per slot, so that any number of measurements can be associated without a buffer.
+        let mut associated: [Option<usize>; MAX_TRACKS] = [None; MAX_TRACKS];
+        let used = |associated: &[Option<usize>; MAX_TRACKS], index: usize| associated.contains(&Some(index));
+        loop {
+            let mut best: Option<(usize, usize, f32)> = None;
+            for (slot, track) in self.tracks.iter().enumerate() {
+                let Some(track) = track else { continue };
+                if associated[slot].is_some() {
+                    continue;
+                }
+                for (index, measurement) in measurements.iter().enumerate() {
+                    if used(&associated, index) {
+                        continue;
+                    }
+                    let distance = track.distance(measurement, measurement_variance);
+                    if distance <= settings.gate && best.is_none_or(|(_, _, best)| distance < best) {
+                        best = Some((slot, index, distance));
+                    }
+                }
+            }
+
+            let Some((slot, index, _)) = best else { break };
+            // Unwrap is safe, since only occupied slots are selected
+            let track = self.tracks[slot].as_mut().unwrap();
+            track.x.update(measurements[index].x_m, measurement_variance);
+            track.y.update(measurements[index].y_m, measurement_variance);
+            track.hits = track.hits.saturating_add(1);
+            track.misses = 0;
+            if track.hits >= settings.confirm_hits {
+                track.state = TrackState::Confirmed;
+            }
+            associated[slot] = Some(index);
+        }
+
+        // Track deletion
+        for (slot, associated) in self.tracks.iter_mut().zip(associated) {
+            let Some(track) = slot else { continue };
+            if associated.is_some() {
+                continue;
+            }
+            track.misses = track.misses.saturating_add(1);
+            track.hits = 0;
+            if track.state == TrackState::Tentative || track.misses > settings.max_misses {
+                *slot = None;
+            }
+        }
+
+        // Track birth from unassociated measurements
+        for (index, measurement) in measurements.iter().enumerate() {
+            if used(&associated, index) {
+                continue;
+            }
+            let Some(slot) = self.tracks.iter_mut().find(|slot| slot.is_none()) else { break };
+
+            // The initial velocity is unknown, so its variance covers walking speeds
+            let velocity_variance = 4.0;
+            *slot = Some(Track {
+                id: self.next_id,
+                state: if settings.confirm_hits <= 1 { TrackState::Confirmed } else { TrackState::Tentative },
+                x: AxisFilter::new(measurement.x_m, measurement_variance, velocity_variance),
+                y: AxisFilter::new(measurement.y_m, measurement_variance, velocity_variance),
+                hits: 1,
+                misses: 0,
+                age: 0,
+            });
+            self.next_id = self.next_id.wrapping_add(1);
+        }
+    }
+}
@@ -0,0 +1,377 @@
+//! Vital-sign extraction (breathing and heart rate) of a stationary person in front of the sensor.
+//!
+//! The chest motion modulates the phase of the range bin of the person: a displacement of `d` changes the phase by `4 * pi * d / wavelength`.
+//! For each frame, the phase of the target range bin (averaged over all chirps of the first antenna) is unwrapped and converted to a displacement,
+//! which is band-pass filtered for respiration and heart rate. The rates are estimated from the autocorrelation of the filtered signals.
+//!
+//! # Supported configurations
+//!
+//! Each frame yields a single sample of the displacement, so the sample rate is `1 / frame_repetition_time_s`,
+//! while the number of chirps and samples per chirp only affect the SNR of the phase.
+//! - Breathing requires a frame time of at most [`MAX_BREATHING_FRAME_TIME_S`] (a frame rate of at least 2 Hz).
+//! - Heart rate requires a frame time of at most [`MAX_HEART_FRAME_TIME_S`] (a frame rate of at least 10 Hz),
+//!   otherwise heart rate estimation is disabled: [`VitalSigns::heart_rate_supported()`] and
+//!   [`VitalSignsEstimate::heart_rate_supported`] are `false` (and a warning is logged with the `debug` feature).
+//!
+//! The person must stay still within the range window, since the target range bin is selected once on the first frame after a reset.
+
+use core::f32::consts::PI;
+
+use libm::{cosf, sinf, sqrtf};
+use num_complex::Complex32;
+
+#[cfg(feature = "debug")]
+use log::warn;
+
+use super::{Frame, RangeFft};
+use crate::config::Config;
+use crate::error::Error;
+
+/// The longest frame time that still samples the breathing band.
+pub const MAX_BREATHING_FRAME_TIME_S: f64 = 0.5;
+
+/// The longest frame time that samples the heart-rate band with enough margin for the filters.
+pub const MAX_HEART_FRAME_TIME_S: f64 = 0.1;
+
+/// A second-order IIR filter (biquad) in transposed direct form II, with coefficients from the RBJ audio EQ cookbook.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Biquad {
+    b: [f32; 3],
+    a: [f32; 2],
+    state: [f32; 2],
+}
+
+impl Biquad {
+    /// A Butterworth low-pass filter with the given cutoff frequency.
+    pub fn low_pass(sample_rate_hz: f32, cutoff_hz: f32) -> Self {
+        let (cos, alpha) = Self::prewarp(sample_rate_hz, cutoff_hz);
+        Self::normalized([(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    /// A Butterworth high-pass filter with the given cutoff frequency.
+    pub fn high_pass(sample_rate_hz: f32, cutoff_hz: f32) -> Self {
+        let (cos, alpha) = Self::prewarp(sample_rate_hz, cutoff_hz);
+        Self::normalized([(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    /// Returns `cos(w0)` and `sin(w0) / (2 * Q)` with a Butterworth Q of `1 / sqrt(2)`.
+    fn prewarp(sample_rate_hz: f32, cutoff_hz: f32) -> (f32, f32) {
+        let w0 = 2.0 * PI * cutoff_hz / sample_rate_hz;
+        (cosf(w0), sinf(w0) / sqrtf(2.0))
+    }
+
+    fn normalized(b: [f32; 3], a: [f32; 3]) -> Self {
+        Biquad {
+            b: [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            a: [a[1] / a[0], a[2] / a[0]],
+            state: [0.0; 2],
+        }
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let output = self.b[0] * input + self.state[0];
+        self.state[0] = self.b[1] * input - self.a[0] * output + self.state[1];
+        self.state[1] = self.b[2] * input - self.a[1] * output;
+        output
+    }
+
+    pub fn reset(&mut self) {
+        self.state = [0.0; 2];
+    }
+}
+
+/// A band-pass filter made of two high-pass and two low-pass biquads, for a steep roll-off
+/// (breathing is much stronger than the heart beat, and must be suppressed in the heart-rate band).
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BandPass {
+    stages: [Biquad; 4],
+}
+
+impl BandPass {
+    fn new(sample_rate_hz: f32, (low_hz, high_hz): (f32, f32)) -> Self {
+        let high_pass = Biquad::high_pass(sample_rate_hz, low_hz);
+        let low_pass = Biquad::low_pass(sample_rate_hz, high_hz);
+        BandPass {
+            stages: [high_pass, high_pass, low_pass, low_pass],
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        self.stages.iter_mut().fold(input, |value, stage| stage.process(value))
+    }
+
+    fn reset(&mut self) {
+        self.stages.iter_mut().for_each(Biquad::reset);
+    }
+}
+
+/// Tuning parameters of [`VitalSigns`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VitalSignsSettings {
+    /// The range window in which the person is searched.
+    pub min_range_m: f32,
+    pub max_range_m: f32,
+    /// The pass band of the respiration filter, in Hz.
+    pub breathing_band_hz: (f32, f32),
+    /// The pass band of the heart-rate filter, in Hz.
+    pub heart_band_hz: (f32, f32),
+}
+
+impl Default for VitalSignsSettings {
+    fn default() -> Self {
+        VitalSignsSettings {
+            min_range_m: 0.3,
+            max_range_m: 2.0,
+            breathing_band_hz: (0.1, 0.5),
+            heart_band_hz: (0.8, 2.0),
+        }
+    }
+}
+
+/// An estimated rate, with the normalized autocorrelation at the period of the rate as confidence (`0.0..=1.0`).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RateEstimate {
+    pub rate_per_min: f32,
+    pub confidence: f32,
+}
+
+/// Breathing and heart rate of a stationary person, each `None` until enough frames have been processed (or if heart rate is not supported).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct VitalSignsEstimate {
+    pub breathing: Option<RateEstimate>,
+    pub heart: Option<RateEstimate>,
+    /// Whether the frame rate is high enough for heart rate estimation, to tell an unsupported config from a missing estimate.
+    pub heart_rate_supported: bool,
+}
+
+/// Extracts breathing and heart rate from consecutive frames, see the [module documentation](self) for the supported configurations.
+///
+/// The filtered signals are kept in caller-provided history buffers, which must cover at least two periods of the lowest rate of each band,
+/// see [`VitalSigns::min_history_len()`]. Longer histories give more stable estimates, but react slower to changes.
+#[derive(Debug)]
+pub struct VitalSigns<'a> {
+    range_fft: RangeFft,
+    settings: VitalSignsSettings,
+    sample_rate_hz: f32,
+    wavelength_mm: f32,
+    breathing_filter: BandPass,
+    heart_filter: Option<BandPass>,
+    breathing_history: &'a mut [f32],
+    heart_history: &'a mut [f32],
+    head: usize,
+    num_samples: usize,
+    target_bin: Option<usize>,
+    phase: f32,
+    previous: f32,
+}
+
+impl<'a> VitalSigns<'a> {
+    pub fn new(
+        config: &Config,
+        range_fft: &RangeFft,
+        settings: VitalSignsSettings,
+        breathing_history: &'a mut [f32],
+        heart_history: &'a mut [f32],
+    ) -> Result<Self, Error> {
+        if config.frame_repetition_time_s > MAX_BREATHING_FRAME_TIME_S {
+            return Err(Error::InvalidParameter);
+        }
+
+        let heart_supported = config.frame_repetition_time_s <= MAX_HEART_FRAME_TIME_S;
+        #[cfg(feature = "debug")]
+        if !heart_supported {
+            warn!(
+                "Frame repetition time of {} s is too long for heart rate estimation (max. {} s), only breathing is estimated",
+                config.frame_repetition_time_s, MAX_HEART_FRAME_TIME_S
+            );
+        }
+
+        let sample_rate_hz = (1.0 / config.frame_repetition_time_s) as f32;
+        for (low_hz, high_hz) in [settings.breathing_band_hz, settings.heart_band_hz] {
+            if !(low_hz > 0.0 && low_hz < high_hz) {
+                return Err(Error::InvalidParameter);
+            }
+        }
+        if settings.breathing_band_hz.1 >= sample_rate_hz / 2.0
+            || (heart_supported && settings.heart_band_hz.1 >= sample_rate_hz / 2.0)
+        {
+            return Err(Error::InvalidParameter);
+        }
+
+        let breathing_len = Self::min_history_len(sample_rate_hz, settings.breathing_band_hz);
+        if breathing_history.len() < breathing_len {
+            return Err(Error::BufferWrongSize(breathing_history.len(), breathing_len));
+        }
+        let heart_len = Self::min_history_len(sample_rate_hz, settings.heart_band_hz);
+        if heart_supported && heart_history.len() < heart_len {
+            return Err(Error::BufferWrongSize(heart_history.len(), heart_len));
+        }
+
+        let mut vitals = VitalSigns {
+            range_fft: range_fft.clone(),
+            settings,
+            sample_rate_hz,
+            wavelength_mm: (config.wavelength_m() * 1e3) as f32,
+            breathing_filter: BandPass::new(sample_rate_hz, settings.breathing_band_hz),
+            heart_filter: heart_supported.then(|| BandPass::new(sample_rate_hz, settings.heart_band_hz)),
+            breathing_history,
+            heart_history,
+            head: 0,
+            num_samples: 0,
+            target_bin: None,
+            phase: 0.0,
+            previous: 0.0,
+        };
+        vitals.reset();
+        Ok(vitals)
+    }
+
+    /// The minimum length of a history buffer for a band at the given sample rate (frame rate): two periods of the lower band edge.
+    pub fn min_history_len(sample_rate_hz: f32, band_hz: (f32, f32)) -> usize {
+        (2.0 * sample_rate_hz / band_hz.0) as usize + 1
+    }
+
+    /// Whether the frame rate of the config is high enough for heart rate estimation.
+    pub fn heart_rate_supported(&self) -> bool {
+        self.heart_filter.is_some()
+    }
+
+    /// The range bin of the person, selected on the first frame after a reset.
+    pub fn target_bin(&self) -> Option<usize> {
+        self.target_bin
+    }
+
+    /// The distance of the person, in meters.
+    pub fn target_range_m(&self) -> Option<f32> {
+        self.target_bin.map(|bin| self.range_fft.bin_to_range_m(bin))
+    }
+
+    /// Discards the history and the target range bin, e.g. when the person has moved or the radar has been reconfigured.
+    pub fn reset(&mut self) {
+        self.breathing_filter.reset();
+        if let Some(filter) = &mut self.heart_filter {
+            filter.reset();
+        }
+        self.breathing_history.fill(0.0);
+        self.heart_history.fill(0.0);
+        self.head = 0;
+        self.num_samples = 0;
+        self.target_bin = None;
+        self.phase = 0.0;
+        self.previous = 0.0;
+    }
+
+    /// Processes a frame, and returns the displacement of the chest since the first frame after a reset, in mm.
+    ///
+    /// The scratch buffer must have the length of the FFT of the range FFT.
+    pub fn process_frame(&mut self, frame: &Frame, scratch: &mut [Complex32]) -> Result<f32, Error> {
+        let first_bin = (self.settings.min_range_m / self.range_fft.range_resolution_m()) as usize;
+        let last_bin = ((self.settings.max_range_m / self.range_fft.range_resolution_m()) as usize)
+            .min(self.range_fft.num_bins() - 1);
+        if first_bin > last_bin {
+            return Err(Error::InvalidParameter);
+        }
+
+        // The target bin is averaged over all chirps, the whole profile is only needed to select the target
+        let target_bin = self.target_bin;
+        let mut value = Complex32::new(0.0, 0.0);
+        let mut profile_bin = first_bin;
+        let mut profile_power = 0.0;
+        for chirp in 0..frame.num_chirps() {
+            self.range_fft.process_chirp(frame, 0, chirp, scratch)?;
+            match target_bin {
+                Some(bin) => value += scratch[bin],
+                None if chirp == 0 => {
+                    for (bin, cell) in scratch.iter().enumerate().take(last_bin + 1).skip(first_bin) {
+                        if cell.norm_sqr() > profile_power {
+                            profile_bin = bin;
+                            profile_power = cell.norm_sqr();
+                        }
+                    }
+                    value = scratch[profile_bin];
+                }
+                None => value += scratch[profile_bin],
+            }
+        }
+        self.target_bin = Some(target_bin.unwrap_or(profile_bin));
+
+        // Unwrap the phase by limiting the change between frames to -pi..pi
+        let phase = value.arg();
+        if self.num_samples > 0 {
+            let mut delta = phase - self.previous;
+            while delta > PI {
+                delta -= 2.0 * PI;
+            }
+            while delta < -PI {
+                delta += 2.0 * PI;
+            }
+            self.phase += delta;
+        }
+        self.previous = phase;
+
+        let displacement_mm = self.phase * self.wavelength_mm / (4.0 * PI);
+
+        let breathing = self.breathing_filter.process(displacement_mm);
+        self.breathing_history[self.head % self.breathing_history.len()] = breathing;
+        if let Some(filter) = &mut self.heart_filter {
+            let heart = filter.process(displacement_mm);
+            self.heart_history[self.head % self.heart_history.len()] = heart;
+        }
+        self.head = self.head.wrapping_add(1);
+        self.num_samples = self.num_samples.saturating_add(1);
+
+        Ok(displacement_mm)
+    }
+
+    /// Estimates the rates from the history. This is more expensive than processing a frame, so it should be called less often (e.g. once a second).
+    pub fn estimate(&self) -> VitalSignsEstimate {
+        VitalSignsEstimate {
+            breathing: self.estimate_rate(self.breathing_history, self.settings.breathing_band_hz),
+            heart: if self.heart_rate_supported() {
+                self.estimate_rate(self.heart_history, self.settings.heart_band_hz)
+            } else {
+                None
+            },
+            heart_rate_supported: self.heart_rate_supported(),
+        }
+    }
+
+    /// Finds the period within the band with the highest normalized autocorrelation.
+    fn estimate_rate(&self, history: &[f32], (low_hz, high_hz): (f32, f32)) -> Option<RateEstimate> {
+        let len = history.len();
+        if self.num_samples < len {
+            return None;
+        }
+
+        // The oldest sample is at the head of the ring buffer
+        let start = self.head % len;
+        let sample = |index: usize| history[(start + index) % len];
+        let autocorrelation = |lag: usize| (0..len - lag).map(|n| sample(n) * sample(n + lag)).sum::<f32>();
+
+        let energy = autocorrelation(0);
+        if energy <= 0.0 {
+            return None;
+        }
+
+        let min_lag = ((self.sample_rate_hz / high_hz) as usize).max(1);
+        let max_lag = ((self.sample_rate_hz / low_hz) as usize).min(len - 2);
+        let (lag, correlation) = (min_lag..=max_lag)
+            .map(|lag| (lag, autocorrelation(lag) / energy))
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+        // Parabolic interpolation of the peak for a sub-sample period
+        let before = autocorrelation(lag - 1) / energy;
+        let after = autocorrelation(lag + 1) / energy;
+        let curvature = before - 2.0 * correlation + after;
+        let offset = if curvature < 0.0 { (0.5 * (before - after) / curvature).clamp(-0.5, 0.5) } else { 0.0 };
+        let period_s = (lag as f32 + offset) / self.sample_rate_hz;
+
+        // The autocorrelation is biased towards zero for larger lags, since fewer samples overlap
+        let confidence = correlation * len as f32 / (len - lag) as f32;
+
+        Some(RateEstimate {
+            rate_per_min: 60.0 / period_s,
+            confidence: confidence.clamp(0.0, 1.0),
+        })
+    }
+}
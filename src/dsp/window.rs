@@ -0,0 +1,93 @@
+use core::f32::consts::PI;
+use core::fmt::{Debug, Formatter};
+
+use libm::cosf;
+
+use crate::error::Error;
+
+/// The maximum length of a [`WindowTable`], which matches the maximum FFT length of the fixed-point FFTs.
+pub const MAX_WINDOW_LEN: usize = 1024;
+
+/// Window functions applied to the samples before an FFT, to reduce spectral leakage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    BlackmanHarris,
+}
+
+impl Window {
+    /// Returns the coefficient `n` of a symmetric window of length `len`.
+    pub fn coefficient(&self, n: usize, len: usize) -> f32 {
+        if len <= 1 {
+            return 1.0;
+        }
+
+        let x = 2.0 * PI * n as f32 / (len - 1) as f32;
+
+        match self {
+            Window::Rectangular => 1.0,
+            Window::Hann => 0.5 - 0.5 * cosf(x),
+            Window::Hamming => 0.54 - 0.46 * cosf(x),
+            Window::BlackmanHarris => {
+                0.35875 - 0.48829 * cosf(x) + 0.14128 * cosf(2.0 * x) - 0.01168 * cosf(3.0 * x)
+            }
+        }
+    }
+
+    /// Fills the output with the coefficients of a window with the length of the output.
+    pub fn fill(&self, output: &mut [f32]) {
+        let len = output.len();
+        for (n, coefficient) in output.iter_mut().enumerate() {
+            *coefficient = self.coefficient(n, len);
+        }
+    }
+}
+
+/// The precomputed coefficients of a window, so that they are not recomputed for every chirp.
+///
+/// The coefficients are stored inline, which takes `4 * MAX_WINDOW_LEN` bytes regardless of the length of the window.
+#[derive(Clone)]
+pub struct WindowTable {
+    window: Window,
+    len: usize,
+    coefficients: [f32; MAX_WINDOW_LEN],
+}
+
+impl WindowTable {
+    /// Computes the coefficients of a window of length `len`.
+    ///
+    /// Returns [`Error::InvalidParameter`] if the length exceeds [`MAX_WINDOW_LEN`].
+    pub fn new(window: Window, len: usize) -> Result<Self, Error> {
+        if len > MAX_WINDOW_LEN {
+            return Err(Error::InvalidParameter);
+        }
+
+        let mut coefficients = [0.0; MAX_WINDOW_LEN];
+        window.fill(&mut coefficients[..len]);
+
+        Ok(WindowTable {
+            window,
+            len,
+            coefficients,
+        })
+    }
+
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    pub fn coefficients(&self) -> &[f32] {
+        &self.coefficients[..self.len]
+    }
+}
+
+impl Debug for WindowTable {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        f.debug_struct("WindowTable")
+            .field("window", &self.window)
+            .field("len", &self.len)
+            .finish()
+    }
+}
@@ -0,0 +1,283 @@
+//! Zone occupancy, entry/exit events and line-crossing counts of tracked targets.
+//!
+//! Zones and lines are defined in the Cartesian coordinates of the [`tracking`](super::tracking) module,
+//! with `y` along the boresight and `x` to the side of positive azimuth.
+//! A [`ZoneMonitor`] is updated with the confirmed tracks of a [`Tracker`](super::tracking::Tracker) after every frame.
+
+use libm::{atan2f, sqrtf};
+
+use super::tracking::Track;
+use crate::error::Error;
+
+/// The area of a zone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZoneShape<'a> {
+    /// A polygon with vertices `(x_m, y_m)` in order (clockwise or counter-clockwise), which is closed implicitly.
+    Polygon(&'a [(f32, f32)]),
+    /// A sector of the field of view, limited by range and azimuth.
+    Sector {
+        min_range_m: f32,
+        max_range_m: f32,
+        min_azimuth_rad: f32,
+        max_azimuth_rad: f32,
+    },
+}
+
+impl ZoneShape<'_> {
+    /// Whether a point lies inside the zone.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        match *self {
+            ZoneShape::Polygon(vertices) => {
+                // Ray casting: count the edges crossed by a ray from the point towards positive x
+                let mut inside = false;
+                let mut previous = match vertices.last() {
+                    Some(&vertex) => vertex,
+                    None => return false,
+                };
+                for &(x1, y1) in vertices {
+                    let (x0, y0) = previous;
+                    if (y1 > y) != (y0 > y) && x < x0 + (y - y0) * (x1 - x0) / (y1 - y0) {
+                        inside = !inside;
+                    }
+                    previous = (x1, y1);
+                }
+                inside
+            }
+            ZoneShape::Sector {
+                min_range_m,
+                max_range_m,
+                min_azimuth_rad,
+                max_azimuth_rad,
+            } => {
+                let range = sqrtf(x * x + y * y);
+                let azimuth = atan2f(x, y);
+                (min_range_m..=max_range_m).contains(&range) && (min_azimuth_rad..=max_azimuth_rad).contains(&azimuth)
+            }
+        }
+    }
+}
+
+/// A counting line from `start` to `end`, with coordinates `(x_m, y_m)`.
+///
+/// Crossings from the right to the left side (looking from `start` to `end`) are counted as forward, the opposite as backward.
+/// Only crossings of the segment itself are counted, not of its extension, nor walking around one of its ends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Line {
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+}
+
+impl Line {
+    /// The side of a point (`true` for left), or `None` if the point is not beside the segment.
+    fn side(&self, x: f32, y: f32) -> Option<bool> {
+        let (dx, dy) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        let (px, py) = (x - self.start.0, y - self.start.1);
+        let length = dx * dx + dy * dy;
+        if length <= 0.0 || !(0.0..=1.0).contains(&((px * dx + py * dy) / length)) {
+            return None;
+        }
+        Some(dx * py - dy * px > 0.0)
+    }
+}
+
+/// Debounce settings of a [`ZoneMonitor`], in frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoneSettings {
+    /// The number of consecutive frames a track must be inside a zone to enter it.
+    pub enter_frames: u8,
+    /// The number of consecutive frames a track must be outside a zone to exit it.
+    pub exit_frames: u8,
+    /// The number of consecutive frames a track must be on the other side of a line to cross it.
+    pub crossing_frames: u8,
+}
+
+impl Default for ZoneSettings {
+    fn default() -> Self {
+        ZoneSettings {
+            enter_frames: 3,
+            exit_frames: 5,
+            crossing_frames: 3,
+        }
+    }
+}
+
+/// The direction of a line crossing, see [`Line`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossingDirection {
+    Forward,
+    Backward,
+}
+
+/// An event of a [`ZoneMonitor`], with the index of the zone or line and the ID of the track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoneEvent {
+    Entered { zone: usize, track_id: u32 },
+    /// Also reported if a track inside the zone is deleted.
+    Exited { zone: usize, track_id: u32 },
+    LineCrossed { line: usize, track_id: u32, direction: CrossingDirection },
+}
+
+/// The debounced state of a single track.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TrackZones<const MAX_ZONES: usize> {
+    id: u32,
+    seen: bool,
+    inside: [bool; MAX_ZONES],
+    zone_pending: [u8; MAX_ZONES],
+    side: [Option<bool>; MAX_ZONES],
+    line_pending: [u8; MAX_ZONES],
+}
+
+/// Monitors the occupancy of up to `MAX_ZONES` zones and the crossings of up to `MAX_ZONES` lines, for up to `MAX_TRACKS` tracks.
+#[derive(Debug, Clone)]
+pub struct ZoneMonitor<'a, const MAX_TRACKS: usize, const MAX_ZONES: usize> {
+    zones: &'a [ZoneShape<'a>],
+    lines: &'a [Line],
+    settings: ZoneSettings,
+    tracks: [Option<TrackZones<MAX_ZONES>>; MAX_TRACKS],
+    occupancy: [usize; MAX_ZONES],
+    crossings: [(u32, u32); MAX_ZONES],
+}
+
+impl<'a, const MAX_TRACKS: usize, const MAX_ZONES: usize> ZoneMonitor<'a, MAX_TRACKS, MAX_ZONES> {
+    pub fn new(zones: &'a [ZoneShape<'a>], lines: &'a [Line], settings: ZoneSettings) -> Result<Self, Error> {
+        if zones.len() > MAX_ZONES || lines.len() > MAX_ZONES {
+            return Err(Error::InvalidParameter);
+        }
+
+        Ok(ZoneMonitor {
+            zones,
+            lines,
+            settings,
+            tracks: [None; MAX_TRACKS],
+            occupancy: [0; MAX_ZONES],
+            crossings: [(0, 0); MAX_ZONES],
+        })
+    }
+
+    /// The number of tracks inside a zone.
+    pub fn occupancy(&self, zone: usize) -> usize {
+        self.occupancy[zone]
+    }
+
+    /// The number of forward and backward crossings of a line since creation or the last reset of the counts.
+    pub fn crossings(&self, line: usize) -> (u32, u32) {
+        self.crossings[line]
+    }
+
+    pub fn reset_crossings(&mut self) {
+        self.crossings = [(0, 0); MAX_ZONES];
+    }
+
+    /// Updates the zones with the tracks of a frame, and returns the number of events written.
+    ///
+    /// Events that do not fit into the output are dropped, but the occupancy and counts are updated regardless.
+    /// Tracks beyond `MAX_TRACKS` are ignored.
+    pub fn update<'t>(&mut self, tracks: impl IntoIterator<Item = &'t Track>, events: &mut [ZoneEvent]) -> usize {
+        let mut count = 0;
+        let mut emit = |event: ZoneEvent| {
+            if let Some(slot) = events.get_mut(count) {
+                *slot = event;
+                count += 1;
+            }
+        };
+
+        for state in self.tracks.iter_mut().flatten() {
+            state.seen = false;
+        }
+
+        for track in tracks {
+            let slot = match self.tracks.iter().position(|state| state.is_some_and(|state| state.id == track.id())) {
+                Some(slot) => slot,
+                None => match self.tracks.iter().position(Option::is_none) {
+                    Some(slot) => {
+                        self.tracks[slot] = Some(TrackZones {
+                            id: track.id(),
+                            seen: false,
+                            inside: [false; MAX_ZONES],
+                            zone_pending: [0; MAX_ZONES],
+                            side: [None; MAX_ZONES],
+                            line_pending: [0; MAX_ZONES],
+                        });
+                        slot
+                    }
+                    None => continue,
+                },
+            };
+            // Unwrap is safe, since the slot has been found or filled above
+            let state = self.tracks[slot].as_mut().unwrap();
+            state.seen = true;
+            let (x, y) = (track.x_m(), track.y_m());
+
+            for (zone, shape) in self.zones.iter().enumerate() {
+                let inside = shape.contains(x, y);
+                if inside == state.inside[zone] {
+                    state.zone_pending[zone] = 0;
+                    continue;
+                }
+
+                state.zone_pending[zone] = state.zone_pending[zone].saturating_add(1);
+                let frames = if inside { self.settings.enter_frames } else { self.settings.exit_frames };
+                if state.zone_pending[zone] >= frames {
+                    state.inside[zone] = inside;
+                    state.zone_pending[zone] = 0;
+                    if inside {
+                        self.occupancy[zone] += 1;
+                        emit(ZoneEvent::Entered { zone, track_id: state.id });
+                    } else {
+                        self.occupancy[zone] -= 1;
+                        emit(ZoneEvent::Exited { zone, track_id: state.id });
+                    }
+                }
+            }
+
+            for (line, segment) in self.lines.iter().enumerate() {
+                // Leaving the span of the segment forgets the side, so that walking around its end is not a crossing
+                let Some(side) = segment.side(x, y) else {
+                    state.side[line] = None;
+                    state.line_pending[line] = 0;
+                    continue;
+                };
+                let Some(previous) = state.side[line] else {
+                    state.side[line] = Some(side);
+                    continue;
+                };
+                if side == previous {
+                    state.line_pending[line] = 0;
+                    continue;
+                }
+
+                state.line_pending[line] = state.line_pending[line].saturating_add(1);
+                if state.line_pending[line] >= self.settings.crossing_frames {
+                    state.side[line] = Some(side);
+                    state.line_pending[line] = 0;
+                    let direction = if side {
+                        self.crossings[line].0 += 1;
+                        CrossingDirection::Forward
+                    } else {
+                        self.crossings[line].1 += 1;
+                        CrossingDirection::Backward
+                    };
+                    emit(ZoneEvent::LineCrossed { line, track_id: state.id, direction });
+                }
+            }
+        }
+
+        // Deleted tracks leave all zones immediately
+        for slot in self.tracks.iter_mut() {
+            let Some(state) = slot else { continue };
+            if state.seen {
+                continue;
+            }
+            for zone in 0..self.zones.len() {
+                if state.inside[zone] {
+                    self.occupancy[zone] -= 1;
+                    emit(ZoneEvent::Exited { zone, track_id: state.id });
+                }
+            }
+            *slot = None;
+        }
+
+        count
+    }
+}
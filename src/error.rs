@@ -1,8 +1,9 @@
 use core::fmt::{Debug, Display, Formatter, Result};
 use embedded_hal::digital::ErrorKind as DigitalErrorKind;
 use embedded_hal::spi::ErrorKind as SpiErrorKind;
+use embedded_io::ErrorKind as IoErrorKind;
 
-use crate::register::GSR0;
+use crate::register::{FSTAT, GSR0};
 
 #[derive(Debug)]
 pub enum Error {
@@ -15,7 +16,14 @@ pub enum Error {
     BufferWrongSize(usize, usize),
     OutputWrongSize(usize, usize),
     GlobalStatusRegisterError(GSR0),
-    ResetError
+    ResetError,
+    RegisterMismatch(u8, u32, u32),
+    BurstOutOfRange(u8, usize),
+    FifoStatusError(FSTAT),
+    ShapeMismatch,
+    InvalidParameter,
+    EndOfRecording,
+    Io(IoErrorKind),
 }
 
 impl Display for Error
@@ -32,6 +40,13 @@ impl Display for Error
             Error::OutputWrongSize(provided, expected) => write!(f, "Output buffer wrong size, provided: {}, expected: {}", provided, expected),
             Error::GlobalStatusRegisterError(gsr0) => write!(f, "Global status register error: {:?}", gsr0),
             Error::ResetError => write!(f, "Unable to perform reset."),
+            Error::RegisterMismatch(addr, expected, actual) => write!(f, "Register {:#04X} mismatch, expected: {:#08X}, actual: {:#08X}", addr, expected, actual),
+            Error::BurstOutOfRange(start, len) => write!(f, "Burst of {} registers starting at {:#04X} exceeds the address range", len, start),
+            Error::FifoStatusError(fstat) => write!(f, "FIFO status error: {:?}", fstat),
+            Error::ShapeMismatch => write!(f, "Frame shape does not match the configuration"),
+            Error::InvalidParameter => write!(f, "Parameter out of the valid range"),
+            Error::EndOfRecording => write!(f, "No more frames in the recording"),
+            Error::Io(kind) => write!(f, "I/O error: {:?}", kind),
         }
     }
 }
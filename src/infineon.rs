@@ -0,0 +1,242 @@
+//! Compatibility with the recordings of Infineon's desktop tools, to replay captures of the evaluation kits through this crate,
+//! and to open captures of this crate in the Infineon tools.
+//!
+//! This requires the infineon feature.
+//!
+//! # Supported formats
+//! - Radar Fusion GUI recordings: a directory with the device configuration in `config.json` and the frames in `radar.npy`,
+//!   with the shape `[num_frames, rx_antennas, num_chirps_per_frame, num_samples_per_chirp]`
+//!   and the samples as f32 normalized to `0.0..=1.0` of the ADC full scale, see [`read_fusion_recording()`] and [`write_fusion_recording()`].
+//! - Raw data files with a JSON sidecar: the samples as little-endian u16 without header, in the same order as `radar.npy`,
+//!   and the device configuration in the same JSON format, see [`read_raw()`] and [`write_raw()`].
+//!
+//! The device configuration is the `device_config` / `fmcw_single_shape` object of the Infineon Radar SDK,
+//! whose fields match the fields of [`Config`] (see [`config_from_json()`] and [`config_to_json()`]).
+//!
+//! # Limitations
+//! - The XML sidecars of older tool versions and the binary recording format of the `ifxRadar` SDK are not publicly documented,
+//!   so they are not supported: reading and writing them requires a specification of the formats from Infineon.
+//! - The layout of raw data exports is not publicly documented either, so the order of a capture should be checked before relying on it.
+//! - The JSON configuration has no register list, so the registers of a config read from JSON are zero.
+//!   Generate them with the bgt60-configurator-cli from the same JSON to configure a radar.
+//! - The JSON lists the numbers of the enabled antennas, while [`Config`] only stores their count:
+//!   reading takes the length of the lists, writing assumes consecutive antennas starting at RX1 and TX1.
+//!   The templates of the bgt60-configurator-cli (see `config/`) hold the count in a single entry instead (e.g. `[3]`),
+//!   read them with [`config_from_json_with()`] and [`AntennaList::Count`].
+//! - Fields that are not part of [`Config`] (`hp_cutoff_Hz`, `aaf_cutoff_Hz` and `mimo_mode`) are ignored when reading,
+//!   and written with fixed values (80 kHz, 500 kHz and `off`).
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::string::String;
+use std::vec::Vec;
+
+use ndarray::Array3;
+use serde_json::{Map, Value, json};
+
+use crate::config::{ADC_FULL_SCALE, Config};
+use crate::error::Error;
+use crate::npy::{FloatScale, frames_shape, read_frames, write_array};
+use crate::recording::std_io_error;
+
+/// The name of the device configuration in a Radar Fusion GUI recording.
+pub const FUSION_CONFIG_FILE: &str = "config.json";
+
+/// The name of the frames in a Radar Fusion GUI recording.
+pub const FUSION_DATA_FILE: &str = "radar.npy";
+
+/// How the `rx_antennas` and `tx_antennas` lists of a device configuration are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AntennaList {
+    /// The lists hold the numbers of the enabled antennas (e.g. `[1, 2, 3]`), as in the Infineon Radar SDK,
+    /// and are read by their length.
+    Numbers,
+    /// A list with a single entry holds the number of antennas (e.g. `[3]`), as in the templates of the bgt60-configurator-cli.
+    /// Longer lists are still read by their length.
+    Count,
+}
+
+/// Parses a device configuration in the JSON format of the Infineon Radar SDK.
+///
+/// The `fmcw_single_shape` object is searched in the whole document, so both a plain device configuration
+/// and the `config.json` of a Radar Fusion GUI recording are accepted. Returns [`Error::InvalidParameter`] if a field is missing or out of range.
+pub fn config_from_json(json: &str) -> Result<Config, Error> {
+    config_from_json_with(json, AntennaList::Numbers)
+}
+
+/// Parses a device configuration like [`config_from_json()`], with the given meaning of the antenna lists.
+pub fn config_from_json_with(json: &str, antenna_list: AntennaList) -> Result<Config, Error> {
+    let document: Value = serde_json::from_str(json).map_err(|_| Error::InvalidParameter)?;
+    let shape = find_object(&document, "fmcw_single_shape").ok_or(Error::InvalidParameter)?;
+
+    let count = |value: &Value| -> Result<u8, Error> {
+        value.as_u64().and_then(|count| u8::try_from(count).ok()).ok_or(Error::InvalidParameter)
+    };
+    let antennas = |key: &str| -> Result<u8, Error> {
+        match (shape.get(key), antenna_list) {
+            (Some(Value::Array(antennas)), AntennaList::Count) if antennas.len() == 1 => count(&antennas[0]),
+            (Some(Value::Array(antennas)), _) => u8::try_from(antennas.len()).map_err(|_| Error::InvalidParameter),
+            // A single antenna number or count instead of a list
+            (Some(value @ Value::Number(_)), AntennaList::Count) => count(value),
+            (Some(Value::Number(_)), AntennaList::Numbers) => Ok(1),
+            _ => Err(Error::InvalidParameter),
+        }
+    };
+    let integer = |keys: &[&str]| -> Result<u64, Error> {
+        keys.iter()
+            .find_map(|key| shape.get(*key))
+            .and_then(|value| value.as_u64().or_else(|| value.as_f64().filter(|value| *value >= 0.0).map(|value| value as u64)))
+            .ok_or(Error::InvalidParameter)
+    };
+    let float = |key: &str| -> Result<f64, Error> {
+        shape.get(key).and_then(Value::as_f64).ok_or(Error::InvalidParameter)
+    };
+    let narrow = |value: u64| -> Result<u8, Error> { u8::try_from(value).map_err(|_| Error::InvalidParameter) };
+
+    Ok(Config::new(
+        antennas("rx_antennas")?,
+        antennas("tx_antennas")?,
+        narrow(integer(&["tx_power_level"])?)?,
+        narrow(integer(&["if_gain_dB", "if_gain_db"])?)?,
+        integer(&["lower_frequency_Hz", "lower_frequency_hz"])?,
+        integer(&["upper_frequency_Hz", "upper_frequency_hz"])?,
+        narrow(integer(&["num_chirps_per_frame"])?)?,
+        u16::try_from(integer(&["num_samples_per_chirp"])?).map_err(|_| Error::InvalidParameter)?,
+        float("chirp_repetition_time_s")?,
+        float("frame_repetition_time_s")?,
+        u32::try_from(integer(&["sample_rate_Hz", "sample_rate_hz"])?).map_err(|_| Error::InvalidParameter)?,
+        [0; 38],
+    ))
+}
+
+/// Formats a config as a device configuration in the JSON format of the Infineon Radar SDK, as used by the Radar Fusion GUI.
+pub fn config_to_json(config: &Config) -> String {
+    let document = json!({
+        "device_config": {
+            "fmcw_single_shape": {
+                "rx_antennas": (1..=config.rx_antennas).collect::<Vec<_>>(),
+                "tx_antennas": (1..=config.tx_antennas).collect::<Vec<_>>(),
+                "tx_power_level": config.tx_power_level,
+                "if_gain_dB": config.if_gain_db,
+                "lower_frequency_Hz": config.lower_frequency_hz,
+                "upper_frequency_Hz": config.upper_frequency_hz,
+                "num_chirps_per_frame": config.num_chirps_per_frame,
+                "num_samples_per_chirp": config.num_samples_per_chirp,
+                "chirp_repetition_time_s": config.chirp_repetition_time_s,
+                "frame_repetition_time_s": config.frame_repetition_time_s,
+                "sample_rate_Hz": config.sample_rate_hz,
+                "hp_cutoff_Hz": 80000,
+                "aaf_cutoff_Hz": 500000,
+                "mimo_mode": "off"
+            }
+        }
+    });
+
+    // Unwrap is safe, since the document only contains strings and numbers
+    serde_json::to_string_pretty(&document).unwrap()
+}
+
+/// Reads a Radar Fusion GUI recording from its directory, and returns the config and the frames.
+///
+/// The scale applies to floating-point samples in `radar.npy` (see [`read_frames()`]): the Infineon tools and
+/// [`write_fusion_recording()`] write samples normalized to the ADC full scale, read them with [`FloatScale::Normalized`].
+/// Integer samples are read as ADC counts.
+pub fn read_fusion_recording(directory: impl AsRef<Path>, scale: FloatScale) -> Result<(Config, Vec<Array3<u16>>), Error> {
+    let directory = directory.as_ref();
+    let config = read_config(directory.join(FUSION_CONFIG_FILE))?;
+    let file = File::open(directory.join(FUSION_DATA_FILE)).map_err(std_io_error)?;
+    let frames = read_frames(BufReader::new(file), &config, scale)?;
+
+    Ok((config, frames))
+}
+
+/// Writes frames of the same config as a Radar Fusion GUI recording into a directory, which is created if needed.
+///
+/// The samples are written as f32 normalized to the ADC full scale, as in the recordings of the Infineon tools.
+pub fn write_fusion_recording(directory: impl AsRef<Path>, config: &Config, frames: &[Array3<u16>]) -> Result<(), Error> {
+    let shape = frames_shape(config, frames)?;
+    let directory = directory.as_ref();
+    fs::create_dir_all(directory).map_err(std_io_error)?;
+    fs::write(directory.join(FUSION_CONFIG_FILE), config_to_json(config)).map_err(std_io_error)?;
+
+    let samples = frames.iter().flatten().map(|&sample| sample as f32 / ADC_FULL_SCALE);
+    let mut writer = BufWriter::new(File::create(directory.join(FUSION_DATA_FILE)).map_err(std_io_error)?);
+    write_array(&mut writer, &shape, samples)?;
+    writer.flush().map_err(std_io_error)
+}
+
+/// Reads a raw data file with its JSON sidecar, and returns the config and the frames.
+///
+/// Returns [`Error::BufferWrongSize`] if the file does not hold a whole number of frames.
+pub fn read_raw(data: impl AsRef<Path>, sidecar: impl AsRef<Path>) -> Result<(Config, Vec<Array3<u16>>), Error> {
+    let config = read_config(sidecar)?;
+
+    let mut bytes = Vec::new();
+    File::open(data)
+        .and_then(|mut file| file.read_to_end(&mut bytes))
+        .map_err(std_io_error)?;
+
+    let frame_bytes = config.get_fifo_limit() * 2;
+    if frame_bytes == 0 || !bytes.len().is_multiple_of(frame_bytes) {
+        return Err(Error::BufferWrongSize(bytes.len(), frame_bytes));
+    }
+
+    let shape = (
+        config.rx_antennas as usize,
+        config.num_chirps_per_frame as usize,
+        config.num_samples_per_chirp as usize,
+    );
+    let frames = bytes
+        .chunks_exact(frame_bytes)
+        .map(|frame| {
+            let samples = frame.chunks_exact(2).map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]])).collect();
+            Array3::from_shape_vec(shape, samples).map_err(|_| Error::ShapeMismatch)
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((config, frames))
+}
+
+/// Writes frames of the same config as a raw data file with its JSON sidecar.
+pub fn write_raw(
+    data: impl AsRef<Path>,
+    sidecar: impl AsRef<Path>,
+    config: &Config,
+    frames: &[Array3<u16>],
+) -> Result<(), Error> {
+    let shape = (
+        config.rx_antennas as usize,
+        config.num_chirps_per_frame as usize,
+        config.num_samples_per_chirp as usize,
+    );
+    if frames.iter().any(|frame| frame.dim() != shape) {
+        return Err(Error::ShapeMismatch);
+    }
+
+    fs::write(sidecar, config_to_json(config)).map_err(std_io_error)?;
+
+    let mut writer = BufWriter::new(File::create(data).map_err(std_io_error)?);
+    for frame in frames {
+        let bytes: Vec<u8> = frame.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        writer.write_all(&bytes).map_err(std_io_error)?;
+    }
+    writer.flush().map_err(std_io_error)
+}
+
+fn read_config(path: impl AsRef<Path>) -> Result<Config, Error> {
+    let json = fs::read_to_string(path).map_err(std_io_error)?;
+    config_from_json(&json)
+}
+
+/// Searches an object with the given key in a JSON document, depth first.
+fn find_object<'a>(value: &'a Value, key: &str) -> Option<&'a Map<String, Value>> {
+    match value {
+        Value::Object(object) => object
+            .get(key)
+            .and_then(Value::as_object)
+            .or_else(|| object.values().find_map(|value| find_object(value, key))),
+        Value::Array(values) => values.iter().find_map(|value| find_object(value, key)),
+        _ => None,
+    }
+}
@@ -1,16 +1,31 @@
 #![deny(unsafe_code)]
 #![no_std]
-#![doc = include_str!("../README.md")]
+#![cfg_attr(not(doctest), doc = include_str!("../README.md"))]
 
 pub mod config;
+#[cfg(any(feature = "std", feature = "protocol"))]
+mod crc;
+#[cfg(feature = "dsp")]
+pub mod dsp;
 pub mod error;
+#[cfg(feature = "infineon")]
+pub mod infineon;
+#[cfg(feature = "std")]
+pub mod npy;
+#[cfg(feature = "protocol")]
+pub mod protocol;
+#[cfg(feature = "recording")]
+pub mod recording;
 pub mod register;
+#[cfg(feature = "std")]
+pub mod replay;
 
 use embedded_hal::digital::Error as DigitalError;
 use embedded_hal::digital::OutputPin;
 use embedded_hal_async::delay::DelayNs;
 use embedded_hal_async::digital::Wait;
 use embedded_hal_async::spi::Error as SpiError;
+use embedded_hal_async::spi::Operation;
 use embedded_hal_async::spi::SpiDevice;
 
 #[cfg(feature = "debug")]
@@ -18,6 +33,8 @@ use log::info;
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 #[cfg(feature = "alloc")]
 use alloc::vec;
 #[cfg(feature = "alloc")]
@@ -28,14 +45,25 @@ use ndarray::prelude::*;
 use config::Config;
 use error::Error;
 use register::Register;
-use register::{BURST, CHIP_ID, GSR0, MAIN, SFCTL};
+use register::{BURST, CHIP_ID, FSTAT, GSR0, MAIN, SFCTL};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Variant {
     BGT60TR13C,
     // BGT60UTR13D has been omitted
     BGT60UTR11AIP,
 }
 
+impl Variant {
+    /// The size of the FIFO in 24-bit data blocks, each holding two 12-bit ADC results.
+    pub fn fifo_size(&self) -> u32 {
+        match self {
+            Variant::BGT60TR13C => 8192,
+            Variant::BGT60UTR11AIP => 2048,
+        }
+    }
+}
+
 pub struct Radar<SPI, RST, IRQ, DLY> {
     spi: SPI,
     reset_pin: RST,
@@ -43,11 +71,20 @@ pub struct Radar<SPI, RST, IRQ, DLY> {
     delay: DLY,
     variant: Variant,
     config: Option<Config>,
+    verify_writes: bool,
+    high_speed_miso: bool,
+    fifo_prefix: bool,
 }
 
 const READ_BIT: u8 = 0;
 const WRITE_BIT: u8 = 1;
 
+/// Maximum number of registers written with a single burst command
+const MAX_BURST_WORDS: usize = 32;
+
+/// Size of the status word that precedes the FIFO data in prefix mode
+const FIFO_PREFIX_SIZE: usize = 3;
+
 impl<SPI, RST, IRQ, DLY> Radar<SPI, RST, IRQ, DLY>
 where
     SPI: SpiDevice,
@@ -70,12 +107,15 @@ where
             delay,
             variant,
             config: None,
+            verify_writes: false,
+            high_speed_miso: false,
+            fifo_prefix: false,
         };
 
         this.reset_hw().await?;
 
         // reset SFCTL register to default state
-        let sfctl = SFCTL::default();
+        let sfctl = this.apply_sfctl_settings(SFCTL::default());
         this.write_register(Register::SFCTL, sfctl.into()).await?;
 
         let chip_id = this.get_chip_id().await?;
@@ -103,97 +143,153 @@ where
     /// Configures the radar.
     ///
     /// - Performs a software reset (clearing all registers)
-    /// - Writes the raw registers as generated by the bgt60-configurator-cli.
+    /// - Writes the raw registers as generated by the bgt60-configurator-cli, using burst writes for consecutive addresses.
     /// - Sets the FIFO limit to a single frame (number of samples per chirp * number of chirps per frame * number of RX antennas)
     /// - Once the FIFO limit is reached, the interrupt pin will be pulled high.
+    /// - If write verification is enabled (see [`Radar::set_verify_writes()`]), every written register is read back and compared.
     ///
     /// ### FIFO considerations:
     /// - The FIFO limit is the number of 12-bit ADC results that can be stored in the FIFO.
     /// - The FIFO limit must be a power of two, because the 12-bit ADC results are stored in 24-bit data blocks.
     /// - The FIFO limit must not exceed the maximum number of 24-bit data blocks that can be stored in the FIFO.
     pub async fn configure(&mut self, config: Config) -> Result<(), Error> {
-        // TODO checks we might want to do on the config (i.e. if RX antennas match the variant)
+        let fifo_limit = check_fifo_limit(self.variant, &config)?;
 
-        let fifo_limit = config.get_fifo_limit() as u32;
+        // SW reset
+        self.reset_sw().await?;
 
-        // Check if limit is a power of two
-        if fifo_limit % 2 != 0 {
-            return Err(Error::NotAPowerOfTwo);
-        }
+        // Write registers, grouping consecutive addresses into burst writes
+        self.write_config_registers(&config.registers).await?;
 
-        // Check if fifo is large enough
-        // We divide by two, because two 12-bit ADC results are packed into one 24-bit data block
-        match self.variant {
-            Variant::BGT60TR13C => {
-                if (fifo_limit / 2) > 8192 {
-                    return Err(Error::FifoTooSmall(fifo_limit, 8192));
-                }
-            }
-            Variant::BGT60UTR11AIP => {
-                if (fifo_limit / 2) > 2048 {
-                    return Err(Error::FifoTooSmall(fifo_limit, 2048));
-                }
+        if self.verify_writes {
+            for reg in config.registers {
+                let (addr, data) = self.patch_register(reg);
+                self.verify_register(addr, data).await?;
             }
         }
 
-        // SW reset
-        self.reset_sw().await?;
+        // Set FIFO limit to a single frame
+        self.set_fifo_limit(fifo_limit).await?;
 
-        // Write registers
-        // TODO: Parse the register address and convert to the enum so that we can just use self.write_register(reg, data)
-        for reg in config.registers {
-            let addr = ((reg & 0xFE000000) >> 25) as u8;
-            let data = reg & 0x00FFFFFF;
+        self.config = Some(config);
 
-            let mut buffer: [u8; 4] = [
-                (addr << 1) | WRITE_BIT,
-                ((data >> 16) & 0xFF) as u8,
-                ((data >> 8) & 0xFF) as u8,
-                (data & 0xFF) as u8,
-            ];
+        Ok(())
+    }
 
-            // TODO if register is SFCTL, disable MISO_HD_RD
+    /// Reconfigures the radar at runtime, without performing a software reset.
+    ///
+    /// - Stops the frame generation by resetting the FSM
+    /// - Writes only the registers that differ from the currently applied configuration
+    /// - Updates the FIFO limit to a single frame of the new configuration
+    /// - Resets the FIFO, so that no samples with the previous frame shape remain
+    /// - Restarts the frame generation
+    ///
+    /// The applied configuration is cleared until all registers have been written, so if any step fails,
+    /// [`Radar::get_fifo_data()`] returns [`Error::NoConfigSet`] instead of reading frames with a mismatching shape.
+    /// In that case, call [`Radar::configure()`] to recover.
+    ///
+    /// If no configuration has been applied yet, this performs a full [`Radar::configure()`] and starts the frame generation.
+    pub async fn reconfigure(&mut self, config: Config) -> Result<(), Error> {
+        let fifo_limit = check_fifo_limit(self.variant, &config)?;
 
-            #[cfg(feature = "debug")]
-            info!(
-                "Write CONFIG register request:  {:#04X} {:#04X}{:02X}{:02X} - {:#010b} {:#010b}{:08b}{:08b}",
-                addr, buffer[1], buffer[2], buffer[3], buffer[0], buffer[1], buffer[2], buffer[3]
-            );
+        let Some(current) = self.config.take() else {
+            self.configure(config).await?;
+            return self.start().await;
+        };
 
-            self.spi
-                .transfer_in_place(&mut buffer)
-                .await
-                .map_err(|e| Error::Spi(e.kind()))?;
+        self.stop().await?;
 
-            #[cfg(feature = "debug")]
-            info!(
-                "Write CONFIG register response: {:#04X} {:#04X}{:02X}{:02X} - {:#010b} {:#010b}{:08b}{:08b}",
-                buffer[0],
-                buffer[1],
-                buffer[2],
-                buffer[3],
-                buffer[0],
-                buffer[1],
-                buffer[2],
-                buffer[3]
-            );
+        // Registers are compared including their address, so a changed register is one whose word is not part of the current configuration
+        let mut changed = [0u32; 38];
+        let mut num_changed = 0;
+        for reg in config.registers {
+            if !current.registers.contains(&reg) {
+                changed[num_changed] = reg;
+                num_changed += 1;
+            }
+        }
 
-            let gsr0 = GSR0::from(buffer[0]);
-            if gsr0.has_error() {
-                return Err(Error::GlobalStatusRegisterError(gsr0));
+        self.write_config_registers(&changed[..num_changed]).await?;
+
+        if self.verify_writes {
+            for &reg in &changed[..num_changed] {
+                let (addr, data) = self.patch_register(reg);
+                self.verify_register(addr, data).await?;
             }
         }
 
-        // Set FIFO limit to a single frame
-        let mut reg: SFCTL = self.read_register(register::Register::SFCTL).await?.into();
+        // The SFCTL register might have been overwritten, so the FIFO limit is always set again
+        self.set_fifo_limit(fifo_limit).await?;
+        self.reset_fifo().await?;
+
+        self.config = Some(config);
+
+        self.start().await
+    }
+
+    /// Sets the FIFO limit (in 12-bit ADC results), after which the interrupt pin will be pulled high.
+    async fn set_fifo_limit(&mut self, fifo_limit: u32) -> Result<(), Error> {
+        let reg: SFCTL = self.read_register(Register::SFCTL).await?.into();
+        let mut reg = self.apply_sfctl_settings(reg);
         reg.set_fifo_cref(((fifo_limit / 2) - 1) as usize);
         self.write_register(Register::SFCTL, reg.into()).await?;
 
-        self.config = Some(config);
+        if self.verify_writes {
+            self.verify_register(Register::SFCTL as u8, reg.into()).await?;
+        }
 
         Ok(())
     }
 
+    /// Returns the active configuration, if the radar has been configured.
+    pub fn config(&self) -> Option<&Config> {
+        self.config.as_ref()
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Enables or disables the verification of configuration writes.
+    ///
+    /// When enabled, [`Radar::configure()`] reads back every register it has written and
+    /// returns [`Error::RegisterMismatch`] if the content does not match what was written.
+    /// Write-only and self-clearing bits are ignored, see [`register::readback_mask()`].
+    ///
+    /// This is disabled by default, as it doubles the number of SPI transactions during configuration.
+    pub fn set_verify_writes(&mut self, enabled: bool) {
+        self.verify_writes = enabled;
+    }
+
+    /// Enables or disables high-speed MISO reads (`SFCTL.MISO_HS_RD`).
+    ///
+    /// High-speed reads are required for SPI clocks above ~25 MHz, where the MISO data would otherwise not be valid
+    /// at the sampling edge of the controller.
+    /// The setting is written immediately and re-applied whenever the driver writes SFCTL,
+    /// i.e. on configuration (overriding the bit in the generated registers) and after a software reset.
+    ///
+    /// Note that the chip starts in normal read mode after a hardware reset, so [`Radar::new()`] needs to run at a lower SPI clock.
+    pub async fn set_high_speed_miso(&mut self, enabled: bool) -> Result<(), Error> {
+        self.high_speed_miso = enabled;
+        let reg: SFCTL = self.read_register(Register::SFCTL).await?.into();
+        let reg = self.apply_sfctl_settings(reg);
+        self.write_register(Register::SFCTL, reg.into()).await
+    }
+
+    /// Enables or disables the FIFO prefix mode (`SFCTL.PREFIX_EN`).
+    ///
+    /// In prefix mode, every FIFO burst read starts with a 24-bit status word (in the layout of [`FSTAT`]),
+    /// which is parsed by [`Radar::get_fifo_data()`] to detect FIFO over- and underflows, SPI burst errors
+    /// and bursts that start before a full frame is available in the FIFO.
+    ///
+    /// The setting is written immediately and re-applied whenever the driver writes SFCTL.
+    pub async fn set_fifo_prefix(&mut self, enabled: bool) -> Result<(), Error> {
+        self.fifo_prefix = enabled;
+        let reg: SFCTL = self.read_register(Register::SFCTL).await?.into();
+        let reg = self.apply_sfctl_settings(reg);
+        self.write_register(Register::SFCTL, reg.into()).await
+    }
+
     /// Resets the hardware by pulling the reset pin high (== reset) and then low (== normal operation).
     pub async fn reset_hw(&mut self) -> Result<(), Error> {
         self.reset_pin
@@ -245,6 +341,12 @@ where
         // A final delay of 10ms is present in the C SDK
         self.delay.delay_ms(10).await;
 
+        // The reset cleared SFCTL, so the SPI settings need to be applied again
+        if self.high_speed_miso || self.fifo_prefix {
+            let reg = self.apply_sfctl_settings(SFCTL::default());
+            self.write_register(Register::SFCTL, reg.into()).await?;
+        }
+
         Ok(())
     }
 
@@ -285,7 +387,8 @@ where
     ///
     /// The test pattern can be verified with the [`crate::get_next_test_word()`] method.
     pub async fn enable_test_mode(&mut self) -> Result<(), Error> {
-        let mut reg: SFCTL = self.read_register(Register::SFCTL).await?.into();
+        let reg: SFCTL = self.read_register(Register::SFCTL).await?.into();
+        let mut reg = self.apply_sfctl_settings(reg);
         reg.set_lfsr_en(true);
         self.write_register(Register::SFCTL, reg.into()).await
     }
@@ -312,43 +415,25 @@ where
     /// This function requires the alloc feature, since it dynamically allocates memory for the frames.
     #[cfg(feature = "alloc")]
     pub async fn get_frames(&mut self) -> Result<Array3<u16>, Error> {
-        let config = self.config.as_ref().ok_or(Error::NoConfigSet)?;
-
-        let shape = (
-            config.rx_antennas as usize, 
-            config.num_chirps_per_frame as usize, 
-            config.num_samples_per_chirp as usize
-        );
-
-        // the data is arranged in the raw buffer in the following way
-        // 1 channel: aa aa aa aa
-        // 2 channels: ab ab ab ab
-        // 3 channels: ab ca bc ab
-        let strides = 
-        (
-            1 as usize, // stride for rx_antennas (innermost dimension)
-            config.rx_antennas as usize * config.num_samples_per_chirp as usize,  // stride for chirps
-            config.rx_antennas as usize // stride for samples
-        );
-
-        let mut frames =  vec![0u16; config.get_fifo_limit()];
-        let mut buffer: Vec<u8> = vec![0u8; config.get_u8_buffer_size()];
-
-        self.get_fifo_data(&mut buffer, &mut frames).await?;
-
-        // We can ignore the error, since we know the frames vector is the correct size
-        Ok(Array3::from_shape_vec(shape.strides(strides), frames).unwrap())
+        FrameSource::get_frames(self).await
     }
 
     /// Reads the data from the FIFO by performing a burst read of the FIFO register.
     /// The function will wait for the interrupt pin to be pulled high before reading the data.
     ///
     /// The buffer must be the correct size to hold a single frame.
-    /// The size of the buffer can be calculated with the formula:
+    /// The size of the buffer can be calculated with the formula (or with [`Config::get_u8_buffer_size()`]):
     ///
     /// `buffer_size = (num_samples_per_chirp * num_chirps_per_frame * rx_antennas * 12) / 8`
     /// `output_size = num_samples_per_chirp * num_chirps_per_frame * rx_antennas`
     ///
+    /// Before the FIFO is drained, GSR0 and the FIFO status register are read in a separate transaction,
+    /// so that no data is clocked out if the chip reports an error ([`Error::GlobalStatusRegisterError`]),
+    /// or if the FIFO reports an error or holds less than a full frame ([`Error::FifoStatusError`]).
+    /// GSR0 returned by the burst command is checked again after the read, for errors raised during the burst itself.
+    ///
+    /// In prefix mode (see [`Radar::set_fifo_prefix()`]), the status word in front of the data is checked as well,
+    /// with the same errors as above.
     pub async fn get_fifo_data(
         &mut self,
         buffer: &mut [u8],
@@ -357,8 +442,7 @@ where
         let config = self.config.as_ref().ok_or(Error::NoConfigSet)?;
         let needed_buffer_size = config.get_u8_buffer_size();
         let fifo_limit = config.get_fifo_limit();
-        
-        // FIFO has a limit of 8192 or 2048 24-bit data blocks, depending on the chip variant
+
         if buffer.len() != needed_buffer_size {
             return Err(Error::BufferWrongSize(buffer.len(), needed_buffer_size));
         }
@@ -371,6 +455,16 @@ where
             .await
             .map_err(|e| Error::Gpio(e.kind()))?;
 
+        // The C implementation checks GSR0 after the burst command, before reading any data.
+        // Here, the burst command and the burst read share one CS-low transaction, which cannot be aborted in between,
+        // so FSTAT (with GSR0) is read in a separate transaction before the burst, at the cost of an extra SPI round trip per frame.
+        let fstat_register = match self.variant {
+            Variant::BGT60TR13C => register::Register::FSTAT_TR13C,
+            Variant::BGT60UTR11AIP => register::Register::FSTAT_UTR11,
+        };
+        let fstat = FSTAT::from(self.read_register(fstat_register).await?);
+        check_fifo_status(fstat, fifo_limit)?;
+
         // The C implementation has the burst command hardcoded to XENSIV_BGT60TRXX_SPI_BURST_MODE_CMD 0xFF000000
         // and only adds the address of the FIFO register to it
         // however, the datasheet specifies the ADDR to be 0x7F, not 0xFF
@@ -384,60 +478,180 @@ where
             .with_rwb(false)
             .with_nbursts(0);
 
-        let burst_raw: u32 = burst.into();
-        buffer[0] = ((burst_raw >> 24) & 0xFF) as u8;
-        buffer[1] = ((burst_raw >> 16) & 0xFF) as u8;
-        buffer[2] = ((burst_raw >> 8) & 0xFF) as u8;
-        buffer[3] = burst_raw as u8;
+        // The header holds the burst command (and receives GSR0), followed by the status word in prefix mode
+        let mut header = [0u8; 4 + FIFO_PREFIX_SIZE];
+        let header_len = if self.fifo_prefix { 4 + FIFO_PREFIX_SIZE } else { 4 };
+        header[..4].copy_from_slice(&u32::from(burst).to_be_bytes());
 
         #[cfg(feature = "debug")]
         info!(
             "Burst command: {:#04X}{:02X}{:02X}{:02X} - {:#010b}{:08b}{:08b}{:08b}",
-            buffer[0], buffer[1], buffer[2], buffer[3], buffer[0], buffer[1], buffer[2], buffer[3]
+            header[0], header[1], header[2], header[3], header[0], header[1], header[2], header[3]
         );
 
-        // The C implementation first sends the burst command, checks the returned GSR0, and then continues to burst read the data only if no error flags are set in GSR0
-        // Since we don't have control over the CS line (which needs to stay low between burst command and burst read), we can't do that
-
+        // The data is read directly into the buffer, and GSR0 (and the prefix) of the burst is checked before the data is unpacked.
         self.spi
-            .transfer_in_place(buffer)
+            .transaction(&mut [
+                Operation::TransferInPlace(&mut header[..header_len]),
+                Operation::Read(buffer),
+            ])
             .await
             .map_err(|e| Error::Spi(e.kind()))?;
 
-        // We can however check the GSR0 after the burst read
-        let gsr0 = GSR0::from(buffer[0]);
+        let gsr0 = GSR0::from(header[0]);
         if gsr0.has_error() || gsr0.fou_err() {
             return Err(Error::GlobalStatusRegisterError(gsr0));
         }
 
-        // Unpack the raw buffer into the output buffer, converting from 12-bit to 16-bit
-        let buffer_view = &buffer[4..]; // skip the first 4 bytes, which are the burst command and GSR0
-        for (i, result) in output.iter_mut().enumerate() {
-            let index = (i * 12) / 8; // this will round down
-            let odd = i % 2 == 0; // odd means the data starts in the middle of the block, even means it starts at the beginning
-
-            let value: u16 = if odd {
-                // the first value we only need to shift 4 bits to the left
-                // the second value needs to be shifted 4 bits to the right to shift out the adjacent next value
-                ((buffer_view[index] as u16) << 4) | ((buffer_view[index + 1] as u16) >> 4)
-            }               
-            else {
-                // the first value we need to ignore the first 4 bits, and shift the remaining 4 bits 8 bits to the left
-                // the second value we can take as is
-                (((buffer_view[index] as u16) & 0x0F) << 8) | (buffer_view[index + 1] as u16)
-            };
-
-            *result = value;
+        if self.fifo_prefix {
+            let prefix = &header[4..4 + FIFO_PREFIX_SIZE];
+            let fstat = FSTAT::from(((prefix[0] as u32) << 16) | ((prefix[1] as u32) << 8) | (prefix[2] as u32));
+
+            #[cfg(feature = "debug")]
+            info!("FIFO prefix: {:?}", fstat);
+
+            check_fifo_status(fstat, fifo_limit)?;
         }
 
+        unpack_fifo_data(buffer, output);
+
         Ok(())
     }
 
     async fn read_register(&mut self, reg: Register) -> Result<u32, Error> {
-        let mut buffer: [u8; 4] = [((reg as u8) << 1) | READ_BIT, 0, 0, 0];
+        self.read_register_raw(reg as u8).await
+    }
+
+    async fn write_register(&mut self, reg: Register, data: u32) -> Result<(), Error> {
+        self.write_register_raw(reg as u8, data).await
+    }
+
+    /// Writes consecutive registers with a single burst write, starting at the given register.
+    ///
+    /// Each entry of `data` holds the 24-bit content of one register, the address is incremented by the chip after every word.
+    /// Longer runs are split into multiple bursts of at most 32 registers.
+    pub async fn write_registers_burst(&mut self, start: Register, data: &[u32]) -> Result<(), Error> {
+        self.write_burst_raw(start as u8, data).await
+    }
+
+    /// Writes raw register words (address and data), as generated by the bgt60-configurator-cli,
+    /// grouping runs of consecutive addresses into burst writes.
+    async fn write_config_registers(&mut self, registers: &[u32]) -> Result<(), Error> {
+        let mut data = [0u32; MAX_BURST_WORDS];
+        let mut start = 0u8;
+        let mut len = 0;
+
+        for &reg in registers {
+            let (addr, value) = self.patch_register(reg);
+
+            if len > 0 && (addr != start + len as u8 || len == MAX_BURST_WORDS) {
+                self.write_burst_raw(start, &data[..len]).await?;
+                len = 0;
+            }
+
+            if len == 0 {
+                start = addr;
+            }
+
+            data[len] = value;
+            len += 1;
+        }
+
+        if len > 0 {
+            self.write_burst_raw(start, &data[..len]).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Splits a raw register word into address and data, and applies the SPI settings of the driver if the register is SFCTL.
+    ///
+    /// The bgt60-configurator-cli enables `MISO_HS_RD` in the generated SFCTL register, which is overridden here.
+    fn patch_register(&self, reg: u32) -> (u8, u32) {
+        let (addr, data) = split_register(reg);
+
+        if addr == Register::SFCTL as u8 {
+            (addr, self.apply_sfctl_settings(data.into()).into())
+        } else {
+            (addr, data)
+        }
+    }
+
+    /// Applies the high-speed MISO and prefix settings of the driver to an SFCTL register value.
+    fn apply_sfctl_settings(&self, reg: SFCTL) -> SFCTL {
+        reg.with_miso_hs_rd(self.high_speed_miso)
+            .with_prefix_en(self.fifo_prefix)
+    }
+
+    async fn write_burst_raw(&mut self, start: u8, data: &[u32]) -> Result<(), Error> {
+        // The start address is 7 bits wide, and the chip does not wrap around
+        if start as usize + data.len() > 0x80 {
+            return Err(Error::BurstOutOfRange(start, data.len()));
+        }
+
+        for (n, chunk) in data.chunks(MAX_BURST_WORDS).enumerate() {
+            let saddr = start as usize + n * MAX_BURST_WORDS;
+
+            let burst = BURST::new()
+                .with_addr(0x7F)
+                .with_rw(true)
+                .with_saddr(saddr)
+                .with_rwb(true)
+                .with_nbursts(chunk.len());
+
+            // 4 bytes for the burst command / GSR0, followed by 3 bytes per register
+            let mut buffer = [0u8; 4 + 3 * MAX_BURST_WORDS];
+            let len = 4 + 3 * chunk.len();
+
+            buffer[..4].copy_from_slice(&u32::from(burst).to_be_bytes());
+            for (i, word) in chunk.iter().enumerate() {
+                buffer[4 + 3 * i] = ((word >> 16) & 0xFF) as u8;
+                buffer[4 + 3 * i + 1] = ((word >> 8) & 0xFF) as u8;
+                buffer[4 + 3 * i + 2] = (word & 0xFF) as u8;
+            }
+
+            #[cfg(feature = "debug")]
+            info!(
+                "Burst write request: {:#04X} ({} registers) - {:02X?}",
+                saddr,
+                chunk.len(),
+                &buffer[..len]
+            );
+
+            self.spi
+                .transfer_in_place(&mut buffer[..len])
+                .await
+                .map_err(|e| Error::Spi(e.kind()))?;
+
+            #[cfg(feature = "debug")]
+            info!("Burst write response: GSR0 {:#010b}", buffer[0]);
+
+            let gsr0 = GSR0::from(buffer[0]);
+            if gsr0.has_error() {
+                return Err(Error::GlobalStatusRegisterError(gsr0));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads back a register and compares it with the expected content, ignoring bits that cannot be read back.
+    async fn verify_register(&mut self, addr: u8, expected: u32) -> Result<(), Error> {
+        let mask = register::readback_mask(addr);
+        let actual = self.read_register_raw(addr).await?;
+
+        if (actual & mask) != (expected & mask) {
+            return Err(Error::RegisterMismatch(addr, expected, actual));
+        }
+
+        Ok(())
+    }
+
+    async fn read_register_raw(&mut self, addr: u8) -> Result<u32, Error> {
+        let mut buffer: [u8; 4] = [(addr << 1) | READ_BIT, 0, 0, 0];
 
         #[cfg(feature = "debug")]
-        info!("Read register {:#04X} - {:#010b}", reg as u8, buffer[0]);
+        info!("Read register {:#04X} - {:#010b}", addr, buffer[0]);
 
         self.spi
             .transfer_in_place(&mut buffer)
@@ -458,9 +672,9 @@ where
         }
     }
 
-    async fn write_register(&mut self, reg: Register, data: u32) -> Result<(), Error> {
+    async fn write_register_raw(&mut self, addr: u8, data: u32) -> Result<(), Error> {
         let mut buffer: [u8; 4] = [
-            ((reg as u8) << 1) | WRITE_BIT,
+            (addr << 1) | WRITE_BIT,
             ((data >> 16) & 0xFF) as u8,
             ((data >> 8) & 0xFF) as u8,
             (data & 0xFF) as u8,
@@ -469,7 +683,7 @@ where
         #[cfg(feature = "debug")]
         info!(
             "Write register request:  {:#04X} {:#04X}{:02X}{:02X} - {:#010b} {:#010b}{:08b}{:08b}",
-            reg as u8, buffer[1], buffer[2], buffer[3], buffer[0], buffer[1], buffer[2], buffer[3]
+            addr, buffer[1], buffer[2], buffer[3], buffer[0], buffer[1], buffer[2], buffer[3]
         );
 
         self.spi
@@ -492,6 +706,189 @@ where
     }
 }
 
+/// The acquisition API of [`Radar`], to run application code and signal processing against other sources of frames,
+/// such as recordings replayed by the `ReplayRadar` of the `replay` module (requires the std feature).
+#[allow(async_fn_in_trait)]
+pub trait FrameSource {
+    fn variant(&self) -> Variant;
+
+    async fn get_chip_id(&mut self) -> Result<CHIP_ID, Error>;
+
+    /// The applied configuration, see [`FrameSource::configure()`].
+    fn config(&self) -> Option<&Config>;
+
+    /// Applies the configuration, see [`Radar::configure()`].
+    async fn configure(&mut self, config: Config) -> Result<(), Error>;
+
+    /// Starts the frame generation, see [`Radar::start()`].
+    async fn start(&mut self) -> Result<(), Error>;
+
+    /// Stops the frame generation, see [`Radar::stop()`].
+    async fn stop(&mut self) -> Result<(), Error>;
+
+    /// Waits for the next frame and reads it, see [`Radar::get_fifo_data()`].
+    async fn get_fifo_data(&mut self, buffer: &mut [u8], output: &mut [u16]) -> Result<(), Error>;
+
+    /// Waits for the next frame and returns it as a 3D array, see [`Radar::get_frames()`].
+    ///
+    /// This function requires the alloc feature, since it dynamically allocates memory for the frames.
+    #[cfg(feature = "alloc")]
+    async fn get_frames(&mut self) -> Result<Array3<u16>, Error> {
+        let config = self.config().ok_or(Error::NoConfigSet)?;
+
+        let mut frames = vec![0u16; config.get_fifo_limit()];
+        let mut buffer: Vec<u8> = vec![0u8; config.get_u8_buffer_size()];
+
+        self.get_fifo_data(&mut buffer, &mut frames).await?;
+
+        // The config has been checked above, and the frames vector has the correct size
+        frames_to_array(self.config().ok_or(Error::NoConfigSet)?, frames)
+    }
+}
+
+impl<SPI, RST, IRQ, DLY> FrameSource for Radar<SPI, RST, IRQ, DLY>
+where
+    SPI: SpiDevice,
+    RST: OutputPin,
+    IRQ: Wait,
+    DLY: DelayNs,
+{
+    fn variant(&self) -> Variant {
+        Radar::variant(self)
+    }
+
+    async fn get_chip_id(&mut self) -> Result<CHIP_ID, Error> {
+        Radar::get_chip_id(self).await
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Radar::config(self)
+    }
+
+    async fn configure(&mut self, config: Config) -> Result<(), Error> {
+        Radar::configure(self, config).await
+    }
+
+    async fn start(&mut self) -> Result<(), Error> {
+        Radar::start(self).await
+    }
+
+    async fn stop(&mut self) -> Result<(), Error> {
+        Radar::stop(self).await
+    }
+
+    async fn get_fifo_data(&mut self, buffer: &mut [u8], output: &mut [u16]) -> Result<(), Error> {
+        Radar::get_fifo_data(self, buffer, output).await
+    }
+}
+
+/// Checks that a single frame of the config fits into the FIFO of the variant, and returns the FIFO limit.
+pub(crate) fn check_fifo_limit(variant: Variant, config: &Config) -> Result<u32, Error> {
+    // TODO checks we might want to do on the config (i.e. if RX antennas match the variant)
+
+    let fifo_limit = config.get_fifo_limit() as u32;
+
+    // Check if limit is a power of two
+    if !fifo_limit.is_multiple_of(2) {
+        return Err(Error::NotAPowerOfTwo);
+    }
+
+    // Check if fifo is large enough
+    // We divide by two, because two 12-bit ADC results are packed into one 24-bit data block
+    let fifo_size = variant.fifo_size();
+    if (fifo_limit / 2) > fifo_size {
+        return Err(Error::FifoTooSmall(fifo_limit, fifo_size));
+    }
+
+    Ok(fifo_limit)
+}
+
+/// Unpacks the raw FIFO data, where two 12-bit ADC results are packed into one 24-bit data block, into 16-bit samples.
+fn unpack_fifo_data(buffer: &[u8], output: &mut [u16]) {
+    for (i, result) in output.iter_mut().enumerate() {
+        let index = (i * 12) / 8; // this will round down
+        let odd = i % 2 == 0; // odd means the data starts in the middle of the block, even means it starts at the beginning
+
+        let value: u16 = if odd {
+            // the first value we only need to shift 4 bits to the left
+            // the second value needs to be shifted 4 bits to the right to shift out the adjacent next value
+            ((buffer[index] as u16) << 4) | ((buffer[index + 1] as u16) >> 4)
+        }               
+        else {
+            // the first value we need to ignore the first 4 bits, and shift the remaining 4 bits 8 bits to the left
+            // the second value we can take as is
+            (((buffer[index] as u16) & 0x0F) << 8) | (buffer[index + 1] as u16)
+        };
+
+        *result = value;
+    }
+}
+
+/// Packs 16-bit samples into the raw FIFO data, the inverse of [`unpack_fifo_data()`].
+#[cfg(feature = "std")]
+pub(crate) fn pack_fifo_data(samples: impl IntoIterator<Item = u16>, buffer: &mut [u8]) {
+    let mut samples = samples.into_iter();
+    for block in buffer.chunks_exact_mut(3) {
+        let first = samples.next().unwrap_or(0) & 0x0FFF;
+        let second = samples.next().unwrap_or(0) & 0x0FFF;
+        block[0] = (first >> 4) as u8;
+        block[1] = (((first & 0x0F) << 4) | (second >> 8)) as u8;
+        block[2] = second as u8;
+    }
+}
+
+/// Splits a raw register word, as generated by the bgt60-configurator-cli, into its address and 24-bit data.
+fn split_register(reg: u32) -> (u8, u32) {
+    (((reg & 0xFE000000) >> 25) as u8, reg & 0x00FFFFFF)
+}
+
+/// Checks the FIFO status for errors, and that at least a full frame of `fifo_limit` ADC results is in the FIFO.
+fn check_fifo_status(fstat: FSTAT, fifo_limit: usize) -> Result<(), Error> {
+    // The fill status is counted in 24-bit data blocks, each holding two ADC results
+    if fstat.has_error() || fstat.fill_status() < fifo_limit / 2 {
+        return Err(Error::FifoStatusError(fstat));
+    }
+
+    Ok(())
+}
+
+/// Wraps the raw FIFO data of a single frame into a 3D array with the shape of [rx_antennas, num_chirps_per_frame, num_samples_per_chirp],
+/// without copying the data.
+///
+/// Returns [`Error::ShapeMismatch`] if the number of samples does not match the shape of the config.
+#[cfg(feature = "alloc")]
+pub(crate) fn frames_to_array(config: &Config, frames: Vec<u16>) -> Result<Array3<u16>, Error> {
+    let shape = (
+        config.rx_antennas as usize,
+        config.num_chirps_per_frame as usize,
+        config.num_samples_per_chirp as usize
+    );
+
+    // the data is arranged in the raw buffer in the following way
+    // 1 channel: aa aa aa aa
+    // 2 channels: ab ab ab ab
+    // 3 channels: ab ca bc ab
+    #[allow(clippy::unnecessary_cast)] // all strides are cast alike
+    let strides =
+    (
+        1 as usize, // stride for rx_antennas (innermost dimension)
+        config.rx_antennas as usize * config.num_samples_per_chirp as usize,  // stride for chirps
+        config.rx_antennas as usize // stride for samples
+    );
+
+    Array3::from_shape_vec(shape.strides(strides), frames).map_err(|_| Error::ShapeMismatch)
+}
+
+/// Returns the samples of a frame with the shape of [rx_antennas, num_chirps_per_frame, num_samples_per_chirp]
+/// in the interleaved order of the FIFO, as written by [`Radar::get_fifo_data()`]: the inverse of [`Radar::get_frames()`].
+///
+/// This function requires the alloc feature.
+#[cfg(feature = "alloc")]
+pub fn fifo_samples(frame: &Array3<u16>) -> impl Iterator<Item = u16> + '_ {
+    // The antennas are interleaved per sample, chirp after chirp
+    frame.view().permuted_axes([1, 2, 0]).into_iter().copied()
+}
+
 /// Generates the next test word based on the current word.
 ///
 /// To be used in conjunction with [`Radar::enable_test_mode()`].
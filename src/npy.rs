@@ -0,0 +1,484 @@
+//! Export of frames to the NumPy `.npy` and `.npz` formats, e.g. for analysis in Python with `numpy.load()`.
+//!
+//! This requires the std feature.
+//! - [`write_frame()`]: a single frame from [`crate::Radar::get_frames()`], with the shape `[rx, chirp, sample]`.
+//! - [`write_frames()`] and [`write_recording_npy()`]: stacked frames, with the shape `[frame, rx, chirp, sample]`.
+//! - [`write_npz()`] and [`write_recording_npz()`]: stacked frames as `frames`, with the fields of the config stored alongside
+//!   (one 0-d array per field, and `registers` as a 1-d array).
+//! - [`read_frames()`]: reads single or stacked frames back, e.g. to replay them through the signal processing of the `dsp` feature.
+//!
+//! Arrays are written in C order with little-endian values (version 1.0 of the `.npy` format),
+//! and `.npz` archives are uncompressed zip files, as written by `numpy.savez()`.
+
+use std::io::{Read, Write};
+use std::string::String;
+use std::vec::Vec;
+
+use ndarray::Array3;
+
+use crate::config::{ADC_FULL_SCALE, Config};
+use crate::crc::crc32;
+use crate::error::Error;
+use crate::recording::{Record, RecordingReader, std_io_error};
+
+/// Magic bytes at the start of a `.npy` file.
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+/// The `.npy` header (magic, version, length and dictionary) is padded to a multiple of this size.
+const NPY_ALIGNMENT: usize = 64;
+
+/// The maximum length of the header dictionary accepted by [`read_frames()`], like the default of NumPy's `max_header_size`.
+const MAX_HEADER_LEN: usize = 10_000;
+
+/// The size of the buffer used to convert values before writing them.
+const BUFFER_SIZE: usize = 8192;
+
+/// A value that can be stored in a `.npy` array.
+pub trait Element: Copy {
+    /// The NumPy type string of the value, e.g. `<u2` for a little-endian u16.
+    const DESCR: &'static str;
+
+    /// Appends the little-endian bytes of the value.
+    fn extend_le(&self, bytes: &mut Vec<u8>);
+}
+
+macro_rules! impl_element {
+    ($($type:ty => $descr:literal),* $(,)?) => {
+        $(
+            impl Element for $type {
+                const DESCR: &'static str = $descr;
+
+                fn extend_le(&self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_element! {
+    u8 => "|u1",
+    u16 => "<u2",
+    u32 => "<u4",
+    u64 => "<u8",
+    f32 => "<f4",
+    f64 => "<f8",
+}
+
+/// Writes an array in the `.npy` format, with the values in C order (the last index changing fastest).
+///
+/// The number of values must match the shape, an empty shape is a 0-d array with a single value.
+pub fn write_array<W: Write, T: Element>(
+    mut writer: W,
+    shape: &[usize],
+    values: impl IntoIterator<Item = T>,
+) -> Result<(), Error> {
+    let expected: usize = shape.iter().product();
+
+    writer.write_all(&npy_header(T::DESCR, shape)).map_err(std_io_error)?;
+
+    let mut count = 0;
+    let mut buffer = Vec::with_capacity(BUFFER_SIZE);
+    for value in values {
+        value.extend_le(&mut buffer);
+        count += 1;
+        if buffer.len() >= BUFFER_SIZE {
+            writer.write_all(&buffer).map_err(std_io_error)?;
+            buffer.clear();
+        }
+    }
+    writer.write_all(&buffer).map_err(std_io_error)?;
+
+    if count != expected {
+        return Err(Error::BufferWrongSize(count, expected));
+    }
+
+    Ok(())
+}
+
+/// Writes a single frame in the `.npy` format, with the shape `[rx_antennas, num_chirps_per_frame, num_samples_per_chirp]`.
+pub fn write_frame<W: Write>(writer: W, frame: &Array3<u16>) -> Result<(), Error> {
+    let (rx, chirps, samples) = frame.dim();
+    write_array(writer, &[rx, chirps, samples], frame.iter().copied())
+}
+
+/// Writes frames of the same config in the `.npy` format, stacked with the shape
+/// `[num_frames, rx_antennas, num_chirps_per_frame, num_samples_per_chirp]`.
+pub fn write_frames<W: Write>(writer: W, config: &Config, frames: &[Array3<u16>]) -> Result<(), Error> {
+    let shape = frames_shape(config, frames)?;
+    write_array(writer, &shape, frames.iter().flat_map(|frame| frame.iter().copied()))
+}
+
+/// Writes frames of the same config and the fields of the config as a `.npz` archive.
+pub fn write_npz<W: Write>(writer: W, config: &Config, frames: &[Array3<u16>]) -> Result<(), Error> {
+    let mut npz = NpzWriter::new(writer);
+    npz.add_array("frames", &frames_shape(config, frames)?, frames.iter().flat_map(|frame| frame.iter().copied()))?;
+    add_config(&mut npz, config)?;
+    npz.finish()?;
+    Ok(())
+}
+
+/// Writes all frames of a recording in the `.npy` format, stacked as with [`write_frames()`].
+pub fn write_recording_npy<W: Write, R: Read>(writer: W, recording: RecordingReader<R>) -> Result<(), Error> {
+    let config = recording.header().config.clone();
+    let records = recording.collect::<Result<Vec<Record>, Error>>()?;
+    let shape = frames_shape(&config, records.iter().map(|record| &record.frame))?;
+    write_array(writer, &shape, records.iter().flat_map(|record| record.frame.iter().copied()))
+}
+
+/// Writes all frames of a recording as a `.npz` archive, as with [`write_npz()`].
+///
+/// The header and metadata of the recording are stored as well: `variant` (0: BGT60TR13C, 1: BGT60UTR11AIP), `chip_id`,
+/// and the `frame_counter` and `timestamp_us` of each frame.
+pub fn write_recording_npz<W: Write, R: Read>(writer: W, recording: RecordingReader<R>) -> Result<(), Error> {
+    let header = recording.header().clone();
+    let records = recording.collect::<Result<Vec<Record>, Error>>()?;
+
+    let mut npz = NpzWriter::new(writer);
+    let shape = frames_shape(&header.config, records.iter().map(|record| &record.frame))?;
+    npz.add_array("frames", &shape, records.iter().flat_map(|record| record.frame.iter().copied()))?;
+    npz.add_array("frame_counter", &[records.len()], records.iter().map(|record| record.frame_counter))?;
+    npz.add_array("timestamp_us", &[records.len()], records.iter().map(|record| record.timestamp_us))?;
+    npz.add_array("variant", &[], [header.variant as u8])?;
+    npz.add_array("chip_id", &[], [header.chip_id.into_bits()])?;
+    add_config(&mut npz, &header.config)?;
+    npz.finish()?;
+    Ok(())
+}
+
+/// The scale of floating-point samples read by [`read_frames()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatScale {
+    /// The values are ADC counts (`0.0..=4095.0`).
+    AdcCounts,
+    /// The values are normalized to the ADC full scale (`0.0..=1.0`), as returned by the Infineon Radar SDK.
+    Normalized,
+}
+
+/// Reads frames from a `.npy` file, either stacked with the shape `[num_frames, rx_antennas, num_chirps_per_frame, num_samples_per_chirp]`
+/// (as written by [`write_frames()`]) or a single frame with the shape `[rx_antennas, num_chirps_per_frame, num_samples_per_chirp]`.
+///
+/// The shape must match the config. Besides u16 values, little-endian f32 and f64 values in C order are accepted (e.g. from other tools),
+/// which are converted with the given scale and rounded to ADC counts. The scale is ignored for u16 values.
+pub fn read_frames<R: Read>(mut reader: R, config: &Config, scale: FloatScale) -> Result<Vec<Array3<u16>>, Error> {
+    let mut preamble = [0u8; 8];
+    reader.read_exact(&mut preamble).map_err(std_io_error)?;
+    if &preamble[..6] != NPY_MAGIC {
+        return Err(Error::InvalidParameter);
+    }
+
+    // Version 1.0 has a 16-bit header length, versions 2.0 and 3.0 a 32-bit header length
+    let header_len = match preamble[6] {
+        1 => {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len).map_err(std_io_error)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len).map_err(std_io_error)?;
+            u32::from_le_bytes(len) as usize
+        }
+        _ => return Err(Error::InvalidParameter),
+    };
+    if header_len > MAX_HEADER_LEN {
+        return Err(Error::InvalidParameter);
+    }
+    let mut dict = std::vec![0u8; header_len];
+    reader.read_exact(&mut dict).map_err(std_io_error)?;
+    let dict = core::str::from_utf8(&dict).map_err(|_| Error::InvalidParameter)?;
+
+    let descr = parse_descr(dict).ok_or(Error::InvalidParameter)?;
+    let shape = parse_shape(dict).ok_or(Error::InvalidParameter)?;
+    if parse_fortran_order(dict) != Some(false) {
+        return Err(Error::InvalidParameter);
+    }
+
+    let frame_shape = [
+        config.rx_antennas as usize,
+        config.num_chirps_per_frame as usize,
+        config.num_samples_per_chirp as usize,
+    ];
+    let num_frames = match shape.as_slice() {
+        [num_frames, rest @ ..] if rest == frame_shape => *num_frames,
+        single if single == frame_shape => 1,
+        _ => return Err(Error::ShapeMismatch),
+    };
+    let frame_size = config.get_fifo_limit();
+    if frame_size == 0 {
+        return Err(Error::ShapeMismatch);
+    }
+    let num_values = num_frames.checked_mul(frame_size).ok_or(Error::ShapeMismatch)?;
+
+    let values: Vec<u16> = match descr {
+        "<u2" => read_values(&mut reader, num_values, u16::from_le_bytes)?,
+        "<f4" => to_adc_counts(read_values(&mut reader, num_values, |bytes| f32::from_le_bytes(bytes) as f64)?, scale),
+        "<f8" => to_adc_counts(read_values(&mut reader, num_values, f64::from_le_bytes)?, scale),
+        _ => return Err(Error::InvalidParameter),
+    };
+
+    let shape = (frame_shape[0], frame_shape[1], frame_shape[2]);
+    values
+        .chunks_exact(frame_size)
+        .map(|frame| Array3::from_shape_vec(shape, frame.to_vec()).map_err(|_| Error::ShapeMismatch))
+        .collect()
+}
+
+/// Writes arrays into an uncompressed `.npz` archive (a zip file with one `.npy` file per array).
+///
+/// Each array is converted in memory to compute its checksum, so the writer does not need to support seeking.
+#[derive(Debug)]
+pub struct NpzWriter<W: Write> {
+    writer: W,
+    offset: u64,
+    entries: Vec<ZipEntry>,
+}
+
+/// An entry of the central directory of a zip file.
+#[derive(Debug)]
+struct ZipEntry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+impl<W: Write> NpzWriter<W> {
+    pub fn new(writer: W) -> Self {
+        NpzWriter {
+            writer,
+            offset: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds an array, which is loaded by NumPy with the given name, see [`write_array()`].
+    ///
+    /// Returns [`Error::InvalidParameter`] if the archive exceeds the 4 GiB limit of zip files without the zip64 extension.
+    pub fn add_array<T: Element>(
+        &mut self,
+        name: &str,
+        shape: &[usize],
+        values: impl IntoIterator<Item = T>,
+    ) -> Result<(), Error> {
+        let mut data = Vec::new();
+        write_array(&mut data, shape, values)?;
+
+        let mut file_name = String::from(name);
+        file_name.push_str(".npy");
+        let size = u32::try_from(data.len()).map_err(|_| Error::InvalidParameter)?;
+        let offset = u32::try_from(self.offset).map_err(|_| Error::InvalidParameter)?;
+        let entry = ZipEntry {
+            name: file_name,
+            crc: crc32(&data),
+            size,
+            offset,
+        };
+
+        // Local file header, followed by the data
+        let mut header = Vec::with_capacity(30 + entry.name.len());
+        header.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        header.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // flags
+        header.extend_from_slice(&0u16.to_le_bytes()); // method: stored
+        header.extend_from_slice(&0u16.to_le_bytes()); // time
+        header.extend_from_slice(&ZIP_DATE.to_le_bytes());
+        header.extend_from_slice(&entry.crc.to_le_bytes());
+        header.extend_from_slice(&entry.size.to_le_bytes()); // compressed size
+        header.extend_from_slice(&entry.size.to_le_bytes()); // uncompressed size
+        header.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+        header.extend_from_slice(entry.name.as_bytes());
+
+        self.write(&header)?;
+        self.write(&data)?;
+        self.entries.push(entry);
+
+        Ok(())
+    }
+
+    /// Writes the central directory, and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, Error> {
+        let start = u32::try_from(self.offset).map_err(|_| Error::InvalidParameter)?;
+        let num_entries = u16::try_from(self.entries.len()).map_err(|_| Error::InvalidParameter)?;
+
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            directory.extend_from_slice(&ZIP_VERSION.to_le_bytes()); // version made by
+            directory.extend_from_slice(&ZIP_VERSION.to_le_bytes()); // version needed
+            directory.extend_from_slice(&0u16.to_le_bytes()); // flags
+            directory.extend_from_slice(&0u16.to_le_bytes()); // method: stored
+            directory.extend_from_slice(&0u16.to_le_bytes()); // time
+            directory.extend_from_slice(&ZIP_DATE.to_le_bytes());
+            directory.extend_from_slice(&entry.crc.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]); // extra field and comment lengths, disk number, internal and external attributes
+            directory.extend_from_slice(&entry.offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        let size = u32::try_from(directory.len()).map_err(|_| Error::InvalidParameter)?;
+
+        // End of central directory record
+        directory.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        directory.extend_from_slice(&[0; 4]); // disk numbers
+        directory.extend_from_slice(&num_entries.to_le_bytes()); // entries on this disk
+        directory.extend_from_slice(&num_entries.to_le_bytes()); // total entries
+        directory.extend_from_slice(&size.to_le_bytes());
+        directory.extend_from_slice(&start.to_le_bytes());
+        directory.extend_from_slice(&0u16.to_le_bytes()); // comment length
+
+        self.write(&directory)?;
+        self.writer.flush().map_err(std_io_error)?;
+
+        Ok(self.writer)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.writer.write_all(bytes).map_err(std_io_error)?;
+        self.offset += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+/// The zip version needed to extract stored files (2.0).
+const ZIP_VERSION: u16 = 20;
+
+/// The modification date of all files (1980-01-01, the earliest date of the zip format), so archives are reproducible.
+const ZIP_DATE: u16 = (1 << 5) | 1;
+
+/// The header of a `.npy` file of version 1.0, padded with spaces and terminated by a newline.
+fn npy_header(descr: &str, shape: &[usize]) -> Vec<u8> {
+    let shape = match shape {
+        [] => String::from("()"),
+        [len] => std::format!("({},)", len),
+        _ => {
+            let dims: Vec<String> = shape.iter().map(|dim| std::format!("{}", dim)).collect();
+            std::format!("({})", dims.join(", "))
+        }
+    };
+    let dict = std::format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+
+    // Magic, version (2 bytes) and header length (2 bytes), then the dictionary and the newline
+    let unpadded = NPY_MAGIC.len() + 4 + dict.len() + 1;
+    let padding = unpadded.next_multiple_of(NPY_ALIGNMENT) - unpadded;
+
+    let mut header = Vec::with_capacity(unpadded + padding);
+    header.extend_from_slice(NPY_MAGIC);
+    header.extend_from_slice(&[1, 0]);
+    header.extend_from_slice(&((dict.len() + padding + 1) as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header.resize(header.len() + padding, b' ');
+    header.push(b'\n');
+    header
+}
+
+/// The value of the `descr` key of a header dictionary, e.g. `<u2`.
+fn parse_descr(dict: &str) -> Option<&str> {
+    let value = dict_value(dict, "descr")?;
+    let quote = value.chars().next().filter(|&quote| quote == '\'' || quote == '"')?;
+    let value = &value[1..];
+    Some(&value[..value.find(quote)?])
+}
+
+/// The value of the `fortran_order` key of a header dictionary.
+fn parse_fortran_order(dict: &str) -> Option<bool> {
+    let value = dict_value(dict, "fortran_order")?;
+    if value.starts_with("False") {
+        Some(false)
+    } else if value.starts_with("True") {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// The value of the `shape` key of a header dictionary, e.g. `(2, 3)`.
+fn parse_shape(dict: &str) -> Option<Vec<usize>> {
+    let value = dict_value(dict, "shape")?.strip_prefix('(')?;
+    let value = &value[..value.find(')')?];
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| dim.parse().ok())
+        .collect()
+}
+
+/// The text following a key (and its colon) of a header dictionary.
+fn dict_value<'a>(dict: &'a str, key: &str) -> Option<&'a str> {
+    let start = ["'", "\""]
+        .iter()
+        .find_map(|quote| dict.find(&std::format!("{quote}{key}{quote}")))?;
+    let rest = &dict[start + key.len() + 2..];
+    Some(rest.trim_start().strip_prefix(':')?.trim_start())
+}
+
+/// Reads a number of little-endian values with `N` bytes each.
+/// Reads `count` values of `N` bytes each.
+///
+/// The buffer grows with the data actually read, so that a shape from a corrupted header does not allocate more than the file holds.
+fn read_values<R: Read, T, const N: usize>(reader: &mut R, count: usize, convert: fn([u8; N]) -> T) -> Result<Vec<T>, Error> {
+    let len = count.checked_mul(N).ok_or(Error::ShapeMismatch)?;
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes).map_err(std_io_error)?;
+    if bytes.len() != len {
+        return Err(std_io_error(std::io::ErrorKind::UnexpectedEof.into()));
+    }
+    Ok(bytes
+        .chunks_exact(N)
+        // Unwrap is safe, since the chunks have exactly N bytes
+        .map(|chunk| convert(chunk.try_into().unwrap()))
+        .collect())
+}
+
+/// Rounds floating-point samples to 12-bit ADC counts, scaling normalized values by the ADC full scale.
+fn to_adc_counts(values: Vec<f64>, scale: FloatScale) -> Vec<u16> {
+    let full_scale = ADC_FULL_SCALE as f64;
+    let scale = match scale {
+        FloatScale::AdcCounts => 1.0,
+        FloatScale::Normalized => full_scale,
+    };
+    values
+        .into_iter()
+        .map(|value| (value * scale).round().clamp(0.0, full_scale) as u16)
+        .collect()
+}
+
+/// The shape of stacked frames, checking that all frames match the config.
+pub(crate) fn frames_shape<'a>(config: &Config, frames: impl IntoIterator<Item = &'a Array3<u16>>) -> Result<[usize; 4], Error> {
+    let shape = (
+        config.rx_antennas as usize,
+        config.num_chirps_per_frame as usize,
+        config.num_samples_per_chirp as usize,
+    );
+
+    let mut num_frames = 0;
+    for frame in frames {
+        if frame.dim() != shape {
+            return Err(Error::ShapeMismatch);
+        }
+        num_frames += 1;
+    }
+
+    Ok([num_frames, shape.0, shape.1, shape.2])
+}
+
+/// Adds the fields of the config, with the same names and types as in [`Config`].
+fn add_config<W: Write>(npz: &mut NpzWriter<W>, config: &Config) -> Result<(), Error> {
+    npz.add_array("rx_antennas", &[], [config.rx_antennas])?;
+    npz.add_array("tx_antennas", &[], [config.tx_antennas])?;
+    npz.add_array("tx_power_level", &[], [config.tx_power_level])?;
+    npz.add_array("if_gain_db", &[], [config.if_gain_db])?;
+    npz.add_array("lower_frequency_hz", &[], [config.lower_frequency_hz])?;
+    npz.add_array("upper_frequency_hz", &[], [config.upper_frequency_hz])?;
+    npz.add_array("num_chirps_per_frame", &[], [config.num_chirps_per_frame])?;
+    npz.add_array("num_samples_per_chirp", &[], [config.num_samples_per_chirp])?;
+    npz.add_array("chirp_repetition_time_s", &[], [config.chirp_repetition_time_s])?;
+    npz.add_array("frame_repetition_time_s", &[], [config.frame_repetition_time_s])?;
+    npz.add_array("sample_rate_hz", &[], [config.sample_rate_hz])?;
+    npz.add_array("registers", &[config.registers.len()], config.registers)?;
+    Ok(())
+}
@@ -0,0 +1,599 @@
+//! A framed binary protocol to stream frames, detections and status from a device to a host, e.g. over USB-CDC or a UART.
+//!
+//! This requires the protocol feature, the [`Decoder`] additionally requires the std feature.
+//!
+//! Each packet is encoded with COBS (consistent overhead byte stuffing) and terminated by a `0x00` delimiter,
+//! so that a receiver can resynchronise on the next delimiter after corrupted or lost bytes.
+//! Before encoding, a packet has the following layout, with all values in little-endian:
+//!
+//! | Field | Type |
+//! |---|---|
+//! | version | u8 |
+//! | message type | u8 |
+//! | sequence number | u16, incremented per packet and wrapping around |
+//! | payload | depends on the message type |
+//! | CRC | u32, CRC-32 (IEEE 802.3) of all previous fields |
+//!
+//! | Message type | Payload |
+//! |---|---|
+//! | 1: config | variant, chip ID and config as a [`Header`] of the [recording format](crate::recording) |
+//! | 2: raw frame | frame counter (u32), timestamp in microseconds (u64), samples (u16 each) in the interleaved layout of the FIFO |
+//! | 3: detections | frame counter (u32), number of detections (u16), per detection: range bin (u16), Doppler bin (u16), range, velocity and SNR (f32 each) |
+//! | 4: status | [`StatusCode`] (u8), three details (u32 each) |
+//!
+//! The [`Encoder`] writes packets to any [`embedded_io::Write`] sink without allocations.
+//! The [`Decoder`] is fed with the received bytes and returns the decoded [`Packet`]s,
+//! reporting corrupted packets as [`embedded_io::ErrorKind::InvalidData`] and counting lost packets by their sequence numbers.
+//!
+//! A host can connect at any time, so the device should repeat the config message periodically,
+//! since raw frames can only be shaped with the config (see [`Decoder::frame_to_array()`]).
+
+use core::fmt::{Display, Formatter};
+
+use embedded_io::Write;
+
+#[cfg(feature = "std")]
+use ndarray::Array3;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use crate::crc::Crc32;
+use crate::dsp::Detection;
+use crate::error::Error;
+use crate::recording::Header;
+
+/// Version of the protocol.
+const VERSION: u8 = 1;
+
+/// The delimiter between packets.
+const DELIMITER: u8 = 0x00;
+
+/// The maximum number of bytes in a COBS group.
+const MAX_GROUP: usize = 254;
+
+/// The size of the packet header (version, message type and sequence number), in bytes.
+#[cfg(feature = "std")]
+const PACKET_HEADER_SIZE: usize = 1 + 1 + 2;
+
+/// The size of the CRC at the end of a packet, in bytes.
+#[cfg(feature = "std")]
+const CRC_SIZE: usize = 4;
+
+/// The size of a detection in a detections message, in bytes.
+const DETECTION_SIZE: usize = 2 + 2 + 3 * 4;
+
+/// The size of the payload of a status message, in bytes.
+const STATUS_SIZE: usize = 1 + 3 * 4;
+
+/// The number of samples converted at once when writing a raw frame.
+const CHUNK_SAMPLES: usize = 32;
+
+const TYPE_CONFIG: u8 = 1;
+const TYPE_FRAME: u8 = 2;
+const TYPE_DETECTIONS: u8 = 3;
+const TYPE_STATUS: u8 = 4;
+
+/// The kind of a status message, mapped from the variants of [`Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum StatusCode {
+    Ok = 0,
+    Spi = 1,
+    Gpio = 2,
+    NoConfigSet = 3,
+    VariantMismatch = 4,
+    NotAPowerOfTwo = 5,
+    FifoTooSmall = 6,
+    BufferWrongSize = 7,
+    OutputWrongSize = 8,
+    GlobalStatusRegisterError = 9,
+    ResetError = 10,
+    RegisterMismatch = 11,
+    BurstOutOfRange = 12,
+    FifoStatusError = 13,
+    ShapeMismatch = 14,
+    InvalidParameter = 15,
+    Io = 16,
+    EndOfRecording = 17,
+}
+
+impl StatusCode {
+    #[cfg(feature = "std")]
+    fn from_u8(code: u8) -> Option<Self> {
+        Some(match code {
+            0 => StatusCode::Ok,
+            1 => StatusCode::Spi,
+            2 => StatusCode::Gpio,
+            3 => StatusCode::NoConfigSet,
+            4 => StatusCode::VariantMismatch,
+            5 => StatusCode::NotAPowerOfTwo,
+            6 => StatusCode::FifoTooSmall,
+            7 => StatusCode::BufferWrongSize,
+            8 => StatusCode::OutputWrongSize,
+            9 => StatusCode::GlobalStatusRegisterError,
+            10 => StatusCode::ResetError,
+            11 => StatusCode::RegisterMismatch,
+            12 => StatusCode::BurstOutOfRange,
+            13 => StatusCode::FifoStatusError,
+            14 => StatusCode::ShapeMismatch,
+            15 => StatusCode::InvalidParameter,
+            16 => StatusCode::Io,
+            17 => StatusCode::EndOfRecording,
+            _ => return None,
+        })
+    }
+}
+
+/// The status of a device, either [`Status::OK`] or an error.
+///
+/// The details hold the values of the error variant, e.g. the register address, expected and actual value of [`Error::RegisterMismatch`],
+/// or the raw register value of [`Error::GlobalStatusRegisterError`] and [`Error::FifoStatusError`]. Unused details are 0.
+/// The error kinds of [`Error::Spi`], [`Error::Gpio`] and [`Error::Io`] are not transmitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Status {
+    pub code: StatusCode,
+    pub details: [u32; 3],
+}
+
+impl Status {
+    pub const OK: Status = Status {
+        code: StatusCode::Ok,
+        details: [0; 3],
+    };
+
+    fn to_bytes(self) -> [u8; STATUS_SIZE] {
+        let mut bytes = [0u8; STATUS_SIZE];
+        bytes[0] = self.code as u8;
+        for (detail, chunk) in self.details.iter().zip(bytes[1..].chunks_exact_mut(4)) {
+            chunk.copy_from_slice(&detail.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+impl From<&Error> for Status {
+    fn from(error: &Error) -> Self {
+        let (code, details) = match *error {
+            Error::Spi(_) => (StatusCode::Spi, [0; 3]),
+            Error::Gpio(_) => (StatusCode::Gpio, [0; 3]),
+            Error::NoConfigSet => (StatusCode::NoConfigSet, [0; 3]),
+            Error::VariantMismatch => (StatusCode::VariantMismatch, [0; 3]),
+            Error::NotAPowerOfTwo => (StatusCode::NotAPowerOfTwo, [0; 3]),
+            Error::FifoTooSmall(provided, max) => (StatusCode::FifoTooSmall, [provided, max, 0]),
+            Error::BufferWrongSize(provided, expected) => {
+                (StatusCode::BufferWrongSize, [provided as u32, expected as u32, 0])
+            }
+            Error::OutputWrongSize(provided, expected) => {
+                (StatusCode::OutputWrongSize, [provided as u32, expected as u32, 0])
+            }
+            Error::GlobalStatusRegisterError(gsr0) => {
+                (StatusCode::GlobalStatusRegisterError, [gsr0.into_bits() as u32, 0, 0])
+            }
+            Error::ResetError => (StatusCode::ResetError, [0; 3]),
+            Error::RegisterMismatch(address, expected, actual) => {
+                (StatusCode::RegisterMismatch, [address as u32, expected, actual])
+            }
+            Error::BurstOutOfRange(start, len) => (StatusCode::BurstOutOfRange, [start as u32, len as u32, 0]),
+            Error::FifoStatusError(fstat) => (StatusCode::FifoStatusError, [fstat.into_bits(), 0, 0]),
+            Error::ShapeMismatch => (StatusCode::ShapeMismatch, [0; 3]),
+            Error::InvalidParameter => (StatusCode::InvalidParameter, [0; 3]),
+            Error::Io(_) => (StatusCode::Io, [0; 3]),
+            Error::EndOfRecording => (StatusCode::EndOfRecording, [0; 3]),
+        };
+
+        Status { code, details }
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self.code {
+            StatusCode::Ok => write!(f, "OK"),
+            code => write!(f, "{:?} {:?}", code, self.details),
+        }
+    }
+}
+
+/// Writes packets to an [`embedded_io::Write`] sink, without allocations.
+#[derive(Debug)]
+pub struct Encoder<W: Write> {
+    writer: W,
+    sequence: u16,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Creates an encoder and writes a delimiter, so that the receiver discards any partial packet received before.
+    pub fn new(mut writer: W) -> Result<Self, Error> {
+        writer.write_all(&[DELIMITER]).map_err(io_error)?;
+
+        Ok(Encoder { writer, sequence: 0 })
+    }
+
+    /// Sends the variant, chip ID and config of the radar.
+    pub fn send_config(&mut self, header: &Header) -> Result<(), Error> {
+        let mut packet = self.start(TYPE_CONFIG)?;
+        packet.put(&header.to_bytes())?;
+        packet.finish()
+    }
+
+    /// Sends a frame as returned by [`crate::Radar::get_fifo_data()`], with its frame counter and a timestamp in microseconds.
+    pub fn send_frame(&mut self, frame_counter: u32, timestamp_us: u64, frame: &[u16]) -> Result<(), Error> {
+        let mut packet = self.start(TYPE_FRAME)?;
+        packet.put(&frame_counter.to_le_bytes())?;
+        packet.put(&timestamp_us.to_le_bytes())?;
+
+        let mut bytes = [0u8; CHUNK_SAMPLES * 2];
+        for samples in frame.chunks(CHUNK_SAMPLES) {
+            for (sample, chunk) in samples.iter().zip(bytes.chunks_exact_mut(2)) {
+                chunk.copy_from_slice(&sample.to_le_bytes());
+            }
+            packet.put(&bytes[..samples.len() * 2])?;
+        }
+
+        packet.finish()
+    }
+
+    /// Sends the detections of a frame.
+    ///
+    /// Returns [`Error::InvalidParameter`] if there are more than 65535 detections or a bin exceeds 65535, before anything is sent.
+    pub fn send_detections(&mut self, frame_counter: u32, detections: &[Detection]) -> Result<(), Error> {
+        let count = u16::try_from(detections.len()).map_err(|_| Error::InvalidParameter)?;
+        let max = u16::MAX as usize;
+        if detections.iter().any(|detection| detection.range_bin > max || detection.doppler_bin > max) {
+            return Err(Error::InvalidParameter);
+        }
+
+        let mut packet = self.start(TYPE_DETECTIONS)?;
+        packet.put(&frame_counter.to_le_bytes())?;
+        packet.put(&count.to_le_bytes())?;
+        for detection in detections {
+            let mut bytes = [0u8; DETECTION_SIZE];
+            bytes[0..2].copy_from_slice(&(detection.range_bin as u16).to_le_bytes());
+            bytes[2..4].copy_from_slice(&(detection.doppler_bin as u16).to_le_bytes());
+            bytes[4..8].copy_from_slice(&detection.range_m.to_le_bytes());
+            bytes[8..12].copy_from_slice(&detection.velocity_mps.to_le_bytes());
+            bytes[12..16].copy_from_slice(&detection.snr_db.to_le_bytes());
+            packet.put(&bytes)?;
+        }
+
+        packet.finish()
+    }
+
+    /// Sends a status, e.g. `Status::OK` as a heartbeat, or `Status::from(&error)` after an error of the radar.
+    pub fn send_status(&mut self, status: Status) -> Result<(), Error> {
+        let mut packet = self.start(TYPE_STATUS)?;
+        packet.put(&status.to_bytes())?;
+        packet.finish()
+    }
+
+    /// The sequence number of the next packet.
+    pub fn sequence(&self) -> u16 {
+        self.sequence
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(io_error)
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn start(&mut self, message_type: u8) -> Result<PacketWriter<'_, W>, Error> {
+        let sequence = self.sequence;
+        self.sequence = self.sequence.wrapping_add(1);
+
+        let mut packet = PacketWriter {
+            writer: &mut self.writer,
+            group: [0; MAX_GROUP],
+            len: 0,
+            crc: Crc32::new(),
+        };
+        packet.put(&[VERSION, message_type])?;
+        packet.put(&sequence.to_le_bytes())?;
+        Ok(packet)
+    }
+}
+
+/// COBS-encodes a packet while it is written, and computes its CRC.
+struct PacketWriter<'a, W: Write> {
+    writer: &'a mut W,
+    group: [u8; MAX_GROUP],
+    len: usize,
+    crc: Crc32,
+}
+
+impl<W: Write> PacketWriter<'_, W> {
+    fn put(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.crc.update(bytes);
+        self.encode(bytes)
+    }
+
+    /// Appends the CRC and the delimiter.
+    fn finish(mut self) -> Result<(), Error> {
+        let crc = self.crc.finish();
+        self.encode(&crc.to_le_bytes())?;
+        self.write_group()?;
+        self.writer.write_all(&[DELIMITER]).map_err(io_error)
+    }
+
+    fn encode(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        for &byte in bytes {
+            if byte == 0 {
+                self.write_group()?;
+            } else {
+                self.group[self.len] = byte;
+                self.len += 1;
+                if self.len == MAX_GROUP {
+                    self.write_group()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the current group with its code, which is the offset to the next zero (or 0xFF for a full group without zero).
+    fn write_group(&mut self) -> Result<(), Error> {
+        self.writer.write_all(&[self.len as u8 + 1]).map_err(io_error)?;
+        self.writer.write_all(&self.group[..self.len]).map_err(io_error)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+/// A message decoded by a [`Decoder`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub enum Message {
+    Config(Header),
+    /// A raw frame, with the samples in the interleaved layout of the FIFO.
+    Frame {
+        frame_counter: u32,
+        timestamp_us: u64,
+        samples: Vec<u16>,
+    },
+    Detections {
+        frame_counter: u32,
+        detections: Vec<Detection>,
+    },
+    Status(Status),
+}
+
+/// A packet decoded by a [`Decoder`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct Packet {
+    pub sequence: u16,
+    pub message: Message,
+}
+
+/// Counters of a [`Decoder`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// The number of packets decoded successfully.
+    pub packets: u64,
+    /// The number of packets discarded because of an invalid encoding, CRC, version, message type or length,
+    /// or because they exceeded the maximum packet size.
+    pub corrupted: u64,
+    /// The number of packets missing in the sequence numbers of the decoded packets, including the corrupted ones.
+    pub lost: u64,
+}
+
+/// Decodes packets from a stream of received bytes.
+///
+/// This requires the std feature.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+    max_packet_size: usize,
+    /// Whether the current packet exceeded the maximum size and is discarded up to the next delimiter.
+    overflow: bool,
+    next_sequence: Option<u16>,
+    header: Option<Header>,
+    statistics: Statistics,
+}
+
+#[cfg(feature = "std")]
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Decoder {
+    /// The default maximum size of an encoded packet, in bytes.
+    pub const DEFAULT_MAX_PACKET_SIZE: usize = 1 << 20;
+
+    pub fn new() -> Self {
+        Self::with_max_packet_size(Self::DEFAULT_MAX_PACKET_SIZE)
+    }
+
+    /// Creates a decoder which discards encoded packets larger than the given size, to limit the memory used when delimiters are lost.
+    pub fn with_max_packet_size(max_packet_size: usize) -> Self {
+        Decoder {
+            buffer: Vec::new(),
+            max_packet_size,
+            overflow: false,
+            next_sequence: None,
+            header: None,
+            statistics: Statistics::default(),
+        }
+    }
+
+    /// Processes a received byte, and returns a packet (or the error of a corrupted packet) when the byte completes one.
+    pub fn push(&mut self, byte: u8) -> Option<Result<Packet, Error>> {
+        if byte != DELIMITER {
+            if self.overflow {
+                // Discarded up to the next delimiter
+            } else if self.buffer.len() < self.max_packet_size {
+                self.buffer.push(byte);
+            } else {
+                self.buffer.clear();
+                self.overflow = true;
+            }
+            return None;
+        }
+
+        if self.overflow {
+            self.overflow = false;
+            self.statistics.corrupted += 1;
+            return Some(Err(invalid_data()));
+        }
+        // Consecutive delimiters, e.g. after the start of an encoder
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        let result = cobs_decode(&self.buffer).and_then(|packet| self.parse(&packet));
+        self.buffer.clear();
+        match &result {
+            Ok(_) => self.statistics.packets += 1,
+            Err(_) => self.statistics.corrupted += 1,
+        }
+        Some(result)
+    }
+
+    /// Processes received bytes, and returns the packets (and the errors of corrupted packets) they complete.
+    pub fn feed<'a>(&'a mut self, bytes: &'a [u8]) -> impl Iterator<Item = Result<Packet, Error>> + 'a {
+        bytes.iter().filter_map(move |&byte| self.push(byte))
+    }
+
+    /// The last config received, if any.
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    /// Shapes the samples of a raw frame with the last config received, as returned by [`crate::Radar::get_frames()`].
+    ///
+    /// Returns [`Error::NoConfigSet`] if no config has been received yet.
+    pub fn frame_to_array(&self, samples: Vec<u16>) -> Result<Array3<u16>, Error> {
+        let header = self.header.as_ref().ok_or(Error::NoConfigSet)?;
+        let frame_size = header.config.get_fifo_limit();
+        if samples.len() != frame_size {
+            return Err(Error::BufferWrongSize(samples.len(), frame_size));
+        }
+        crate::frames_to_array(&header.config, samples)
+    }
+
+    pub fn statistics(&self) -> Statistics {
+        self.statistics
+    }
+
+    fn parse(&mut self, packet: &[u8]) -> Result<Packet, Error> {
+        if packet.len() < PACKET_HEADER_SIZE + CRC_SIZE {
+            return Err(invalid_data());
+        }
+
+        let (data, crc) = packet.split_at(packet.len() - CRC_SIZE);
+        let mut expected = Crc32::new();
+        expected.update(data);
+        if expected.finish().to_le_bytes() != crc {
+            return Err(invalid_data());
+        }
+        if data[0] != VERSION {
+            return Err(invalid_data());
+        }
+
+        let message_type = data[1];
+        let sequence = u16::from_le_bytes([data[2], data[3]]);
+        let payload = &data[PACKET_HEADER_SIZE..];
+
+        let message = match message_type {
+            TYPE_CONFIG => Message::Config(Header::from_bytes(payload).map_err(|_| invalid_data())?),
+            TYPE_FRAME => {
+                let (fields, samples) = payload.split_at_checked(4 + 8).ok_or_else(invalid_data)?;
+                if samples.len() % 2 != 0 {
+                    return Err(invalid_data());
+                }
+                Message::Frame {
+                    frame_counter: u32::from_le_bytes(fields[0..4].try_into().unwrap()),
+                    timestamp_us: u64::from_le_bytes(fields[4..12].try_into().unwrap()),
+                    samples: samples
+                        .chunks_exact(2)
+                        .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+                        .collect(),
+                }
+            }
+            TYPE_DETECTIONS => {
+                let (fields, detections) = payload.split_at_checked(4 + 2).ok_or_else(invalid_data)?;
+                let count = u16::from_le_bytes([fields[4], fields[5]]) as usize;
+                if detections.len() != count * DETECTION_SIZE {
+                    return Err(invalid_data());
+                }
+                let f32_at = |bytes: &[u8], offset: usize| f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+                Message::Detections {
+                    frame_counter: u32::from_le_bytes(fields[0..4].try_into().unwrap()),
+                    detections: detections
+                        .chunks_exact(DETECTION_SIZE)
+                        .map(|bytes| Detection {
+                            range_bin: u16::from_le_bytes([bytes[0], bytes[1]]) as usize,
+                            doppler_bin: u16::from_le_bytes([bytes[2], bytes[3]]) as usize,
+                            range_m: f32_at(bytes, 4),
+                            velocity_mps: f32_at(bytes, 8),
+                            snr_db: f32_at(bytes, 12),
+                        })
+                        .collect(),
+                }
+            }
+            TYPE_STATUS => {
+                if payload.len() != STATUS_SIZE {
+                    return Err(invalid_data());
+                }
+                let code = StatusCode::from_u8(payload[0]).ok_or_else(invalid_data)?;
+                let mut details = [0u32; 3];
+                for (detail, chunk) in details.iter_mut().zip(payload[1..].chunks_exact(4)) {
+                    *detail = u32::from_le_bytes(chunk.try_into().unwrap());
+                }
+                Message::Status(Status { code, details })
+            }
+            _ => return Err(invalid_data()),
+        };
+
+        // Only valid packets are trusted for the sequence numbers
+        if let Some(next_sequence) = self.next_sequence {
+            self.statistics.lost += sequence.wrapping_sub(next_sequence) as u64;
+        }
+        self.next_sequence = Some(sequence.wrapping_add(1));
+        if let Message::Config(header) = &message {
+            self.header = Some(header.clone());
+        }
+
+        Ok(Packet { sequence, message })
+    }
+}
+
+/// Decodes a COBS-encoded packet without its delimiter.
+#[cfg(feature = "std")]
+fn cobs_decode(encoded: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut position = 0;
+    while position < encoded.len() {
+        let code = encoded[position] as usize;
+        position += 1;
+        let end = position + code - 1;
+        if end > encoded.len() {
+            return Err(invalid_data());
+        }
+        decoded.extend_from_slice(&encoded[position..end]);
+        position = end;
+        // Every group except a full one and the last one is followed by a zero
+        if code != MAX_GROUP + 1 && position < encoded.len() {
+            decoded.push(0);
+        }
+    }
+    Ok(decoded)
+}
+
+/// The error of a corrupted packet.
+#[cfg(feature = "std")]
+fn invalid_data() -> Error {
+    Error::Io(embedded_io::ErrorKind::InvalidData)
+}
+
+fn io_error<E: embedded_io::Error>(error: E) -> Error {
+    Error::Io(error.kind())
+}
@@ -0,0 +1,331 @@
+//! A versioned recording format for raw frames, to capture data on a device and analyze it later on a host.
+//!
+//! A recording starts with a [`Header`], followed by one record per frame. All values are little-endian.
+//!
+//! | Field | Type |
+//! |---|---|
+//! | magic | `BGTR` |
+//! | version | u8 |
+//! | variant | u8 (0: BGT60TR13C, 1: BGT60UTR11AIP) |
+//! | chip ID | u32 (raw register value) |
+//! | rx_antennas, tx_antennas, tx_power_level, if_gain_db | u8 each |
+//! | lower_frequency_hz, upper_frequency_hz | u64 each |
+//! | num_chirps_per_frame | u8 |
+//! | num_samples_per_chirp | u16 |
+//! | chirp_repetition_time_s, frame_repetition_time_s | f64 each |
+//! | sample_rate_hz | u32 |
+//! | registers | 38 x u32 |
+//!
+//! Each record holds the frame counter (u32), a timestamp in microseconds (u64), the number of samples (u32),
+//! and the samples (u16 each) in the interleaved layout of the FIFO, as written by [`crate::Radar::get_fifo_data()`].
+//!
+//! The [`Recorder`] writes recordings to any [`embedded_io::Write`] sink (e.g. a UART or an SD card) without allocations.
+//! With the `std` feature, [`RecordingReader`] reads them back into frames with the same shape as [`crate::Radar::get_frames()`].
+
+use embedded_io::Write;
+
+#[cfg(feature = "std")]
+use alloc::vec;
+#[cfg(feature = "std")]
+use ndarray::Array3;
+
+use crate::Variant;
+use crate::config::Config;
+use crate::error::Error;
+use crate::register::CHIP_ID;
+
+/// Magic bytes at the start of a recording.
+const MAGIC: [u8; 4] = *b"BGTR";
+
+/// Version of the recording format.
+const VERSION: u8 = 1;
+
+/// The size of a record header (frame counter, timestamp and number of samples), in bytes.
+pub const RECORD_HEADER_SIZE: usize = 4 + 8 + 4;
+
+/// The number of samples converted at once when writing a record.
+const CHUNK_SAMPLES: usize = 32;
+
+/// The header of a recording, describing the radar and its configuration.
+#[derive(Debug, Clone)]
+pub struct Header {
+    pub variant: Variant,
+    pub chip_id: CHIP_ID,
+    pub config: Config,
+}
+
+impl Header {
+    /// The size of a serialized header, in bytes.
+    pub const SIZE: usize = MAGIC.len() + 1 + 1 + 4 + 4 + 2 * 8 + 1 + 2 + 2 * 8 + 4 + 4 * 38;
+
+    /// Serializes the header into a little-endian byte array, see the [module documentation](self) for the layout.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        let config = &self.config;
+        let variant = match self.variant {
+            Variant::BGT60TR13C => 0,
+            Variant::BGT60UTR11AIP => 1,
+        };
+
+        let mut cursor = Cursor::new(&mut bytes);
+        cursor.put(&MAGIC);
+        cursor.put(&[VERSION, variant]);
+        cursor.put(&self.chip_id.into_bits().to_le_bytes());
+        cursor.put(&[config.rx_antennas, config.tx_antennas, config.tx_power_level, config.if_gain_db]);
+        cursor.put(&config.lower_frequency_hz.to_le_bytes());
+        cursor.put(&config.upper_frequency_hz.to_le_bytes());
+        cursor.put(&[config.num_chirps_per_frame]);
+        cursor.put(&config.num_samples_per_chirp.to_le_bytes());
+        cursor.put(&config.chirp_repetition_time_s.to_le_bytes());
+        cursor.put(&config.frame_repetition_time_s.to_le_bytes());
+        cursor.put(&config.sample_rate_hz.to_le_bytes());
+        for register in config.registers {
+            cursor.put(&register.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    /// Deserializes a header written by [`Header::to_bytes()`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != Self::SIZE {
+            return Err(Error::BufferWrongSize(bytes.len(), Self::SIZE));
+        }
+        if bytes[..4] != MAGIC || bytes[4] != VERSION {
+            return Err(Error::InvalidParameter);
+        }
+
+        let variant = match bytes[5] {
+            0 => Variant::BGT60TR13C,
+            1 => Variant::BGT60UTR11AIP,
+            _ => return Err(Error::InvalidParameter),
+        };
+
+        // Unwraps are safe, since the size of the header has been checked and the fields have fixed sizes
+        let mut reader = Reader { bytes: &bytes[6..] };
+        let chip_id = CHIP_ID::from_bits(u32::from_le_bytes(reader.take().unwrap()));
+        let [rx_antennas, tx_antennas, tx_power_level, if_gain_db] = reader.take().unwrap();
+        let lower_frequency_hz = u64::from_le_bytes(reader.take().unwrap());
+        let upper_frequency_hz = u64::from_le_bytes(reader.take().unwrap());
+        let [num_chirps_per_frame] = reader.take().unwrap();
+        let num_samples_per_chirp = u16::from_le_bytes(reader.take().unwrap());
+        let chirp_repetition_time_s = f64::from_le_bytes(reader.take().unwrap());
+        let frame_repetition_time_s = f64::from_le_bytes(reader.take().unwrap());
+        let sample_rate_hz = u32::from_le_bytes(reader.take().unwrap());
+        let mut registers = [0u32; 38];
+        for register in registers.iter_mut() {
+            *register = u32::from_le_bytes(reader.take().unwrap());
+        }
+
+        Ok(Header {
+            variant,
+            chip_id,
+            config: Config::new(
+                rx_antennas,
+                tx_antennas,
+                tx_power_level,
+                if_gain_db,
+                lower_frequency_hz,
+                upper_frequency_hz,
+                num_chirps_per_frame,
+                num_samples_per_chirp,
+                chirp_repetition_time_s,
+                frame_repetition_time_s,
+                sample_rate_hz,
+                registers,
+            ),
+        })
+    }
+}
+
+/// Writes a recording to an [`embedded_io::Write`] sink, without allocations.
+///
+/// The header is written on creation, followed by one record per call of [`Recorder::write_frame()`].
+#[derive(Debug)]
+pub struct Recorder<W: Write> {
+    writer: W,
+    frame_size: usize,
+    frames_written: u32,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Creates a recorder and writes the header, e.g. with the variant, chip ID and config of a [`crate::Radar`]
+    /// (see [`crate::Radar::variant()`], [`crate::Radar::get_chip_id()`] and [`crate::Radar::config()`]).
+    pub fn new(mut writer: W, header: &Header) -> Result<Self, Error> {
+        writer.write_all(&header.to_bytes()).map_err(io_error)?;
+
+        Ok(Recorder {
+            writer,
+            frame_size: header.config.get_fifo_limit(),
+            frames_written: 0,
+        })
+    }
+
+    /// Writes a frame as returned by [`crate::Radar::get_fifo_data()`], with its frame counter and a timestamp in microseconds.
+    pub fn write_frame(&mut self, frame_counter: u32, timestamp_us: u64, frame: &[u16]) -> Result<(), Error> {
+        if frame.len() != self.frame_size {
+            return Err(Error::BufferWrongSize(frame.len(), self.frame_size));
+        }
+
+        let mut header = [0u8; RECORD_HEADER_SIZE];
+        let mut cursor = Cursor::new(&mut header);
+        cursor.put(&frame_counter.to_le_bytes());
+        cursor.put(&timestamp_us.to_le_bytes());
+        cursor.put(&(frame.len() as u32).to_le_bytes());
+        self.writer.write_all(&header).map_err(io_error)?;
+
+        let mut bytes = [0u8; CHUNK_SAMPLES * 2];
+        for samples in frame.chunks(CHUNK_SAMPLES) {
+            for (sample, chunk) in samples.iter().zip(bytes.chunks_exact_mut(2)) {
+                chunk.copy_from_slice(&sample.to_le_bytes());
+            }
+            self.writer.write_all(&bytes[..samples.len() * 2]).map_err(io_error)?;
+        }
+
+        self.frames_written = self.frames_written.wrapping_add(1);
+        Ok(())
+    }
+
+    /// The number of frames written since creation.
+    pub fn frames_written(&self) -> u32 {
+        self.frames_written
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(io_error)
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// A frame read from a recording.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub frame_counter: u32,
+    pub timestamp_us: u64,
+    /// The frame with the shape `[rx_antennas, num_chirps_per_frame, num_samples_per_chirp]`, as returned by [`crate::Radar::get_frames()`].
+    pub frame: Array3<u16>,
+}
+
+/// Reads a recording written by a [`Recorder`] from any [`std::io::Read`] source.
+///
+/// This requires the std feature. The records are returned by [`RecordingReader::next_record()`], or by iterating over the reader.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct RecordingReader<R: std::io::Read> {
+    reader: R,
+    header: Header,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> RecordingReader<R> {
+    /// Reads the header of the recording.
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut bytes = [0u8; Header::SIZE];
+        reader.read_exact(&mut bytes).map_err(std_io_error)?;
+        let header = Header::from_bytes(&bytes)?;
+
+        Ok(RecordingReader { reader, header })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Reads the next record, or returns `None` at the end of the recording.
+    ///
+    /// A record that is cut off (e.g. when the device lost power while recording) is reported as [`embedded_io::ErrorKind::InvalidData`].
+    pub fn next_record(&mut self) -> Result<Option<Record>, Error> {
+        let mut header = [0u8; RECORD_HEADER_SIZE];
+        let mut len = 0;
+        while len < header.len() {
+            match self.reader.read(&mut header[len..]) {
+                Ok(0) if len == 0 => return Ok(None),
+                Ok(0) => return Err(Error::Io(embedded_io::ErrorKind::InvalidData)),
+                Ok(read) => len += read,
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(std_io_error(error)),
+            }
+        }
+
+        // Unwraps are safe, since the record header has a fixed size
+        let mut reader = Reader { bytes: &header };
+        let frame_counter = u32::from_le_bytes(reader.take().unwrap());
+        let timestamp_us = u64::from_le_bytes(reader.take().unwrap());
+        let num_samples = u32::from_le_bytes(reader.take().unwrap()) as usize;
+
+        let frame_size = self.header.config.get_fifo_limit();
+        if num_samples != frame_size {
+            return Err(Error::BufferWrongSize(num_samples, frame_size));
+        }
+
+        let mut bytes = vec![0u8; num_samples * 2];
+        self.reader.read_exact(&mut bytes).map_err(|error| match error.kind() {
+            std::io::ErrorKind::UnexpectedEof => Error::Io(embedded_io::ErrorKind::InvalidData),
+            _ => std_io_error(error),
+        })?;
+        let samples = bytes
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect();
+
+        Ok(Some(Record {
+            frame_counter,
+            timestamp_us,
+            frame: crate::frames_to_array(&self.header.config, samples)?,
+        }))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Iterator for RecordingReader<R> {
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+fn io_error<E: embedded_io::Error>(error: E) -> Error {
+    Error::Io(error.kind())
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn std_io_error(error: std::io::Error) -> Error {
+    Error::Io(embedded_io::Error::kind(&error))
+}
+
+/// Writes consecutive fields into a byte array.
+struct Cursor<'a> {
+    bytes: &'a mut [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(bytes: &'a mut [u8]) -> Self {
+        Cursor { bytes, position: 0 }
+    }
+
+    /// Panics if the field exceeds the array, the callers use arrays with the exact size of their fields.
+    fn put(&mut self, field: &[u8]) {
+        self.bytes[self.position..self.position + field.len()].copy_from_slice(field);
+        self.position += field.len();
+    }
+}
+
+/// Reads consecutive fields from a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (field, rest) = self.bytes.split_first_chunk::<N>()?;
+        self.bytes = rest;
+        Some(*field)
+    }
+}
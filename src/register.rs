@@ -127,6 +127,53 @@ pub struct MAIN {
     __: usize,
 }
 
+/// Bits of the MAIN register that are cleared by the chip right after they have been acted upon.
+const MAIN_SELF_CLEARING: u32 = MAIN::new()
+    .with_frame_start(true)
+    .with_sw_reset(true)
+    .with_fsm_reset(true)
+    .with_fifo_reset(true)
+    .into_bits();
+
+/// Bits of the SFCTL register that are defined in [`SFCTL`], the reserved bits in between are not guaranteed to read back as written.
+const SFCTL_DEFINED: u32 = SFCTL::new()
+    .with_fifo_cref(0x1FFF)
+    .with_fifo_lp_mode(true)
+    .with_miso_hs_rd(true)
+    .with_lfsr_en(true)
+    .with_prefix_en(true)
+    .into_bits();
+
+/// Returns the mask of the bits of a register that hold their written value and can therefore be verified by reading them back.
+///
+/// - Write-only and self-clearing bits, such as `MAIN.frame_start` or `MAIN.sw_reset`, always read back as zero and are masked out.
+/// - Only the fields defined in [`SFCTL`] are compared, its reserved bits are masked out.
+/// - Read-only status registers (CHIP_ID, STAT1, STAT0, SADC_RESULT, FSTAT) are not compared at all.
+///
+/// All other registers are compared in full. Registers with volatile bits that are not listed above,
+/// e.g. trigger or status bits of registers that are not modelled in this module, will falsely fail the verification
+/// if such a bit is set in the written configuration.
+pub fn readback_mask(addr: u8) -> u32 {
+    const READ_ONLY: [Register; 6] = [
+        Register::CHIP_ID,
+        Register::STAT1,
+        Register::STAT0,
+        Register::SADC_RESULT,
+        Register::FSTAT_TR13C,
+        Register::FSTAT_UTR11,
+    ];
+
+    if addr == Register::MAIN as u8 {
+        0x00FFFFFF & !MAIN_SELF_CLEARING
+    } else if addr == Register::SFCTL as u8 {
+        SFCTL_DEFINED
+    } else if READ_ONLY.iter().any(|&reg| reg as u8 == addr) {
+        0
+    } else {
+        0x00FFFFFF
+    }
+}
+
 #[bitfield(u32)]
 #[allow(non_camel_case_types)]
 pub struct CHIP_ID {
@@ -190,7 +237,7 @@ pub struct STAT0 {
 #[allow(non_camel_case_types)]
 pub struct FSTAT {
     #[bits(14, access = RO)]
-    fill_status: usize,
+    pub fill_status: usize,
     #[bits(3)]
     __: usize,
     #[bits(1, access = RO)]
@@ -243,6 +290,12 @@ pub struct GSR0 {
     __: usize,
 }
 
+impl FSTAT {
+    pub fn has_error(&self) -> bool {
+        self.clk_num_err() || self.spi_burst_err() || self.fuf_err() || self.fof_err()
+    }
+}
+
 impl GSR0 {
     pub fn has_error(&self) -> bool {
         self.clock_number_error() || self.spi_burst_error() // || self.fou_err() // fou is also set when the FIFO is empty, so we don't check it here, only in the burst read
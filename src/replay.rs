@@ -0,0 +1,202 @@
+//! Replay of recordings through the acquisition API of the driver, to regression-test application code
+//! and signal processing on a host against captured scenes.
+//!
+//! This requires the std feature.
+//!
+//! [`ReplayRadar`] implements [`FrameSource`] like [`crate::Radar`], and returns the frames of a recording in order,
+//! with the same buffer sizes, checks and errors as the driver. The raw FIFO data is packed into 12-bit data blocks
+//! and unpacked again, so the output of [`FrameSource::get_fifo_data()`] matches the one of the driver, including its layout.
+//! After the last frame, [`Error::EndOfRecording`] is returned.
+//!
+//! ```rust,ignore
+//! use bgt60trxx::FrameSource;
+//! use bgt60trxx::recording::RecordingReader;
+//! use bgt60trxx::replay::{ReplayRadar, Timing};
+//!
+//! async fn application(radar: &mut impl FrameSource) { /* ... */ }
+//!
+//! let recording = RecordingReader::new(BufReader::new(File::open("capture.bgtr")?))?;
+//! let config = recording.header().config.clone();
+//! let mut radar = ReplayRadar::from_recording(recording, delay, Timing::AsFastAsPossible);
+//! radar.configure(config).await?;
+//! application(&mut radar).await;
+//! ```
+
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+use embedded_hal_async::delay::DelayNs;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::recording::{Header, Record, RecordingReader};
+use crate::register::CHIP_ID;
+use crate::{FrameSource, Variant, check_fifo_limit, fifo_samples, pack_fifo_data, unpack_fifo_data};
+
+/// The timing of the frames returned by a [`ReplayRadar`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timing {
+    /// Waits for the recorded interval between two frames, like the interrupt of the radar.
+    /// The time spent by the caller between two reads is taken into account.
+    Recorded,
+    /// Returns every frame immediately.
+    AsFastAsPossible,
+}
+
+/// A test double of [`crate::Radar`], which returns the frames of a recording instead of reading them from the radar.
+///
+/// The records can come from a [`RecordingReader`] (see [`ReplayRadar::from_recording()`]),
+/// or from any other source, e.g. frames read with the `npy` or `infineon` modules.
+#[derive(Debug)]
+pub struct ReplayRadar<I, DLY> {
+    header: Header,
+    records: I,
+    delay: DLY,
+    timing: Timing,
+    config: Option<Config>,
+    /// The timestamp of the previous frame, and when it has been returned.
+    previous: Option<(u64, Instant)>,
+    frames_read: u32,
+    frame_counter: Option<u32>,
+}
+
+impl<R, DLY> ReplayRadar<RecordingReader<R>, DLY>
+where
+    R: Read,
+    DLY: DelayNs,
+{
+    /// Replays a recording, with the variant and chip ID of its header.
+    pub fn from_recording(recording: RecordingReader<R>, delay: DLY, timing: Timing) -> Self {
+        let header = recording.header().clone();
+        Self::new(header, recording, delay, timing)
+    }
+}
+
+impl<I, DLY> ReplayRadar<I, DLY>
+where
+    I: Iterator<Item = Result<Record, Error>>,
+    DLY: DelayNs,
+{
+    /// Replays records, with the variant and chip ID of the header.
+    ///
+    /// Like the driver, no frames are returned until a config has been applied with [`FrameSource::configure()`].
+    pub fn new(header: Header, records: impl IntoIterator<IntoIter = I>, delay: DLY, timing: Timing) -> Self {
+        ReplayRadar {
+            header,
+            records: records.into_iter(),
+            delay,
+            timing,
+            config: None,
+            previous: None,
+            frames_read: 0,
+            frame_counter: None,
+        }
+    }
+
+    /// The header of the recording.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// The number of frames returned so far.
+    pub fn frames_read(&self) -> u32 {
+        self.frames_read
+    }
+
+    /// The recorded frame counter of the last frame returned.
+    pub fn frame_counter(&self) -> Option<u32> {
+        self.frame_counter
+    }
+
+    /// Waits until the recorded interval since the previous frame has passed.
+    async fn wait_for_frame(&mut self, timestamp_us: u64) {
+        if let (Timing::Recorded, Some((previous_us, returned))) = (self.timing, self.previous) {
+            let interval = Duration::from_micros(timestamp_us.saturating_sub(previous_us));
+            if let Some(remaining) = interval.checked_sub(returned.elapsed()) {
+                let remaining_us = u32::try_from(remaining.as_micros()).unwrap_or(u32::MAX);
+                self.delay.delay_us(remaining_us).await;
+            }
+        }
+        self.previous = Some((timestamp_us, Instant::now()));
+    }
+}
+
+impl<I, DLY> FrameSource for ReplayRadar<I, DLY>
+where
+    I: Iterator<Item = Result<Record, Error>>,
+    DLY: DelayNs,
+{
+    fn variant(&self) -> Variant {
+        self.header.variant
+    }
+
+    async fn get_chip_id(&mut self) -> Result<CHIP_ID, Error> {
+        Ok(self.header.chip_id)
+    }
+
+    fn config(&self) -> Option<&Config> {
+        self.config.as_ref()
+    }
+
+    /// Applies the config, which must fit into the FIFO of the recorded variant like for the driver,
+    /// and must have the same frame shape as the recording (or returns [`Error::ShapeMismatch`]).
+    async fn configure(&mut self, config: Config) -> Result<(), Error> {
+        check_fifo_limit(self.header.variant, &config)?;
+
+        let recorded = &self.header.config;
+        if (config.rx_antennas, config.num_chirps_per_frame, config.num_samples_per_chirp)
+            != (recorded.rx_antennas, recorded.num_chirps_per_frame, recorded.num_samples_per_chirp)
+        {
+            return Err(Error::ShapeMismatch);
+        }
+
+        self.config = Some(config);
+        Ok(())
+    }
+
+    /// Restarts the timing, so that the next frame is returned immediately.
+    async fn start(&mut self) -> Result<(), Error> {
+        self.previous = None;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), Error> {
+        self.previous = None;
+        Ok(())
+    }
+
+    /// Returns the next frame of the recording, after the recorded interval with [`Timing::Recorded`].
+    ///
+    /// Returns [`Error::EndOfRecording`] after the last frame, and the error of the records (e.g. of a cut off recording) as is.
+    async fn get_fifo_data(&mut self, buffer: &mut [u8], output: &mut [u16]) -> Result<(), Error> {
+        let config = self.config.as_ref().ok_or(Error::NoConfigSet)?;
+        let needed_buffer_size = config.get_u8_buffer_size();
+        let fifo_limit = config.get_fifo_limit();
+        let shape = (
+            config.rx_antennas as usize,
+            config.num_chirps_per_frame as usize,
+            config.num_samples_per_chirp as usize,
+        );
+
+        if buffer.len() != needed_buffer_size {
+            return Err(Error::BufferWrongSize(buffer.len(), needed_buffer_size));
+        }
+        if output.len() != fifo_limit {
+            return Err(Error::OutputWrongSize(output.len(), fifo_limit));
+        }
+
+        let record = self.records.next().ok_or(Error::EndOfRecording)??;
+        if record.frame.dim() != shape {
+            return Err(Error::ShapeMismatch);
+        }
+
+        self.wait_for_frame(record.timestamp_us).await;
+
+        pack_fifo_data(fifo_samples(&record.frame), buffer);
+        unpack_fifo_data(buffer, output);
+
+        self.frames_read += 1;
+        self.frame_counter = Some(record.frame_counter);
+        Ok(())
+    }
+}
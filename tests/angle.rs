@@ -0,0 +1,97 @@
+//! Angle-of-arrival estimation from known phase slopes across the L-shaped RX array.
+
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::angle::BGT60TR13C_SPACING_M;
+use bgt60trxx::dsp::{AntennaGeometry, Complex32, Detection, DopplerFft, RangeFft, Window};
+use bgt60trxx::error::Error;
+
+mod common;
+
+use common::antenna_phases;
+
+/// The values of RX1, RX2 and RX3 for a target at the given angles, with phase offsets added per antenna.
+fn antenna_values(azimuth_rad: f32, elevation_rad: f32, offsets_rad: [f32; 3]) -> [Complex32; 3] {
+    let phases = antenna_phases(azimuth_rad, elevation_rad);
+    let mut values = [Complex32::new(0.0, 0.0); 3];
+    for rx in 0..3 {
+        values[rx] = Complex32::from_polar(2.0, phases[rx] + offsets_rad[rx] + 0.7);
+    }
+    values
+}
+
+fn assert_angle(actual: Option<f32>, expected: f32) {
+    let actual = actual.unwrap();
+    assert!((actual - expected).abs() < 1e-4, "{} != {}", actual, expected);
+}
+
+#[test]
+fn recovers_known_angles() {
+    let geometry = AntennaGeometry::bgt60tr13c();
+    for (azimuth, elevation) in [(0.0, 0.0), (0.3, -0.2), (-0.9, 0.5), (1.2, 1.0)] {
+        let angle = geometry.estimate(&antenna_values(azimuth, elevation, [0.0; 3])).unwrap();
+        assert_angle(angle.azimuth_rad, azimuth);
+        assert_angle(angle.elevation_rad, elevation);
+    }
+}
+
+#[test]
+fn applies_phase_offsets() {
+    let offsets = [0.4, -1.1, 0.25];
+    let values = antenna_values(0.3, -0.2, offsets);
+
+    let uncalibrated = AntennaGeometry::bgt60tr13c().estimate(&values).unwrap();
+    assert!((uncalibrated.azimuth_rad.unwrap() - 0.3).abs() > 0.01);
+
+    let angle = AntennaGeometry::bgt60tr13c().with_phase_offsets(offsets).estimate(&values).unwrap();
+    assert_angle(angle.azimuth_rad, 0.3);
+    assert_angle(angle.elevation_rad, -0.2);
+}
+
+#[test]
+fn board_orientation_and_spacing() {
+    let values = antenna_values(0.3, -0.2, [0.0; 3]);
+
+    // A chip rotated by 90° swaps the axes
+    let angle = AntennaGeometry::bgt60tr13c().rotated().estimate(&values).unwrap();
+    assert_angle(angle.azimuth_rad, -0.2);
+    assert_angle(angle.elevation_rad, 0.3);
+
+    let mut geometry = AntennaGeometry::bgt60tr13c();
+    geometry.azimuth.as_mut().unwrap().invert = true;
+    assert_angle(geometry.estimate(&values).unwrap().azimuth_rad, -0.3);
+
+    // The nominal spacing of half a wavelength at 60 GHz is slightly larger at higher center frequencies
+    let config = Config::high_framerate_preset();
+    let geometry = AntennaGeometry::bgt60tr13c().with_spacing_m(BGT60TR13C_SPACING_M, &config);
+    let spacing = geometry.azimuth.unwrap().spacing_wavelengths;
+    let expected = BGT60TR13C_SPACING_M as f64 * config.center_frequency_hz() as f64 / 299_792_458.0;
+    assert!((spacing as f64 - expected).abs() < 1e-6);
+    assert!(spacing > 0.5 && spacing < 0.52, "{}", spacing);
+    assert_eq!(geometry.elevation.unwrap().spacing_wavelengths, spacing);
+}
+
+#[test]
+fn estimates_detection_from_range_doppler_maps() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let doppler_fft = DopplerFft::new(&config, &range_fft, Window::Hann);
+    let map_size = doppler_fft.map_size();
+
+    let detection = Detection { range_bin: 17, doppler_bin: 3, ..Default::default() };
+    let cell = detection.doppler_bin * doppler_fft.num_range_bins() + detection.range_bin;
+    let mut maps = vec![Complex32::new(0.01, 0.0); 3 * map_size];
+    for (rx, value) in antenna_values(-0.4, 0.1, [0.0; 3]).into_iter().enumerate() {
+        maps[rx * map_size + cell] = value;
+    }
+
+    let geometry = AntennaGeometry::bgt60tr13c();
+    let angle = geometry.estimate_detection(&maps, &doppler_fft, &detection).unwrap();
+    assert_angle(angle.azimuth_rad, -0.4);
+    assert_angle(angle.elevation_rad, 0.1);
+
+    let outside = Detection { range_bin: doppler_fft.num_range_bins(), ..detection };
+    assert!(matches!(geometry.estimate_detection(&maps, &doppler_fft, &outside), Err(Error::InvalidParameter)));
+
+    // A single antenna cannot be used with pairs of different antennas
+    assert!(matches!(geometry.estimate(&[Complex32::new(1.0, 0.0)]), Err(Error::InvalidParameter)));
+}
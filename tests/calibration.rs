@@ -0,0 +1,149 @@
+//! Estimation of the phase and gain offsets of the RX channels from a synthetic corner reflector, also from a replayed recording.
+
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use bgt60trxx::{FrameSource, Variant};
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::{AntennaGeometry, Calibration, Calibrator, Complex32, Frame, RangeFft, Window};
+use bgt60trxx::error::Error;
+use bgt60trxx::recording::{Header, Recorder, RecordingReader};
+use bgt60trxx::register::CHIP_ID;
+use bgt60trxx::replay::{ReplayRadar, Timing};
+use embedded_hal_async::delay::DelayNs;
+
+mod common;
+
+use common::{beat_phase, synthetic_frame};
+
+const PHASES_RAD: [f32; 3] = [0.0, 0.8, -1.3];
+const GAINS: [f32; 3] = [1.0, 0.8, 1.25];
+/// The reflector is centered on a range bin, so that there is no leakage into the neighbouring bins.
+const REFLECTOR_BIN: usize = 20;
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+struct NoDelay;
+
+impl DelayNs for NoDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// A frame with a reflector at boresight, seen by each antenna with its phase and gain offset.
+fn reflector_frame(config: &Config) -> Vec<u16> {
+    synthetic_frame(config, |rx, _, sample| {
+        800.0 * GAINS[rx] * (beat_phase(config, REFLECTOR_BIN as f32, sample) + PHASES_RAD[rx]).cos()
+    })
+}
+
+fn assert_calibration(calibration: &Calibration) {
+    for rx in 0..3 {
+        assert!((calibration.phase_offsets_rad[rx] - PHASES_RAD[rx]).abs() < 0.01, "{:?}", calibration);
+        assert!((calibration.gains[rx] - GAINS[rx]).abs() < 0.01, "{:?}", calibration);
+    }
+}
+
+#[test]
+fn estimates_phase_and_gain_offsets() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let range_m = range_fft.bin_to_range_m(REFLECTOR_BIN);
+    let mut calibrator = Calibrator::new(&range_fft, range_m, 0.1).unwrap();
+    let mut scratch = vec![Complex32::new(0.0, 0.0); range_fft.fft_len()];
+
+    assert!(matches!(calibrator.calibration(None), Err(Error::InvalidParameter)));
+
+    let data = reflector_frame(&config);
+    calibrator.add_frame(&Frame::new(&data, &config).unwrap(), &mut scratch).unwrap();
+    assert_eq!(calibrator.num_chirps(), 16);
+
+    let calibration = calibrator.calibration(Some(31.5)).unwrap();
+    assert_calibration(&calibration);
+    assert_eq!(calibration.temperature_c, Some(31.5));
+
+    // Applied to the range FFT, the antennas are in phase and of the same amplitude
+    let calibrated = range_fft.clone().with_calibration(calibration);
+    let mut values = [Complex32::new(0.0, 0.0); 3];
+    for (rx, value) in values.iter_mut().enumerate() {
+        calibrated.process_chirp(&Frame::new(&data, &config).unwrap(), rx, 0, &mut scratch).unwrap();
+        *value = scratch[REFLECTOR_BIN];
+    }
+    for value in &values[1..] {
+        assert!((value - values[0]).norm() < 0.01 * values[0].norm());
+    }
+
+    // And the angle of the reflector at boresight is zero
+    let angle = AntennaGeometry::bgt60tr13c().with_calibration(&calibration);
+    let mut raw = [Complex32::new(0.0, 0.0); 3];
+    for (rx, value) in raw.iter_mut().enumerate() {
+        range_fft.process_chirp(&Frame::new(&data, &config).unwrap(), rx, 0, &mut scratch).unwrap();
+        *value = scratch[REFLECTOR_BIN];
+    }
+    let angle = angle.estimate(&raw).unwrap();
+    assert!(angle.azimuth_rad.unwrap().abs() < 0.01 && angle.elevation_rad.unwrap().abs() < 0.01);
+}
+
+#[test]
+fn corrections_and_serialization() {
+    let calibration = Calibration {
+        phase_offsets_rad: PHASES_RAD,
+        gains: GAINS,
+        temperature_c: None,
+    };
+    let bytes = calibration.to_bytes();
+    assert_eq!(Calibration::from_bytes(&bytes).unwrap(), calibration);
+
+    let mut corrupted = bytes;
+    corrupted[0] = b'X';
+    assert!(matches!(Calibration::from_bytes(&corrupted), Err(Error::InvalidParameter)));
+    assert!(matches!(Calibration::from_bytes(&bytes[1..]), Err(Error::BufferWrongSize(_, _))));
+
+    // A fourth antenna has no correction, and is left unchanged
+    assert!(calibration.correction(2).is_some());
+    assert_eq!(calibration.correction(3), None);
+    let mut values = [Complex32::new(1.0, 0.0); 4];
+    calibration.apply(&mut values);
+    assert!((values[2].norm() - 1.0 / GAINS[2]).abs() < 1e-6);
+    assert_eq!(values[3], Complex32::new(1.0, 0.0));
+}
+
+#[test]
+fn captures_from_replayed_recording() {
+    let config = Config::high_framerate_preset();
+    let header = Header {
+        variant: Variant::BGT60TR13C,
+        chip_id: CHIP_ID::from_bits(0x0303),
+        config: config.clone(),
+    };
+    let mut recorder = Recorder::new(Vec::new(), &header).unwrap();
+    for frame in 0..3 {
+        recorder.write_frame(frame, frame as u64 * 20_000, &reflector_frame(&config)).unwrap();
+    }
+    let bytes = recorder.into_inner();
+
+    let reader = RecordingReader::new(&bytes[..]).unwrap();
+    let mut radar = ReplayRadar::from_recording(reader, NoDelay, Timing::AsFastAsPossible);
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let mut calibrator = Calibrator::new(&range_fft, range_fft.bin_to_range_m(REFLECTOR_BIN), 0.1).unwrap();
+    let mut buffer = vec![0u8; config.get_u8_buffer_size()];
+    let mut output = vec![0u16; config.get_fifo_limit()];
+    let mut scratch = vec![Complex32::new(0.0, 0.0); range_fft.fft_len()];
+
+    let calibration = block_on(async {
+        radar.configure(config.clone()).await.unwrap();
+        radar.start().await.unwrap();
+        calibrator.capture(&mut radar, 3, &mut buffer, &mut output, &mut scratch, None).await
+    })
+    .unwrap();
+
+    assert_eq!(calibrator.num_chirps(), 3 * 16);
+    assert_calibration(&calibration);
+}
@@ -0,0 +1,100 @@
+//! Detection of synthetic targets above a noise floor with CA- and OS-CFAR.
+
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::{Cfar, CfarKind, Detection, DopplerFft, RangeFft, Window};
+use bgt60trxx::error::Error;
+
+mod common;
+
+use common::noise;
+
+/// A noise floor of magnitude ~1.0, with targets of the given magnitudes at the given cells.
+fn noise_floor(len: usize, targets: &[(usize, f32)]) -> Vec<f32> {
+    let mut state = 7;
+    let mut values: Vec<f32> = (0..len).map(|_| 0.5 + noise(&mut state)).collect();
+    for &(cell, magnitude) in targets {
+        values[cell] = magnitude;
+    }
+    values
+}
+
+#[test]
+fn detects_peaks_in_range_profile() {
+    let config = Config::test_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let profile = noise_floor(range_fft.num_bins(), &[(2, 30.0), (20, 30.0), (40, 12.0)]);
+
+    for kind in [CfarKind::CellAveraging, CfarKind::OrderedStatistic { rank: 0.75 }] {
+        let cfar = Cfar::new(kind, 2, 8, 1e-4).unwrap();
+        let mut scratch = vec![0.0; cfar.scratch_size_1d()];
+        let mut detections = [Detection::default(); 8];
+        let count = cfar.detect_1d(&profile, &range_fft, &mut scratch, &mut detections).unwrap();
+
+        // The target at the edge is detected as well, since the window is shifted inwards
+        let bins: Vec<usize> = detections[..count].iter().map(|detection| detection.range_bin).collect();
+        assert_eq!(bins, [2, 20, 40], "{:?}", kind);
+
+        let detection = &detections[1];
+        assert_eq!(detection.range_m, range_fft.bin_to_range_m(20));
+        assert!(detection.snr_db > 20.0, "{:?}: {} dB", kind, detection.snr_db);
+        assert!(detections[2].snr_db < detection.snr_db);
+    }
+}
+
+#[test]
+fn noise_only_has_no_detections() {
+    let config = Config::test_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let profile = noise_floor(range_fft.num_bins(), &[]);
+
+    let cfar = Cfar::new(CfarKind::CellAveraging, 2, 8, 1e-4).unwrap();
+    let mut scratch = vec![0.0; cfar.scratch_size_1d()];
+    let mut detections = [Detection::default(); 8];
+    assert_eq!(cfar.detect_1d(&profile, &range_fft, &mut scratch, &mut detections).unwrap(), 0);
+}
+
+#[test]
+fn detects_peak_in_range_doppler_map() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let doppler_fft = DopplerFft::new(&config, &range_fft, Window::Hann);
+    let num_range_bins = doppler_fft.num_range_bins();
+
+    // A target next to the wrap-around of the Doppler axis
+    let target = (1, 25);
+    let map = noise_floor(doppler_fft.map_size(), &[(target.0 * num_range_bins + target.1, 40.0)]);
+
+    let cfar = Cfar::new(CfarKind::OrderedStatistic { rank: 0.75 }, 1, 3, 1e-4)
+        .unwrap()
+        .with_doppler_cells(1, 2);
+    assert_eq!(cfar.scratch_size_2d(), 9 * 7 - 3 * 3);
+    let mut scratch = vec![0.0; cfar.scratch_size_2d()];
+    let mut detections = [Detection::default(); 8];
+    let count = cfar.detect_2d(&map, &range_fft, &doppler_fft, &mut scratch, &mut detections).unwrap();
+
+    assert_eq!(count, 1);
+    let detection = &detections[0];
+    assert_eq!((detection.doppler_bin, detection.range_bin), target);
+    assert_eq!(detection.velocity_mps, doppler_fft.bin_to_velocity_mps(target.0));
+}
+
+#[test]
+fn rejects_invalid_settings_and_buffers() {
+    assert!(matches!(Cfar::new(CfarKind::CellAveraging, 2, 0, 1e-4), Err(Error::InvalidParameter)));
+    assert!(matches!(Cfar::new(CfarKind::CellAveraging, 2, 8, 1.0), Err(Error::InvalidParameter)));
+    assert!(matches!(
+        Cfar::new(CfarKind::OrderedStatistic { rank: 1.5 }, 2, 8, 1e-4),
+        Err(Error::InvalidParameter)
+    ));
+
+    let config = Config::test_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let profile = noise_floor(range_fft.num_bins(), &[]);
+    let cfar = Cfar::new(CfarKind::CellAveraging, 2, 8, 1e-4).unwrap();
+    let mut scratch = vec![0.0; 8];
+    let mut detections = [Detection::default(); 8];
+    assert!(matches!(
+        cfar.detect_1d(&profile, &range_fft, &mut scratch, &mut detections),
+        Err(Error::BufferWrongSize(8, 16))
+    ));
+}
@@ -0,0 +1,155 @@
+//! The commands of the bgt60trxx-cli binary, run against the `config/` templates and converted recordings.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use bgt60trxx::config::Config;
+use bgt60trxx::infineon::config_to_json;
+use bgt60trxx::recording::{Header, Recorder, RecordingReader};
+use bgt60trxx::register::CHIP_ID;
+use bgt60trxx::{Variant, fifo_samples};
+use ndarray::Array3;
+use serde_json::Value;
+
+mod common;
+
+use common::TempDir;
+
+fn cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_bgt60trxx-cli")).args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn template(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("config").join(name)
+}
+
+/// Writes the high framerate template with a change to its shape object.
+fn modified_template(directory: &Path, name: &str, change: impl FnOnce(&mut Value)) -> PathBuf {
+    let json = fs::read_to_string(template("radar_high_framerate_config.json")).unwrap();
+    let mut document: Value = serde_json::from_str(&json).unwrap();
+    change(&mut document["device_config"]["fmcw_single_shape"]);
+
+    let path = directory.join(name);
+    fs::write(&path, document.to_string()).unwrap();
+    path
+}
+
+fn frames(config: &Config, count: usize) -> Vec<Array3<u16>> {
+    let shape = (
+        config.rx_antennas as usize,
+        config.num_chirps_per_frame as usize,
+        config.num_samples_per_chirp as usize,
+    );
+    (0..count)
+        .map(|index| Array3::from_shape_fn(shape, |(rx, chirp, sample)| ((rx * 1000 + chirp * 131 + sample * 7 + index * 17) % 4096) as u16))
+        .collect()
+}
+
+#[test]
+fn check_config_accepts_the_templates() {
+    for entry in fs::read_dir(template("")).unwrap() {
+        let path = entry.unwrap().path();
+        let output = cli(&["check-config", "--antennas", "count", path.to_str().unwrap()]);
+        assert!(output.status.success(), "{}: {}", path.display(), stdout(&output));
+        assert!(stdout(&output).ends_with("OK\n"), "{}: {}", path.display(), stdout(&output));
+        assert!(stderr(&output).is_empty(), "{}: {}", path.display(), stderr(&output));
+    }
+
+    // Read by the numbers of the antennas, the [3] of the template is a single antenna, which is pointed out
+    let path = template("radar_high_framerate_config.json");
+    let output = cli(&["check-config", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("rx_antennas: 1"), "{}", stdout(&output));
+    assert!(stderr(&output).contains("pass --antennas count"), "{}", stderr(&output));
+
+    // But not if the numbers are chosen explicitly
+    let output = cli(&["check-config", "--antennas", "numbers", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(stderr(&output).is_empty(), "{}", stderr(&output));
+}
+
+#[test]
+fn check_config_reports_issues() {
+    let directory = TempDir::new("cli-check-config");
+
+    let inverted = modified_template(&directory.0, "inverted.json", |shape| {
+        let lower = shape["lower_frequency_Hz"].take();
+        shape["lower_frequency_Hz"] = shape["upper_frequency_Hz"].take();
+        shape["upper_frequency_Hz"] = lower;
+    });
+    let output = cli(&["check-config", "--antennas", "count", inverted.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("Issue: the upper frequency must be above the lower frequency"), "{}", stdout(&output));
+
+    let overflow = modified_template(&directory.0, "overflow.json", |shape| {
+        shape["num_chirps_per_frame"] = Value::from(64);
+        shape["num_samples_per_chirp"] = Value::from(256);
+    });
+    let output = cli(&["check-config", "--antennas", "count", overflow.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("Issue: a frame does not fit into the FIFO of the BGT60TR13C"), "{}", stdout(&output));
+    assert!(!stdout(&output).contains("Issue: the upper frequency"), "{}", stdout(&output));
+
+    let output = cli(&["check-config", directory.0.join("missing.json").to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with("error: "), "{}", stderr(&output));
+}
+
+#[test]
+fn convert_round_trip() {
+    let directory = TempDir::new("cli-convert");
+    let config = Config::high_framerate_preset();
+    let frames = frames(&config, 3);
+
+    let header = Header {
+        variant: Variant::BGT60TR13C,
+        chip_id: CHIP_ID::from_bits(0x0303),
+        config: config.clone(),
+    };
+    let mut recorder = Recorder::new(Vec::new(), &header).unwrap();
+    for (index, frame) in frames.iter().enumerate() {
+        let data: Vec<u16> = fifo_samples(frame).collect();
+        recorder.write_frame(index as u32, index as u64 * 5_000, &data).unwrap();
+    }
+    let recording = directory.0.join("input.bgtr");
+    fs::write(&recording, recorder.into_inner()).unwrap();
+
+    let path = |name: &str| directory.0.join(name).to_str().unwrap().to_owned();
+    let config_path = path("config.json");
+    fs::write(&config_path, config_to_json(&config)).unwrap();
+
+    // .bgtr to .npy and .npz
+    assert!(cli(&["convert", &path("input.bgtr"), &path("frames.npy")]).status.success());
+    assert!(cli(&["convert", &path("input.bgtr"), &path("frames.npz")]).status.success());
+    assert!(fs::read(path("frames.npz")).unwrap().starts_with(b"PK\x03\x04"));
+
+    // And .npy back to .bgtr, with the config of the frames
+    let output = cli(&["convert", &path("frames.npy"), &path("output.bgtr"), "--config", &config_path]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let bytes = fs::read(path("output.bgtr")).unwrap();
+    let reader = RecordingReader::new(&bytes[..]).unwrap();
+    assert_eq!(reader.header().variant, Variant::BGT60TR13C);
+    assert_eq!(reader.header().config, Config { registers: [0; 38], ..config.clone() });
+    let records: Vec<_> = reader.map(Result::unwrap).collect();
+    assert_eq!(records.iter().map(|record| record.frame.clone()).collect::<Vec<_>>(), frames);
+
+    // Frames without counters are numbered and timed by the frame rate
+    let counters: Vec<_> = records.iter().map(|record| (record.frame_counter, record.timestamp_us)).collect();
+    let timestamp_us = |index: u32| (index as f64 * config.frame_repetition_time_s * 1e6) as u64;
+    assert_eq!(counters, [(0, 0), (1, timestamp_us(1)), (2, timestamp_us(2))]);
+
+    // Reading .npy without a config fails
+    let output = cli(&["convert", &path("frames.npy"), &path("other.bgtr")]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("requires --config"), "{}", stderr(&output));
+}
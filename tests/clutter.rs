@@ -0,0 +1,106 @@
+//! Cancellation of static targets by the clutter removal filters, while moving targets are kept.
+
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::clutter::{ExponentialMti, TwoPulseCanceller, remove_mean_chirp};
+use bgt60trxx::dsp::{Complex32, RangeFft, Window};
+use bgt60trxx::error::Error;
+
+const STATIC_BIN: usize = 10;
+const MOVING_BIN: usize = 30;
+
+/// A range FFT output with a static target, and a target whose phase advances from chirp to chirp and from frame to frame.
+fn range_output(range_fft: &RangeFft, frame: usize) -> Vec<Complex32> {
+    let (num_chirps, num_bins) = (range_fft.num_chirps(), range_fft.num_bins());
+    let mut output = vec![Complex32::new(0.0, 0.0); range_fft.output_size()];
+    for rx in 0..range_fft.rx_antennas() {
+        for chirp in 0..num_chirps {
+            let offset = (rx * num_chirps + chirp) * num_bins;
+            output[offset + STATIC_BIN] = Complex32::from_polar(5.0, 0.3 * rx as f32);
+            output[offset + MOVING_BIN] = Complex32::from_polar(1.0, 0.4 * (frame * num_chirps + chirp) as f32);
+        }
+    }
+    output
+}
+
+/// The largest magnitude of a range bin over all chirps and antennas.
+fn peak(range_fft: &RangeFft, output: &[Complex32], bin: usize) -> f32 {
+    output
+        .chunks_exact(range_fft.num_bins())
+        .map(|chirp| chirp[bin].norm())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn mean_chirp_removes_static_target() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let mut output = range_output(&range_fft, 0);
+
+    remove_mean_chirp(&range_fft, &mut output).unwrap();
+
+    assert!(peak(&range_fft, &output, STATIC_BIN) < 1e-5);
+    assert!(peak(&range_fft, &output, MOVING_BIN) > 0.5);
+
+    let mut wrong_size = vec![Complex32::new(0.0, 0.0); 10];
+    assert!(matches!(remove_mean_chirp(&range_fft, &mut wrong_size), Err(Error::BufferWrongSize(10, _))));
+}
+
+#[test]
+fn exponential_mti_converges_to_background() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let mut background = vec![Complex32::new(0.0, 0.0); ExponentialMti::state_size(&range_fft)];
+    let mut mti = ExponentialMti::new(&range_fft, 0.2, &mut background).unwrap();
+
+    // The background is initialized with the first frame, so the static target is removed right away
+    for frame in 0..10 {
+        let mut output = range_output(&range_fft, frame);
+        mti.process(&mut output).unwrap();
+        assert!(peak(&range_fft, &output, STATIC_BIN) < 1e-4, "frame {}", frame);
+        assert!(peak(&range_fft, &output, MOVING_BIN) > 0.5, "frame {}", frame);
+    }
+
+    // A target that appears is kept at first, and fades into the background
+    let appeared = |frame| {
+        let mut output = range_output(&range_fft, frame);
+        output.chunks_exact_mut(range_fft.num_bins()).for_each(|chirp| chirp[50] = Complex32::new(2.0, 0.0));
+        output
+    };
+    let mut output = appeared(10);
+    mti.process(&mut output).unwrap();
+    let first = peak(&range_fft, &output, 50);
+    for frame in 11..40 {
+        output = appeared(frame);
+        mti.process(&mut output).unwrap();
+    }
+    let last = peak(&range_fft, &output, 50);
+    assert!(first > 1.5 && last < 0.01 * first, "{} -> {}", first, last);
+
+    assert!(matches!(ExponentialMti::new(&range_fft, 0.0, &mut []), Err(Error::InvalidParameter)));
+    assert!(matches!(ExponentialMti::new(&range_fft, 0.5, &mut []), Err(Error::BufferWrongSize(0, _))));
+}
+
+#[test]
+fn two_pulse_canceller_subtracts_previous_frame() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let mut previous = vec![Complex32::new(0.0, 0.0); TwoPulseCanceller::state_size(&range_fft)];
+    let mut canceller = TwoPulseCanceller::new(&range_fft, &mut previous).unwrap();
+
+    // The first frame has no previous frame
+    let mut output = range_output(&range_fft, 0);
+    canceller.process(&mut output).unwrap();
+    assert!(output.iter().all(|value| value.norm() == 0.0));
+
+    let mut output = range_output(&range_fft, 1);
+    let expected = range_output(&range_fft, 1)[MOVING_BIN] - range_output(&range_fft, 0)[MOVING_BIN];
+    canceller.process(&mut output).unwrap();
+    assert!(peak(&range_fft, &output, STATIC_BIN) < 1e-6);
+    assert!((output[MOVING_BIN] - expected).norm() < 1e-6);
+
+    // After a reset, the next frame is the first one again
+    canceller.reset(&range_fft).unwrap();
+    let mut output = range_output(&range_fft, 2);
+    canceller.process(&mut output).unwrap();
+    assert!(output.iter().all(|value| value.norm() == 0.0));
+}
@@ -0,0 +1,65 @@
+//! Synthetic inputs shared by the tests.
+
+#![allow(dead_code)]
+
+use core::f32::consts::PI;
+
+use std::fs;
+use std::path::PathBuf;
+
+use bgt60trxx::config::Config;
+
+/// The ADC mid-scale, around which the synthetic samples oscillate.
+pub const MID_SCALE: f32 = 2048.0;
+
+/// Deterministic pseudo-random numbers in 0.0..1.0
+pub fn noise(state: &mut u32) -> f32 {
+    *state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+    (*state >> 8) as f32 / (1u32 << 24) as f32
+}
+
+/// The phases of RX1, RX2 and RX3 for a target at the given angles, with the antennas half a wavelength apart.
+///
+/// RX3 is the reference of both pairs, RX1 is offset along azimuth and RX2 along elevation.
+pub fn antenna_phases(azimuth_rad: f32, elevation_rad: f32) -> [f32; 3] {
+    [PI * azimuth_rad.sin(), PI * elevation_rad.sin(), 0.0]
+}
+
+/// A frame in the interleaved FIFO layout of `Radar::get_fifo_data()`,
+/// with the samples of `signal(rx, chirp, sample)` around the ADC mid-scale, rounded to ADC counts.
+pub fn synthetic_frame(config: &Config, signal: impl Fn(usize, usize, usize) -> f32) -> Vec<u16> {
+    let rx_antennas = config.rx_antennas as usize;
+    let num_samples = config.num_samples_per_chirp as usize;
+    let mut data = vec![0u16; config.get_fifo_limit()];
+    for chirp in 0..config.num_chirps_per_frame as usize {
+        for sample in 0..num_samples {
+            for rx in 0..rx_antennas {
+                let value = MID_SCALE + signal(rx, chirp, sample);
+                data[chirp * rx_antennas * num_samples + sample * rx_antennas + rx] = value.round() as u16;
+            }
+        }
+    }
+    data
+}
+
+/// The phase of a beat tone at the center of the given range bin, at a sample of a chirp without zero-padding.
+pub fn beat_phase(config: &Config, bin: f32, sample: usize) -> f32 {
+    2.0 * PI * bin * sample as f32 / config.num_samples_per_chirp as f32
+}
+
+/// A temporary directory, which is removed when dropped.
+pub struct TempDir(pub PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("bgt60trxx-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,86 @@
+//! Velocity bin labels of the Doppler FFT against synthetic targets moving at known speeds.
+
+use core::f64::consts::PI;
+
+use bgt60trxx::config::{Config, SPEED_OF_LIGHT};
+use bgt60trxx::dsp::{Complex32, DopplerFft, Frame, RangeFft, Window};
+
+mod common;
+
+use common::{beat_phase, synthetic_frame};
+
+const TARGET_BIN: usize = 12;
+
+/// A frame with a target in the range bin, whose distance grows by `velocity * T_chirp` from chirp to chirp,
+/// which advances the phase of its beat tone by `4 * pi * velocity * T_chirp / lambda`.
+fn moving_frame(config: &Config, velocity_mps: f64) -> Vec<u16> {
+    let wavelength_m = SPEED_OF_LIGHT / config.center_frequency_hz() as f64;
+    let phase_step = 4.0 * PI * velocity_mps * config.chirp_repetition_time_s / wavelength_m;
+    synthetic_frame(config, |_, chirp, sample| {
+        let doppler_phase = (phase_step * chirp as f64).rem_euclid(2.0 * PI) as f32;
+        1000.0 * (beat_phase(config, TARGET_BIN as f32, sample) + doppler_phase).cos()
+    })
+}
+
+/// The range and velocity of the strongest cell of the summed range-Doppler maps.
+fn peak(config: &Config, range_fft: &RangeFft, doppler_fft: &DopplerFft, data: &[u16]) -> (f32, f32) {
+    let mut scratch = vec![Complex32::new(0.0, 0.0); range_fft.fft_len()];
+    let mut range = vec![Complex32::new(0.0, 0.0); range_fft.output_size()];
+    range_fft.process_frame(&Frame::new(data, config).unwrap(), &mut scratch, &mut range).unwrap();
+
+    let mut scratch = vec![Complex32::new(0.0, 0.0); doppler_fft.fft_len()];
+    let mut maps = vec![Complex32::new(0.0, 0.0); range_fft.rx_antennas() * doppler_fft.map_size()];
+    let mut sum = vec![0.0; doppler_fft.map_size()];
+    doppler_fft.process_frame(&range, &mut scratch, &mut maps, &mut sum).unwrap();
+
+    let cell = (0..sum.len()).max_by(|&a, &b| sum[a].total_cmp(&sum[b])).unwrap();
+    let (doppler_bin, range_bin) = (cell / doppler_fft.num_range_bins(), cell % doppler_fft.num_range_bins());
+    (range_fft.bin_to_range_m(range_bin), doppler_fft.bin_to_velocity_mps(doppler_bin))
+}
+
+#[test]
+fn velocity_resolution_and_labels() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let doppler_fft = DopplerFft::new(&config, &range_fft, Window::Hann);
+
+    // lambda / (2 * N * T_chirp) with 16 chirps at a center frequency of 61.25 GHz
+    let wavelength_m = SPEED_OF_LIGHT / 61_250_000_000.0;
+    let expected = wavelength_m / (2.0 * 16.0 * config.chirp_repetition_time_s);
+    let resolution = doppler_fft.velocity_resolution_mps();
+    assert!((resolution as f64 - expected).abs() < 1e-5 * expected, "{} {}", resolution, expected);
+
+    // The zero velocity is in the middle, and the labels cover up to the maximum velocity
+    let labels: Vec<_> = doppler_fft.velocity_bins().collect();
+    assert_eq!(labels.len(), 16);
+    assert_eq!(labels[8], 0.0);
+    assert_eq!(labels[0], -8.0 * resolution);
+    assert_eq!(labels[15], 7.0 * resolution);
+    assert!((-labels[0] as f64 - config.max_velocity_mps()).abs() < 1e-5 * config.max_velocity_mps());
+
+    let padded = DopplerFft::with_fft_len(&config, &range_fft, Window::Hann, 64).unwrap();
+    assert_eq!(padded.velocity_resolution_mps(), resolution / 4.0);
+    assert_eq!(padded.bin_to_velocity_mps(0), labels[0]);
+}
+
+#[test]
+fn targets_at_known_velocities() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let doppler_fft = DopplerFft::new(&config, &range_fft, Window::Hann);
+    let padded = DopplerFft::with_fft_len(&config, &range_fft, Window::Hann, 128).unwrap();
+
+    // Receding targets have a positive velocity
+    for velocity_mps in [0.0, 3.0, -5.5, 8.0, -13.2] {
+        let data = moving_frame(&config, velocity_mps);
+
+        let (range_m, velocity) = peak(&config, &range_fft, &doppler_fft, &data);
+        assert_eq!(range_m, range_fft.bin_to_range_m(TARGET_BIN));
+        let tolerance = 0.5 * doppler_fft.velocity_resolution_mps() as f64;
+        assert!((velocity as f64 - velocity_mps).abs() <= tolerance, "{} {}", velocity, velocity_mps);
+
+        let (_, velocity) = peak(&config, &range_fft, &padded, &data);
+        let tolerance = 0.5 * padded.velocity_resolution_mps() as f64;
+        assert!((velocity as f64 - velocity_mps).abs() <= tolerance, "{} {}", velocity, velocity_mps);
+    }
+}
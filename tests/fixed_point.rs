@@ -0,0 +1,162 @@
+//! Accuracy of the fixed-point (Q15) FFTs against the floating-point reference.
+
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::fft::fft;
+use bgt60trxx::dsp::fixed::{ComplexQ15, DopplerFftQ15, MAX_FFT_LEN, RangeFftQ15, fft_q15, window_q15};
+use bgt60trxx::dsp::{Complex32, DopplerFft, Frame, RangeFft, Window};
+use bgt60trxx::error::Error;
+
+/// Deterministic pseudo-random numbers in -0.5..0.5
+fn noise(state: &mut u32) -> f32 {
+    *state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+    (*state >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+}
+
+/// Signal-to-error ratio in dB of a fixed-point result against the reference
+fn snr_db(reference: &[Complex32], result: &[Complex32]) -> f32 {
+    let signal: f32 = reference.iter().map(|x| x.norm_sqr()).sum();
+    let error: f32 = reference.iter().zip(result).map(|(x, y)| (x - y).norm_sqr()).sum();
+    10.0 * (signal / error).log10()
+}
+
+fn to_float(value: &ComplexQ15, scale: f32) -> Complex32 {
+    Complex32::new(value.re as f32, value.im as f32) * scale / 32768.0
+}
+
+/// Generates a frame in FIFO layout with one target per antenna, moving with a constant phase increment from chirp to chirp.
+fn synthetic_frame(config: &Config, range_cycles: f32, doppler_cycles: f32) -> Vec<u16> {
+    let rx_antennas = config.rx_antennas as usize;
+    let num_chirps = config.num_chirps_per_frame as usize;
+    let num_samples = config.num_samples_per_chirp as usize;
+    let mut state = 1;
+
+    let mut data = vec![0u16; config.get_fifo_limit()];
+    for chirp in 0..num_chirps {
+        for sample in 0..num_samples {
+            for rx in 0..rx_antennas {
+                let phase = 2.0
+                    * std::f32::consts::PI
+                    * (range_cycles * sample as f32 / num_samples as f32
+                        + doppler_cycles * chirp as f32
+                        + 0.1 * rx as f32);
+                let value = 2048.0 + 1500.0 * phase.cos() + 20.0 * noise(&mut state);
+                data[chirp * rx_antennas * num_samples + sample * rx_antennas + rx] = value as u16;
+            }
+        }
+    }
+    data
+}
+
+#[test]
+fn fft_q15_matches_float_fft() {
+    for len in [16, 64, 128, 256, 1024] {
+        let mut state = len as u32;
+        let input: Vec<Complex32> = (0..len)
+            .map(|_| Complex32::new(noise(&mut state), noise(&mut state)))
+            .collect();
+
+        let mut reference = input.clone();
+        fft(&mut reference).unwrap();
+
+        let mut fixed: Vec<ComplexQ15> = input
+            .iter()
+            .map(|x| ComplexQ15::new((x.re * 32768.0) as i16, (x.im * 32768.0) as i16))
+            .collect();
+        fft_q15(&mut fixed).unwrap();
+
+        let result: Vec<Complex32> = fixed.iter().map(|x| to_float(x, len as f32)).collect();
+        let snr = snr_db(&reference, &result);
+        assert!(snr > 40.0, "FFT length {}: SNR {:.1} dB", len, snr);
+    }
+}
+
+#[test]
+fn fft_q15_rejects_invalid_lengths() {
+    let mut buffer = vec![ComplexQ15::new(0, 0); 96];
+    assert!(matches!(fft_q15(&mut buffer), Err(Error::NotAPowerOfTwo)));
+
+    let mut buffer = vec![ComplexQ15::new(0, 0); 2 * MAX_FFT_LEN];
+    assert!(matches!(fft_q15(&mut buffer), Err(Error::InvalidParameter)));
+
+    let config = Config::test_preset();
+    let mut window = vec![0i16; config.num_samples_per_chirp as usize];
+    window_q15(Window::Hann, &mut window);
+    assert!(matches!(RangeFftQ15::new(&config, &window, 64), Err(Error::BufferWrongSize(64, 128))));
+    assert!(matches!(RangeFftQ15::new(&config, &window, 2 * MAX_FFT_LEN), Err(Error::InvalidParameter)));
+}
+
+#[test]
+fn range_fft_q15_matches_float_range_fft() {
+    let config = Config::test_preset();
+    let data = synthetic_frame(&config, 10.3, 0.0);
+
+    let reference_fft = RangeFft::with_fft_len(&config, Window::Hann, 256).unwrap();
+    let mut reference = vec![Complex32::new(0.0, 0.0); 256];
+    reference_fft
+        .process_chirp(&Frame::new(&data, &config).unwrap(), 0, 0, &mut reference)
+        .unwrap();
+
+    let mut window = vec![0i16; config.num_samples_per_chirp as usize];
+    window_q15(Window::Hann, &mut window);
+    let range_fft = RangeFftQ15::new(&config, &window, 256).unwrap();
+    let mut fixed = vec![ComplexQ15::new(0, 0); 256];
+    range_fft.process_chirp(&data, 0, 0, &mut fixed).unwrap();
+
+    let result: Vec<Complex32> = fixed.iter().map(|x| to_float(x, 256.0)).collect();
+    let snr = snr_db(&reference[..128], &result[..128]);
+    assert!(snr > 40.0, "SNR {:.1} dB", snr);
+}
+
+#[test]
+fn doppler_fft_q15_matches_float_doppler_fft() {
+    let config = Config::high_framerate_preset();
+    let data = synthetic_frame(&config, 12.0, 0.2);
+
+    // Floating-point reference
+    let reference_range = RangeFft::new(&config, Window::Hann).unwrap();
+    let reference_doppler = DopplerFft::new(&config, &reference_range, Window::Hann);
+    let mut scratch = vec![Complex32::new(0.0, 0.0); reference_range.fft_len()];
+    let mut range = vec![Complex32::new(0.0, 0.0); reference_range.output_size()];
+    reference_range
+        .process_frame(&Frame::new(&data, &config).unwrap(), &mut scratch, &mut range)
+        .unwrap();
+    let mut scratch = vec![Complex32::new(0.0, 0.0); reference_doppler.fft_len()];
+    let mut reference = vec![Complex32::new(0.0, 0.0); 3 * reference_doppler.map_size()];
+    let mut magnitude_sum = vec![0.0; reference_doppler.map_size()];
+    reference_doppler
+        .process_frame(&range, &mut scratch, &mut reference, &mut magnitude_sum)
+        .unwrap();
+
+    // Fixed-point
+    let mut range_window = vec![0i16; config.num_samples_per_chirp as usize];
+    window_q15(Window::Hann, &mut range_window);
+    let mut doppler_window = vec![0i16; config.num_chirps_per_frame as usize];
+    window_q15(Window::Hann, &mut doppler_window);
+
+    let range_fft = RangeFftQ15::new(&config, &range_window, 128).unwrap();
+    let doppler_fft = DopplerFftQ15::new(&config, &range_fft, &doppler_window, 16).unwrap();
+
+    let mut scratch = vec![ComplexQ15::new(0, 0); range_fft.fft_len()];
+    let mut range = vec![ComplexQ15::new(0, 0); range_fft.output_size()];
+    range_fft.process_frame(&data, &mut scratch, &mut range).unwrap();
+    let mut scratch = vec![ComplexQ15::new(0, 0); doppler_fft.fft_len()];
+    let mut maps = vec![ComplexQ15::new(0, 0); 3 * doppler_fft.map_size()];
+    let mut power_sum = vec![0u32; doppler_fft.map_size()];
+    doppler_fft
+        .process_frame(&range, &mut scratch, &mut maps, &mut power_sum)
+        .unwrap();
+
+    let result: Vec<Complex32> = maps.iter().map(|x| to_float(x, 128.0 * 16.0)).collect();
+    let snr = snr_db(&reference, &result);
+    assert!(snr > 30.0, "SNR {:.1} dB", snr);
+
+    // The strongest cell must be the same in both maps
+    let argmax_float = magnitude_sum
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .unwrap()
+        .0;
+    let argmax_fixed = power_sum.iter().enumerate().max_by_key(|x| *x.1).unwrap().0;
+    assert_eq!(argmax_float, argmax_fixed);
+}
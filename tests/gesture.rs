@@ -0,0 +1,179 @@
+//! Feature extraction of a synthetic moving hand, segmentation of gesture windows and the rule-based classifier.
+
+use core::f32::consts::PI;
+
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::gesture::{
+    FeatureExtractor, Gesture, GestureClassifier, GestureFeatures, GestureSegmenter, RuleBasedClassifier, SegmenterSettings,
+};
+use bgt60trxx::dsp::{AntennaGeometry, Complex32, DopplerFft, Frame, RangeFft, Window};
+use bgt60trxx::error::Error;
+
+mod common;
+
+use common::{antenna_phases, beat_phase, synthetic_frame};
+
+const HAND_BIN: usize = 3;
+const STATIC_BIN: usize = 6;
+/// The phase advance of the hand from chirp to chirp, in Doppler bins.
+const DOPPLER_BINS: usize = 2;
+
+/// A frame with a strong static reflector, and a weaker moving hand at the given angles.
+fn hand_frame(config: &Config, azimuth_rad: f32, elevation_rad: f32) -> Vec<u16> {
+    let num_chirps = config.num_chirps_per_frame as usize;
+    let antenna_phases = antenna_phases(azimuth_rad, elevation_rad);
+    synthetic_frame(config, |rx, chirp, sample| {
+        let doppler_phase = 2.0 * PI * (DOPPLER_BINS * chirp) as f32 / num_chirps as f32;
+        900.0 * beat_phase(config, STATIC_BIN as f32, sample).cos()
+            + 300.0 * (beat_phase(config, HAND_BIN as f32, sample) + doppler_phase + antenna_phases[rx]).cos()
+    })
+}
+
+#[test]
+fn extracts_features_of_moving_hand() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let doppler_fft = DopplerFft::new(&config, &range_fft, Window::Hann);
+    let max_range_m = range_fft.bin_to_range_m(STATIC_BIN + 2);
+    let extractor = FeatureExtractor::new(&config, &range_fft, &doppler_fft, AntennaGeometry::bgt60tr13c(), 0.2, max_range_m).unwrap();
+    let mut workspace = vec![Complex32::new(0.0, 0.0); extractor.workspace_size()];
+
+    // The static reflector is stronger, but removed before the Doppler FFT
+    let data = hand_frame(&config, 0.3, -0.2);
+    let features = extractor.extract(&Frame::new(&data, &config).unwrap(), &mut workspace).unwrap();
+    assert_eq!(features.range_m, range_fft.bin_to_range_m(HAND_BIN));
+    let velocity = DOPPLER_BINS as f32 * doppler_fft.velocity_resolution_mps();
+    assert!((features.velocity_mps.abs() - velocity).abs() < 1e-6, "{:?}", features);
+    assert!((features.azimuth_rad - 0.3).abs() < 0.01, "{:?}", features);
+    assert!((features.elevation_rad + 0.2).abs() < 0.01, "{:?}", features);
+    assert!(features.energy > 0.0);
+
+    let mut wrong_size = vec![Complex32::new(0.0, 0.0); 10];
+    let result = extractor.extract(&Frame::new(&data, &config).unwrap(), &mut wrong_size);
+    assert!(matches!(result, Err(Error::BufferWrongSize(10, _))));
+
+    // The range window must contain at least one bin
+    let result = FeatureExtractor::new(&config, &range_fft, &doppler_fft, AntennaGeometry::bgt60tr13c(), 2.0, 1.0);
+    assert!(matches!(result, Err(Error::InvalidParameter)));
+
+    // A single sample per chirp has no range bins at all
+    let single_sample = Config { num_samples_per_chirp: 1, ..config };
+    let range_fft = RangeFft::new(&single_sample, Window::Hann).unwrap();
+    assert_eq!(range_fft.num_bins(), 0);
+    let doppler_fft = DopplerFft::new(&single_sample, &range_fft, Window::Hann);
+    let result = FeatureExtractor::new(&single_sample, &range_fft, &doppler_fft, AntennaGeometry::bgt60tr13c(), 0.0, 1.0);
+    assert!(matches!(result, Err(Error::InvalidParameter)));
+}
+
+fn active(energy: f32) -> GestureFeatures {
+    GestureFeatures { energy, ..Default::default() }
+}
+
+#[test]
+fn segments_windows_with_gaps() {
+    // Gestures of at least 20 frames, ending after a gap of more than 20 frames
+    let config = Config::high_framerate_preset();
+    let frame_time_s = config.frame_repetition_time_s as f32;
+    let settings = SegmenterSettings {
+        energy_threshold: 1.0,
+        min_duration_s: 20.5 * frame_time_s,
+        max_gap_s: 20.5 * frame_time_s,
+    };
+    let mut window = [GestureFeatures::default(); 40];
+    let mut segmenter = GestureSegmenter::new(&config, settings, &mut window).unwrap();
+
+    // Quiet frames before a gesture are ignored
+    for _ in 0..50 {
+        assert_eq!(segmenter.push(active(0.0)), None);
+    }
+
+    // A short gap does not split a gesture
+    for energy in 0..15 {
+        assert_eq!(segmenter.push(active(1.0 + energy as f32)), None);
+    }
+    for _ in 0..20 {
+        assert_eq!(segmenter.push(active(0.5)), None);
+    }
+    for energy in 15..30 {
+        assert_eq!(segmenter.push(active(1.0 + energy as f32)), None);
+    }
+    for _ in 0..20 {
+        assert_eq!(segmenter.push(active(0.0)), None);
+    }
+    let gesture = segmenter.push(active(0.0)).unwrap();
+    let energies: Vec<f32> = gesture.iter().map(|features| features.energy).collect();
+    assert_eq!(energies, (0..30).map(|energy| 1.0 + energy as f32).collect::<Vec<_>>());
+
+    // Windows shorter than the minimum duration are discarded
+    for _ in 0..10 {
+        assert_eq!(segmenter.push(active(1.0)), None);
+    }
+    for _ in 0..21 {
+        assert_eq!(segmenter.push(active(0.0)), None);
+    }
+
+    // Gestures longer than the buffer are truncated
+    for energy in 0..50 {
+        assert_eq!(segmenter.push(active(1.0 + energy as f32)), None);
+    }
+    for _ in 0..20 {
+        assert_eq!(segmenter.push(active(0.0)), None);
+    }
+    let gesture = segmenter.push(active(0.0)).unwrap();
+    assert_eq!(gesture.len(), 40);
+    assert_eq!(gesture[39].energy, 40.0);
+
+    let mut window = [GestureFeatures::default(); 10];
+    assert!(matches!(GestureSegmenter::new(&config, settings, &mut window), Err(Error::BufferWrongSize(10, 20))));
+}
+
+/// A window of 20 frames, linearly moving from the start to the end features.
+fn motion(start: GestureFeatures, end: GestureFeatures) -> Vec<GestureFeatures> {
+    (0..20)
+        .map(|frame| {
+            let t = frame as f32 / 19.0;
+            GestureFeatures {
+                range_m: start.range_m + t * (end.range_m - start.range_m),
+                velocity_mps: start.velocity_mps + t * (end.velocity_mps - start.velocity_mps),
+                azimuth_rad: start.azimuth_rad + t * (end.azimuth_rad - start.azimuth_rad),
+                elevation_rad: start.elevation_rad + t * (end.elevation_rad - start.elevation_rad),
+                energy: 1.0,
+            }
+        })
+        .collect()
+}
+
+fn at(range_m: f32, azimuth_rad: f32, elevation_rad: f32) -> GestureFeatures {
+    GestureFeatures { range_m, azimuth_rad, elevation_rad, energy: 1.0, ..Default::default() }
+}
+
+#[test]
+fn classifies_dominant_motion() {
+    let mut classifier = RuleBasedClassifier::default();
+    let cases = [
+        (at(0.5, -0.6, 0.1), at(0.5, 0.6, 0.0), Some(Gesture::SwipeRight)),
+        (at(0.5, 0.6, 0.0), at(0.5, -0.6, 0.1), Some(Gesture::SwipeLeft)),
+        (at(0.5, 0.1, -0.5), at(0.5, 0.0, 0.5), Some(Gesture::SwipeUp)),
+        (at(0.5, 0.0, 0.5), at(0.5, 0.1, -0.5), Some(Gesture::SwipeDown)),
+        (at(0.6, 0.0, 0.0), at(0.3, 0.1, 0.0), Some(Gesture::Push)),
+        (at(0.3, 0.0, 0.0), at(0.6, 0.0, -0.1), Some(Gesture::Pull)),
+        (at(0.5, 0.0, 0.0), at(0.5, 0.1, 0.1), None),
+    ];
+    for (start, end, expected) in cases {
+        assert_eq!(classifier.classify(&motion(start, end)), expected, "{:?} -> {:?}", start, end);
+    }
+
+    // Within a single range bin, the radial velocity tells a push from a pull
+    let push = GestureFeatures { velocity_mps: -0.5, ..at(0.5, 0.0, 0.0) };
+    assert_eq!(classifier.classify(&motion(push, push)), Some(Gesture::Push));
+    let pull = GestureFeatures { velocity_mps: 0.5, ..at(0.5, 0.0, 0.0) };
+    assert_eq!(classifier.classify(&motion(pull, pull)), Some(Gesture::Pull));
+
+    // Windows without energy or with fewer than 4 frames are not classified
+    let silent: Vec<_> = motion(at(0.5, -0.6, 0.0), at(0.5, 0.6, 0.0))
+        .iter()
+        .map(|features| GestureFeatures { energy: 0.0, ..*features })
+        .collect();
+    assert_eq!(classifier.classify(&silent), None);
+    assert_eq!(classifier.classify(&motion(at(0.5, -0.6, 0.0), at(0.5, 0.6, 0.0))[..3]), None);
+}
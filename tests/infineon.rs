@@ -0,0 +1,174 @@
+//! Round trips of Radar Fusion GUI recordings, raw data files and the JSON device configuration, and the `config/` templates.
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use bgt60trxx::config::{ADC_FULL_SCALE, Config};
+use bgt60trxx::error::Error;
+use bgt60trxx::infineon::{
+    AntennaList, FUSION_CONFIG_FILE, FUSION_DATA_FILE, config_from_json, config_from_json_with, config_to_json, read_fusion_recording, read_raw,
+    write_fusion_recording, write_raw,
+};
+use bgt60trxx::npy::{FloatScale, write_array};
+use ndarray::Array3;
+use serde_json::Value;
+
+mod common;
+
+use common::TempDir;
+
+/// The config as read back from JSON, which has no registers.
+fn without_registers(config: &Config) -> Config {
+    Config { registers: [0; 38], ..config.clone() }
+}
+
+fn frames(config: &Config, count: usize) -> Vec<Array3<u16>> {
+    let shape = (
+        config.rx_antennas as usize,
+        config.num_chirps_per_frame as usize,
+        config.num_samples_per_chirp as usize,
+    );
+    (0..count)
+        .map(|index| Array3::from_shape_fn(shape, |(rx, chirp, sample)| ((index * 1000 + rx * 300 + chirp * 17 + sample) % 4096) as u16))
+        .collect()
+}
+
+fn template(name: &str, antenna_list: AntennaList) -> Config {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("config").join(name);
+    config_from_json_with(&fs::read_to_string(path).unwrap(), antenna_list).unwrap()
+}
+
+#[test]
+fn config_json_round_trip() {
+    for config in [Config::test_preset(), Config::high_framerate_preset()] {
+        let json = config_to_json(&config);
+        assert_eq!(config_from_json(&json).unwrap(), without_registers(&config));
+    }
+
+    // The device configuration is also found nested in a larger document
+    let json = config_to_json(&Config::high_framerate_preset());
+    let nested = format!(r#"{{"recording": {{"version": 1, "devices": [{}]}}}}"#, json);
+    assert_eq!(config_from_json(&nested).unwrap(), without_registers(&Config::high_framerate_preset()));
+}
+
+#[test]
+fn reads_configurator_templates() {
+    // The lists of antennas are read by their length by default, so `[3]` is a single antenna, unless read as the count
+    let config = template("radar_high_framerate_config.json", AntennaList::Numbers);
+    assert_eq!((config.rx_antennas, config.tx_antennas), (1, 1));
+    let config = template("radar_high_framerate_config.json", AntennaList::Count);
+    assert_eq!((config.rx_antennas, config.tx_antennas), (3, 1));
+    assert_eq!(config.get_fifo_limit(), Config::high_framerate_preset().get_fifo_limit());
+    assert_eq!((config.tx_power_level, config.if_gain_db), (31, 60));
+    assert_eq!((config.lower_frequency_hz, config.upper_frequency_hz), (61_020_098_000, 61_479_902_000));
+    assert_eq!((config.num_chirps_per_frame, config.num_samples_per_chirp), (16, 128));
+    assert_eq!((config.chirp_repetition_time_s, config.frame_repetition_time_s), (7e-5, 5e-3));
+    assert_eq!(config.sample_rate_hz, 2_330_000);
+    assert_eq!(config.registers, [0; 38]);
+
+    for name in ["radar_low_framerate_config.json", "radar_low_framerate_single_antenna_config.json", "radar_test_config.json"] {
+        let config = template(name, AntennaList::Count);
+        assert!(config.num_chirps_per_frame > 0 && config.num_samples_per_chirp > 0, "{}", name);
+        assert!(config.lower_frequency_hz < config.upper_frequency_hz, "{}", name);
+    }
+}
+
+#[test]
+fn rejects_invalid_configs() {
+    let json = config_to_json(&Config::test_preset());
+    assert!(matches!(config_from_json(&json.replace("sample_rate_Hz", "rate")), Err(Error::InvalidParameter)));
+    let gain = format!("\"if_gain_dB\": {}", Config::test_preset().if_gain_db);
+    assert!(matches!(config_from_json(&json.replace(&gain, "\"if_gain_dB\": 300")), Err(Error::InvalidParameter)));
+    assert!(matches!(config_from_json(&json.replace("fmcw_single_shape", "shape")), Err(Error::InvalidParameter)));
+    assert!(matches!(config_from_json("not json"), Err(Error::InvalidParameter)));
+
+    // Swapped frequencies are read as they are, without a bandwidth
+    let mut document: Value = serde_json::from_str(&json).unwrap();
+    let shape = &mut document["device_config"]["fmcw_single_shape"];
+    let lower = shape["lower_frequency_Hz"].take();
+    shape["lower_frequency_Hz"] = shape["upper_frequency_Hz"].take();
+    shape["upper_frequency_Hz"] = lower;
+    let config = config_from_json(&document.to_string()).unwrap();
+    assert!(config.lower_frequency_hz > config.upper_frequency_hz);
+    assert_eq!(config.bandwidth_hz(), 0);
+    assert!(config.range_resolution_m().is_infinite());
+
+    // A single antenna number instead of a list
+    document["device_config"]["fmcw_single_shape"]["rx_antennas"] = Value::from(3);
+    assert_eq!(config_from_json(&document.to_string()).unwrap().rx_antennas, 1);
+    assert_eq!(config_from_json_with(&document.to_string(), AntennaList::Count).unwrap().rx_antennas, 3);
+
+    // Longer lists are read by their length either way, as written by `config_to_json()`
+    let json = config_to_json(&Config::high_framerate_preset());
+    assert_eq!(config_from_json_with(&json, AntennaList::Count).unwrap().rx_antennas, 3);
+}
+
+#[test]
+fn fusion_recording_round_trip() {
+    let directory = TempDir::new("fusion");
+    let config = Config::high_framerate_preset();
+    let frames = frames(&config, 3);
+
+    write_fusion_recording(&directory.0, &config, &frames).unwrap();
+    assert!(directory.0.join(FUSION_CONFIG_FILE).is_file());
+    let (read_config, read_frames) = read_fusion_recording(&directory.0, FloatScale::Normalized).unwrap();
+    assert_eq!(read_config, without_registers(&config));
+    assert_eq!(read_frames, frames);
+
+    // The samples are written as f32 normalized to the ADC full scale, as in the recordings of the Infineon tools
+    let data = fs::read(directory.0.join(FUSION_DATA_FILE)).unwrap();
+    let header_len = 10 + u16::from_le_bytes([data[8], data[9]]) as usize;
+    let header = String::from_utf8_lossy(&data[10..header_len]);
+    assert!(header.contains("'descr': '<f4'"), "{}", header);
+    assert!(header.contains("'shape': (3, 3, 16, 128)"), "{}", header);
+    let samples: Vec<f32> = data[header_len..]
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+        .collect();
+    let expected: Vec<f32> = frames.iter().flatten().map(|&sample| sample as f32 / ADC_FULL_SCALE).collect();
+    assert_eq!(samples, expected);
+
+    // Read as ADC counts, the same samples are all 0 or 1
+    let (_, read_frames) = read_fusion_recording(&directory.0, FloatScale::AdcCounts).unwrap();
+    assert!(read_frames.iter().flatten().all(|&sample| sample <= 1));
+
+    // Integer samples are read as ADC counts regardless of the scale
+    let file = File::create(directory.0.join(FUSION_DATA_FILE)).unwrap();
+    write_array(BufWriter::new(file), &[3, 3, 16, 128], frames.iter().flatten().copied()).unwrap();
+    let (_, read_frames) = read_fusion_recording(&directory.0, FloatScale::Normalized).unwrap();
+    assert_eq!(read_frames, frames);
+
+    // Frames that do not match the config are rejected before anything is written
+    let other = TempDir::new("fusion-mismatch");
+    let result = write_fusion_recording(&other.0, &Config::test_preset(), &frames);
+    assert!(matches!(result, Err(Error::ShapeMismatch)));
+    assert!(!other.0.join(FUSION_CONFIG_FILE).exists());
+
+    fs::remove_file(directory.0.join(FUSION_CONFIG_FILE)).unwrap();
+    assert!(read_fusion_recording(&directory.0, FloatScale::Normalized).is_err());
+}
+
+#[test]
+fn raw_round_trip() {
+    let directory = TempDir::new("raw");
+    let (data, sidecar) = (directory.0.join("radar.raw"), directory.0.join("radar.json"));
+    let config = Config::high_framerate_preset();
+    let frames = frames(&config, 2);
+
+    write_raw(&data, &sidecar, &config, &frames).unwrap();
+    assert_eq!(fs::metadata(&data).unwrap().len(), 2 * 2 * config.get_fifo_limit() as u64);
+    let (read_config, read_frames) = read_raw(&data, &sidecar).unwrap();
+    assert_eq!(read_config, without_registers(&config));
+    assert_eq!(read_frames, frames);
+
+    // A truncated file does not hold a whole number of frames
+    let bytes = fs::read(&data).unwrap();
+    fs::write(&data, &bytes[..bytes.len() - 2]).unwrap();
+    let frame_bytes = 2 * config.get_fifo_limit();
+    assert!(matches!(read_raw(&data, &sidecar), Err(Error::BufferWrongSize(_, size)) if size == frame_bytes));
+
+    // All frames must match the config
+    let result = write_raw(&data, &sidecar, &Config::test_preset(), &frames);
+    assert!(matches!(result, Err(Error::ShapeMismatch)));
+}
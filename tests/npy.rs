@@ -0,0 +1,219 @@
+//! Round trips of the `.npy` and `.npz` export through the pure-Rust reader of `npyz`, and through `read_frames()`.
+
+use std::io::Cursor;
+
+use bgt60trxx::config::Config;
+use bgt60trxx::npy::{FloatScale, read_frames, write_array, write_frame, write_frames, write_npz, write_recording_npz};
+use bgt60trxx::recording::{Header, Recorder, RecordingReader};
+use bgt60trxx::register::CHIP_ID;
+use bgt60trxx::Variant;
+use ndarray::Array3;
+use npyz::NpyFile;
+use npyz::npz::NpzArchive;
+
+/// A frame in FIFO layout with a distinct value per sample, offset by the frame index.
+fn fifo_data(config: &Config, index: usize) -> Vec<u16> {
+    (0..config.get_fifo_limit()).map(|sample| ((sample * 7 + index * 13) % 4096) as u16).collect()
+}
+
+/// The same frame as returned by `Radar::get_frames()`.
+fn frame(config: &Config, index: usize) -> Array3<u16> {
+    let rx_antennas = config.rx_antennas as usize;
+    let num_chirps = config.num_chirps_per_frame as usize;
+    let num_samples = config.num_samples_per_chirp as usize;
+    let data = fifo_data(config, index);
+
+    Array3::from_shape_fn((rx_antennas, num_chirps, num_samples), |(rx, chirp, sample)| {
+        data[chirp * rx_antennas * num_samples + sample * rx_antennas + rx]
+    })
+}
+
+fn read_npz_scalar<T: npyz::Deserialize>(npz: &mut NpzArchive<Cursor<Vec<u8>>>, name: &str) -> T {
+    let array = npz.by_name(name).unwrap().unwrap();
+    assert!(array.shape().is_empty(), "{} is not a 0-d array", name);
+    array.into_vec::<T>().unwrap().remove(0)
+}
+
+fn assert_config(npz: &mut NpzArchive<Cursor<Vec<u8>>>, config: &Config) {
+    assert_eq!(read_npz_scalar::<u8>(npz, "rx_antennas"), config.rx_antennas);
+    assert_eq!(read_npz_scalar::<u8>(npz, "tx_antennas"), config.tx_antennas);
+    assert_eq!(read_npz_scalar::<u8>(npz, "tx_power_level"), config.tx_power_level);
+    assert_eq!(read_npz_scalar::<u8>(npz, "if_gain_db"), config.if_gain_db);
+    assert_eq!(read_npz_scalar::<u64>(npz, "lower_frequency_hz"), config.lower_frequency_hz);
+    assert_eq!(read_npz_scalar::<u64>(npz, "upper_frequency_hz"), config.upper_frequency_hz);
+    assert_eq!(read_npz_scalar::<u8>(npz, "num_chirps_per_frame"), config.num_chirps_per_frame);
+    assert_eq!(read_npz_scalar::<u16>(npz, "num_samples_per_chirp"), config.num_samples_per_chirp);
+    assert_eq!(read_npz_scalar::<f64>(npz, "chirp_repetition_time_s"), config.chirp_repetition_time_s);
+    assert_eq!(read_npz_scalar::<f64>(npz, "frame_repetition_time_s"), config.frame_repetition_time_s);
+    assert_eq!(read_npz_scalar::<u32>(npz, "sample_rate_hz"), config.sample_rate_hz);
+
+    let registers = npz.by_name("registers").unwrap().unwrap();
+    assert_eq!(registers.shape(), &[38]);
+    assert_eq!(registers.into_vec::<u32>().unwrap(), config.registers);
+}
+
+#[test]
+fn single_frame_round_trip() {
+    let config = Config::high_framerate_preset();
+    let frame = frame(&config, 0);
+
+    let mut bytes = Vec::new();
+    write_frame(&mut bytes, &frame).unwrap();
+
+    // The header must be padded to a multiple of 64 bytes for numpy
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+
+    let npy = NpyFile::new(&bytes[..]).unwrap();
+    assert_eq!(npy.header().dtype().descr(), "'<u2'");
+    assert_eq!(npy.shape(), &[3, 16, 128]);
+    assert_eq!(npy.order(), npyz::Order::C);
+    assert_eq!(npy.into_vec::<u16>().unwrap(), frame.iter().copied().collect::<Vec<_>>());
+}
+
+#[test]
+fn stacked_frames_round_trip() {
+    let config = Config::high_framerate_preset();
+    let frames: Vec<_> = (0..4).map(|index| frame(&config, index)).collect();
+
+    let mut bytes = Vec::new();
+    write_frames(&mut bytes, &config, &frames).unwrap();
+
+    let npy = NpyFile::new(&bytes[..]).unwrap();
+    assert_eq!(npy.shape(), &[4, 3, 16, 128]);
+    let values = npy.into_vec::<u16>().unwrap();
+    for (index, frame) in frames.iter().enumerate() {
+        // Index [frame, rx, chirp, sample] in C order
+        let (rx, chirp, sample) = (2, 5, 77);
+        assert_eq!(values[((index * 3 + rx) * 16 + chirp) * 128 + sample], frame[[rx, chirp, sample]]);
+    }
+
+    // Frames of another config are rejected
+    let other = Config::test_preset();
+    assert!(write_frames(&mut Vec::new(), &other, &frames).is_err());
+}
+
+#[test]
+fn npz_round_trip_with_config() {
+    let config = Config::high_framerate_preset();
+    let frames: Vec<_> = (0..3).map(|index| frame(&config, index)).collect();
+
+    let mut bytes = Vec::new();
+    write_npz(&mut bytes, &config, &frames).unwrap();
+
+    // The zip reader verifies the CRC of every entry when reading it to the end
+    let mut npz = NpzArchive::new(Cursor::new(bytes)).unwrap();
+    let frames_npy = npz.by_name("frames").unwrap().unwrap();
+    assert_eq!(frames_npy.shape(), &[3, 3, 16, 128]);
+    let values = frames_npy.into_vec::<u16>().unwrap();
+    let expected: Vec<u16> = frames.iter().flat_map(|frame| frame.iter().copied()).collect();
+    assert_eq!(values, expected);
+
+    assert_config(&mut npz, &config);
+}
+
+#[test]
+fn recording_npz_round_trip() {
+    let config = Config::test_preset();
+    let header = Header {
+        variant: Variant::BGT60UTR11AIP,
+        chip_id: CHIP_ID::from_bits(0x0807),
+        config: config.clone(),
+    };
+
+    let mut recorder = Recorder::new(Vec::new(), &header).unwrap();
+    for index in 0..5 {
+        recorder.write_frame(100 + index as u32, 1_000 * index as u64, &fifo_data(&config, index)).unwrap();
+    }
+    let recording = recorder.into_inner();
+
+    let mut bytes = Vec::new();
+    write_recording_npz(&mut bytes, RecordingReader::new(&recording[..]).unwrap()).unwrap();
+
+    let mut npz = NpzArchive::new(Cursor::new(bytes)).unwrap();
+    let frames_npy = npz.by_name("frames").unwrap().unwrap();
+    assert_eq!(frames_npy.shape(), &[5, 1, 1, 128]);
+    let values = frames_npy.into_vec::<u16>().unwrap();
+    let expected: Vec<u16> = (0..5).flat_map(|index| frame(&config, index).into_iter()).collect();
+    assert_eq!(values, expected);
+
+    let counters = npz.by_name("frame_counter").unwrap().unwrap().into_vec::<u32>().unwrap();
+    assert_eq!(counters, [100, 101, 102, 103, 104]);
+    let timestamps = npz.by_name("timestamp_us").unwrap().unwrap().into_vec::<u64>().unwrap();
+    assert_eq!(timestamps, [0, 1_000, 2_000, 3_000, 4_000]);
+    assert_eq!(read_npz_scalar::<u8>(&mut npz, "variant"), 1);
+    assert_eq!(read_npz_scalar::<u32>(&mut npz, "chip_id"), 0x0807);
+
+    assert_config(&mut npz, &config);
+}
+
+#[test]
+fn read_frames_round_trip() {
+    let config = Config::high_framerate_preset();
+    let frames: Vec<_> = (0..3).map(|index| frame(&config, index)).collect();
+
+    // The scale is ignored for u16 samples
+    let mut bytes = Vec::new();
+    write_frames(&mut bytes, &config, &frames).unwrap();
+    assert_eq!(read_frames(&bytes[..], &config, FloatScale::Normalized).unwrap(), frames);
+    let mut bytes = Vec::new();
+    write_frame(&mut bytes, &frames[0]).unwrap();
+    assert_eq!(read_frames(&bytes[..], &config, FloatScale::AdcCounts).unwrap(), &frames[..1]);
+
+    // Floating-point samples are converted with the given scale, also if all of them are within 1.0
+    let shape = [3, 3, 16, 128];
+    let mut normalized = Vec::new();
+    write_array(&mut normalized, &shape, frames.iter().flatten().map(|&sample| sample as f32 / 4095.0)).unwrap();
+    assert_eq!(read_frames(&normalized[..], &config, FloatScale::Normalized).unwrap(), frames);
+
+    let mut counts = Vec::new();
+    write_array(&mut counts, &shape, frames.iter().flatten().map(|&sample| sample as f64)).unwrap();
+    assert_eq!(read_frames(&counts[..], &config, FloatScale::AdcCounts).unwrap(), frames);
+
+    let small: Vec<_> = frames.iter().map(|frame| frame.mapv(|sample| sample % 2)).collect();
+    let mut bytes = Vec::new();
+    write_array(&mut bytes, &shape, small.iter().flatten().map(|&sample| sample as f32)).unwrap();
+    assert_eq!(read_frames(&bytes[..], &config, FloatScale::AdcCounts).unwrap(), small);
+
+    let mut bytes = Vec::new();
+    write_frames(&mut bytes, &config, &frames).unwrap();
+    let result = read_frames(&bytes[..], &Config::test_preset(), FloatScale::AdcCounts);
+    assert!(matches!(result, Err(bgt60trxx::error::Error::ShapeMismatch)));
+}
+
+/// A `.npy` file of version 1.0 with the given dictionary and data.
+fn npy_file(dict: &str, data: &[u8]) -> Vec<u8> {
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(dict.as_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+#[test]
+fn read_frames_rejects_corrupted_headers() {
+    let config = Config::high_framerate_preset();
+    let read = |bytes: &[u8]| read_frames(bytes, &config, FloatScale::AdcCounts);
+    let dict = |shape: &str| format!("{{'descr': '<u2', 'fortran_order': False, 'shape': ({}), }}\n", shape);
+
+    let data = vec![0u8; 2 * config.get_fifo_limit()];
+    assert_eq!(read(&npy_file(&dict("1, 3, 16, 128"), &data)).unwrap().len(), 1);
+
+    // A number of frames that overflows the number of values, or exceeds the data of the file
+    let result = read(&npy_file(&dict(&format!("{}, 3, 16, 128", usize::MAX / 1000)), &data));
+    assert!(matches!(result, Err(bgt60trxx::error::Error::ShapeMismatch)));
+    let result = read(&npy_file(&dict(&format!("{}, 3, 16, 128", u32::MAX)), &data));
+    assert!(matches!(result, Err(bgt60trxx::error::Error::Io(_))));
+    let result = read(&npy_file(&dict("2, 3, 16, 128"), &data));
+    assert!(matches!(result, Err(bgt60trxx::error::Error::Io(_))));
+
+    // A header length beyond any real header
+    let mut bytes = b"\x93NUMPY\x02\x00".to_vec();
+    bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(read(&bytes), Err(bgt60trxx::error::Error::InvalidParameter)));
+
+    // A config without samples
+    let empty = Config { num_samples_per_chirp: 0, ..config.clone() };
+    let result = read_frames(&npy_file(&dict("1, 3, 16, 0"), &[])[..], &empty, FloatScale::AdcCounts);
+    assert!(matches!(result, Err(bgt60trxx::error::Error::ShapeMismatch)));
+}
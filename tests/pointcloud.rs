@@ -0,0 +1,148 @@
+//! Conversion of detections into points, mounting transforms, and the round trip of the serialized point cloud.
+
+use core::f32::consts::{FRAC_PI_2, PI};
+
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::angle::AngleOfArrival;
+use bgt60trxx::dsp::pointcloud::{HEADER_SIZE, POINT_SIZE, decode, decode_header, detections_to_points, encode, encoded_size};
+use bgt60trxx::dsp::{AntennaGeometry, Complex32, Detection, DopplerFft, MountingTransform, Point, RangeFft, Window};
+use bgt60trxx::error::Error;
+
+fn assert_position(point: &Point, expected: [f32; 3]) {
+    let actual = [point.x_m, point.y_m, point.z_m];
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+    }
+}
+
+fn detection(range_m: f32) -> Detection {
+    Detection {
+        range_bin: 10,
+        doppler_bin: 5,
+        range_m,
+        velocity_mps: -0.8,
+        snr_db: 14.0,
+    }
+}
+
+#[test]
+fn converts_detections_into_points() {
+    // At boresight, the point lies on the y axis
+    let boresight = AngleOfArrival { azimuth_rad: Some(0.0), elevation_rad: Some(0.0) };
+    let point = Point::from_detection(&detection(2.0), &boresight, 7);
+    assert_position(&point, [0.0, 2.0, 0.0]);
+    assert_eq!((point.velocity_mps, point.snr_db, point.frame), (-0.8, 14.0, 7));
+
+    // Positive azimuth is towards x, positive elevation towards z
+    let angle = AngleOfArrival { azimuth_rad: Some(PI / 6.0), elevation_rad: Some(PI / 6.0) };
+    let point = Point::from_detection(&detection(2.0), &angle, 7);
+    let horizontal = 2.0 * (PI / 6.0).cos();
+    assert_position(&point, [horizontal * 0.5, horizontal * (PI / 6.0).cos(), 1.0]);
+    let range = (point.x_m * point.x_m + point.y_m * point.y_m + point.z_m * point.z_m).sqrt();
+    assert!((range - 2.0).abs() < 1e-5);
+
+    // A missing elevation puts the point into the horizontal plane
+    let azimuth_only = AngleOfArrival { elevation_rad: None, ..angle };
+    assert_eq!(Point::from_detection(&detection(2.0), &azimuth_only, 7).z_m, 0.0);
+}
+
+#[test]
+fn applies_mounting_transform() {
+    let point = Point { x_m: 0.0, y_m: 2.0, z_m: 0.0, ..Default::default() };
+    assert_eq!(MountingTransform::default().apply(&point), point);
+
+    // A sensor turned by 90° towards negative x, mounted 1.5 m above the floor
+    let transform = MountingTransform::from_euler(0.0, 0.0, FRAC_PI_2, [0.0, 0.0, 1.5]);
+    assert_position(&transform.apply(&point), [-2.0, 0.0, 1.5]);
+
+    // Tilted upwards, the boresight points up
+    let transform = MountingTransform::from_euler(0.0, FRAC_PI_2, 0.0, [0.0; 3]);
+    assert_position(&transform.apply(&point), [0.0, 0.0, 2.0]);
+
+    // Rolled by 90°, positive azimuth becomes negative elevation
+    let transform = MountingTransform::from_euler(FRAC_PI_2, 0.0, 0.0, [0.0; 3]);
+    let side = Point { x_m: 1.0, ..point };
+    assert_position(&transform.apply(&side), [0.0, 2.0, -1.0]);
+
+    // The rotation is orthonormal for any combination of angles
+    let transform = MountingTransform::from_euler(0.3, -0.7, 1.9, [0.0; 3]);
+    for a in 0..3 {
+        for b in 0..3 {
+            let dot: f32 = (0..3).map(|k| transform.rotation[a][k] * transform.rotation[b][k]).sum();
+            assert!((dot - if a == b { 1.0 } else { 0.0 }).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn converts_detections_from_range_doppler_maps() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let doppler_fft = DopplerFft::new(&config, &range_fft, Window::Hann);
+    let map_size = doppler_fft.map_size();
+
+    // A target at boresight is in phase on all antennas
+    let detection = detection(1.5);
+    let cell = detection.doppler_bin * doppler_fft.num_range_bins() + detection.range_bin;
+    let mut maps = vec![Complex32::new(0.0, 0.0); 3 * map_size];
+    for rx in 0..3 {
+        maps[rx * map_size + cell] = Complex32::new(1.0, 1.0);
+    }
+
+    let transform = MountingTransform::from_euler(0.0, 0.0, 0.0, [1.0, 0.0, 0.0]);
+    let mut points = [Point::default(); 1];
+    let geometry = AntennaGeometry::bgt60tr13c();
+    let count = detections_to_points(&maps, &doppler_fft, &geometry, &[detection, detection], 3, &transform, &mut points).unwrap();
+    assert_eq!(count, 1);
+    assert_position(&points[0], [1.0, 1.5, 0.0]);
+    assert_eq!(points[0].frame, 3);
+}
+
+#[test]
+fn encode_decode_round_trip() {
+    let points: Vec<_> = (0..5)
+        .map(|index| Point {
+            x_m: index as f32 * 0.5 - 1.0,
+            y_m: 2.0 + index as f32,
+            z_m: -0.25 * index as f32,
+            velocity_mps: 0.1 * index as f32,
+            snr_db: 10.0 + index as f32,
+            frame: 42,
+        })
+        .collect();
+
+    let mut bytes = vec![0u8; encoded_size(points.len())];
+    assert_eq!(bytes.len(), HEADER_SIZE + 5 * POINT_SIZE);
+    assert_eq!(encode(42, &points, &mut bytes).unwrap(), bytes.len());
+    assert_eq!(&bytes[..4], b"BGTP");
+    assert_eq!(decode_header(&bytes).unwrap(), (42, 5));
+
+    let mut decoded = [Point::default(); 8];
+    assert_eq!(decode(&bytes, &mut decoded).unwrap(), (42, 5));
+    assert_eq!(&decoded[..5], &points[..]);
+
+    // An empty frame is only a header
+    let mut empty = [0u8; HEADER_SIZE];
+    assert_eq!(encode(43, &[], &mut empty).unwrap(), HEADER_SIZE);
+    assert_eq!(decode(&empty, &mut []).unwrap(), (43, 0));
+}
+
+#[test]
+fn rejects_invalid_point_clouds() {
+    let points = [Point { frame: 1, ..Default::default() }; 2];
+    let mut bytes = vec![0u8; encoded_size(2)];
+
+    // All points must belong to the frame, and fit into the output
+    assert!(matches!(encode(2, &points, &mut bytes), Err(Error::InvalidParameter)));
+    assert!(matches!(encode(1, &points, &mut bytes[..20]), Err(Error::OutputWrongSize(20, _))));
+    encode(1, &points, &mut bytes).unwrap();
+
+    let mut output = [Point::default(); 2];
+    assert!(matches!(decode(&bytes[..HEADER_SIZE - 1], &mut output), Err(Error::BufferWrongSize(_, HEADER_SIZE))));
+    assert!(matches!(decode(&bytes[..bytes.len() - 1], &mut output), Err(Error::BufferWrongSize(_, _))));
+    assert!(matches!(decode(&bytes, &mut output[..1]), Err(Error::OutputWrongSize(1, 2))));
+
+    let mut corrupted = bytes.clone();
+    corrupted[4] = 2;
+    assert!(matches!(decode(&corrupted, &mut output), Err(Error::InvalidParameter)));
+}
@@ -0,0 +1,169 @@
+//! State transitions of the presence detector, with thresholds, hysteresis and hold times.
+
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::{Complex32, Frame, PresenceDetector, PresenceEvent, PresenceSettings, PresenceState, RangeFft, Window};
+
+mod common;
+
+use common::{beat_phase, synthetic_frame};
+
+const STATIC_BIN: usize = 8;
+const TARGET_BIN: usize = 20;
+const GATES: usize = 40;
+
+/// A frame with a static reflector, and a target with the given phase.
+fn frame_data(config: &Config, target_phase: f32) -> Vec<u16> {
+    synthetic_frame(config, |_, _, sample| {
+        600.0 * beat_phase(config, STATIC_BIN as f32, sample).cos()
+            + 400.0 * (beat_phase(config, TARGET_BIN as f32, sample) + target_phase).cos()
+    })
+}
+
+/// The magnitude of the target in the range profile of the detector, normalized to the number of samples.
+fn target_magnitude(config: &Config, range_fft: &RangeFft) -> f32 {
+    let data = frame_data(config, 0.0);
+    let mut scratch = vec![Complex32::new(0.0, 0.0); range_fft.fft_len()];
+    range_fft.process_chirp(&Frame::new(&data, config).unwrap(), 0, 0, &mut scratch).unwrap();
+    scratch[TARGET_BIN].norm() / config.num_samples_per_chirp as f32
+}
+
+struct Scene {
+    config: Config,
+    detector: PresenceDetector<GATES>,
+    scratch: Vec<Complex32>,
+    phase: f32,
+}
+
+impl Scene {
+    fn new(settings: PresenceSettings) -> Self {
+        let config = Config::test_preset();
+        let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+        Scene {
+            detector: PresenceDetector::new(&config, &range_fft, settings).unwrap(),
+            scratch: vec![Complex32::new(0.0, 0.0); range_fft.fft_len()],
+            config,
+            phase: 0.0,
+        }
+    }
+
+    /// Processes a frame with the target moved by the given phase step, and returns the event.
+    fn step(&mut self, phase_step: f32) -> Option<PresenceEvent> {
+        self.phase += phase_step;
+        let data = frame_data(&self.config, self.phase);
+        self.detector.process_frame(&Frame::new(&data, &self.config).unwrap(), &mut self.scratch).unwrap()
+    }
+}
+
+/// Settings for macro motion only, with a background that follows the scene within a single frame,
+/// so that the macro motion is the change of the range profile from the previous frame: `2 * magnitude * sin(phase_step / 2)`.
+fn macro_settings(config: &Config, hold_frames: f32) -> PresenceSettings {
+    let range_fft = RangeFft::new(config, Window::Hann).unwrap();
+    let magnitude = target_magnitude(config, &range_fft);
+    PresenceSettings {
+        // A phase step of 0.5 rad exceeds the threshold, a step of 0.4 rad only the threshold with hysteresis
+        macro_threshold: 2.0 * magnitude * (0.45f32 / 2.0).sin(),
+        micro_threshold: f32::INFINITY,
+        hysteresis: 0.7,
+        macro_hold_s: (hold_frames - 0.5) * config.frame_repetition_time_s as f32,
+        macro_time_constant_s: 0.0,
+        ..PresenceSettings::default()
+    }
+}
+
+#[test]
+fn macro_motion_with_hysteresis_and_hold_time() {
+    let config = Config::test_preset();
+    let mut scene = Scene::new(macro_settings(&config, 4.0));
+
+    // A static scene, and motion below the threshold, are absent
+    for _ in 0..5 {
+        assert_eq!(scene.step(0.0), None);
+    }
+    for _ in 0..5 {
+        assert_eq!(scene.step(0.4), None);
+    }
+    assert_eq!(scene.detector.state(), PresenceState::Absent);
+
+    // Motion above the threshold is reported with the distance of the target
+    let event = scene.step(0.5).unwrap();
+    assert_eq!(event.state, PresenceState::MacroMotion);
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    assert_eq!(event.distance_m, Some(range_fft.bin_to_range_m(TARGET_BIN)));
+
+    // Motion that drops below the threshold, but not below the hysteresis, keeps the gate active beyond the hold time
+    for _ in 0..10 {
+        assert_eq!(scene.step(0.4), None);
+        assert_eq!(scene.detector.distance_m(), event.distance_m);
+    }
+
+    // Once the motion stops, the state is held for 4 frames
+    for _ in 0..3 {
+        assert_eq!(scene.step(0.0), None);
+        assert_eq!(scene.detector.distance_m(), None);
+    }
+    let event = scene.step(0.0).unwrap();
+    assert_eq!(event, PresenceEvent { state: PresenceState::Absent, distance_m: None });
+}
+
+#[test]
+fn micro_motion_and_reset() {
+    let config = Config::test_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let magnitude = target_magnitude(&config, &range_fft);
+    let frame_time_s = config.frame_repetition_time_s as f32;
+    let mut scene = Scene::new(PresenceSettings {
+        macro_threshold: f32::INFINITY,
+        micro_threshold: 0.05 * magnitude,
+        micro_hold_s: 9.5 * frame_time_s,
+        micro_time_constant_s: 20.0 * frame_time_s,
+        micro_smoothing_s: frame_time_s,
+        ..PresenceSettings::default()
+    });
+
+    for _ in 0..5 {
+        assert_eq!(scene.step(0.0), None);
+    }
+
+    // A small oscillation, like breathing, deviates from the slow background
+    let mut events = Vec::new();
+    for frame in 0..30 {
+        let step = 0.1 * if (frame / 5) % 2 == 0 { 1.0 } else { -1.0 };
+        events.extend(scene.step(step).map(|event| (frame, event)));
+    }
+    assert_eq!(events.len(), 1, "{:?}", events);
+    assert_eq!(events[0].1.state, PresenceState::MicroMotion);
+    // The window spreads the target over the neighbouring bins, which also see the oscillation
+    let distance_m = events[0].1.distance_m.unwrap();
+    assert!((distance_m - range_fft.bin_to_range_m(TARGET_BIN)).abs() <= 1.01 * range_fft.range_resolution_m());
+
+    // A reset relearns the background, so the scene is absent right away
+    scene.detector.reset();
+    assert_eq!(scene.detector.state(), PresenceState::Absent);
+    for _ in 0..5 {
+        assert_eq!(scene.step(0.0), None);
+    }
+}
+
+#[test]
+fn settings_for_presets() {
+    // The defaults are chosen for 16 chirps of 128 samples at 10 Hz and more
+    assert_eq!(PresenceSettings::for_config(&Config::high_framerate_preset()), PresenceSettings::default());
+    let mut low_framerate = Config::high_framerate_preset();
+    low_framerate.frame_repetition_time_s = 100e-3;
+    assert_eq!(PresenceSettings::for_config(&low_framerate), PresenceSettings::default());
+
+    // A single chirp is noisier, so the thresholds are raised
+    let settings = PresenceSettings::for_config(&Config::test_preset());
+    let defaults = PresenceSettings::default();
+    assert!((settings.macro_threshold - 4.0 * defaults.macro_threshold).abs() < 1e-9);
+    assert!((settings.micro_threshold - 4.0 * defaults.micro_threshold).abs() < 1e-9);
+
+    // At 1 Hz, the backgrounds and filters span multiple frames
+    low_framerate.frame_repetition_time_s = 1.0;
+    let settings = PresenceSettings::for_config(&low_framerate);
+    assert_eq!(settings.macro_time_constant_s, 2.0);
+    assert_eq!(settings.micro_time_constant_s, 20.0);
+    assert_eq!(settings.micro_smoothing_s, 5.0);
+    assert_eq!(settings.macro_hold_s, 3.0);
+    assert_eq!(settings.micro_hold_s, defaults.micro_hold_s);
+}
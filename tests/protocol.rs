@@ -0,0 +1,255 @@
+//! Round trips of the streaming protocol, and resynchronisation of the decoder on corrupted byte streams.
+
+use bgt60trxx::Variant;
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::Detection;
+use bgt60trxx::error::Error;
+use bgt60trxx::protocol::{Decoder, Encoder, Message, Packet, Status, StatusCode};
+use bgt60trxx::recording::Header;
+use bgt60trxx::register::CHIP_ID;
+
+fn header(config: &Config) -> Header {
+    Header {
+        variant: Variant::BGT60TR13C,
+        chip_id: CHIP_ID::from_bits(0x0303),
+        config: config.clone(),
+    }
+}
+
+/// A frame in FIFO layout with a distinct value per sample, offset by the frame index, including zeros.
+fn fifo_data(config: &Config, index: usize) -> Vec<u16> {
+    (0..config.get_fifo_limit()).map(|sample| ((sample * 7 + index * 13) % 4096) as u16).collect()
+}
+
+fn detections() -> Vec<Detection> {
+    vec![
+        Detection { range_bin: 12, doppler_bin: 3, range_m: 0.47, velocity_mps: -0.8, snr_db: 21.5 },
+        Detection { range_bin: 40, doppler_bin: 0, range_m: 1.56, velocity_mps: 0.0, snr_db: 13.25 },
+    ]
+}
+
+/// Encodes a config, two frames, detections and a status, and returns the encoded packets including their delimiters.
+fn packets(config: &Config) -> Vec<Vec<u8>> {
+    let mut encoder = Encoder::new(Vec::new()).unwrap();
+    encoder.send_config(&header(config)).unwrap();
+    encoder.send_frame(100, 5_000, &fifo_data(config, 0)).unwrap();
+    encoder.send_detections(100, &detections()).unwrap();
+    encoder.send_frame(101, 10_000, &fifo_data(config, 1)).unwrap();
+    encoder.send_status(Status::from(&Error::RegisterMismatch(0x2C, 0x1234, 0x4321))).unwrap();
+    assert_eq!(encoder.sequence(), 5);
+    let bytes = encoder.into_inner();
+
+    // The stream starts with a delimiter, and each packet ends with one
+    assert_eq!(bytes[0], 0);
+    bytes[1..].split_inclusive(|&byte| byte == 0).map(<[u8]>::to_vec).collect()
+}
+
+fn decode(decoder: &mut Decoder, bytes: &[u8]) -> Vec<Result<Packet, Error>> {
+    decoder.feed(bytes).collect()
+}
+
+fn assert_frame(packet: &Packet, sequence: u16, counter: u32, samples: &[u16]) {
+    assert_eq!(packet.sequence, sequence);
+    match &packet.message {
+        Message::Frame { frame_counter, samples: decoded, .. } => {
+            assert_eq!(*frame_counter, counter);
+            assert_eq!(decoded, samples);
+        }
+        message => panic!("expected a frame, got {:?}", message),
+    }
+}
+
+#[test]
+fn round_trip() {
+    let config = Config::high_framerate_preset();
+    let packets = packets(&config);
+    assert_eq!(packets.len(), 5);
+
+    let mut decoder = Decoder::new();
+    let decoded: Vec<Packet> = decode(&mut decoder, &packets.concat()).into_iter().map(Result::unwrap).collect();
+    assert_eq!(decoded.len(), 5);
+
+    match &decoded[0].message {
+        Message::Config(decoded) => assert_eq!(decoded.to_bytes(), header(&config).to_bytes()),
+        message => panic!("expected a config, got {:?}", message),
+    }
+    assert_eq!(decoder.header().unwrap().config, config);
+
+    match &decoded[1].message {
+        Message::Frame { frame_counter, timestamp_us, samples } => {
+            assert_eq!((*frame_counter, *timestamp_us), (100, 5_000));
+            assert_eq!(samples, &fifo_data(&config, 0));
+
+            // Shaped like Radar::get_frames(), the FIFO interleaves the antennas per sample
+            let frame = decoder.frame_to_array(samples.clone()).unwrap();
+            assert_eq!(frame.dim(), (3, 16, 128));
+            assert_eq!(frame[[2, 5, 77]], samples[5 * 3 * 128 + 77 * 3 + 2]);
+        }
+        message => panic!("expected a frame, got {:?}", message),
+    }
+
+    match &decoded[2].message {
+        Message::Detections { frame_counter, detections: decoded } => {
+            assert_eq!(*frame_counter, 100);
+            assert_eq!(decoded, &detections());
+        }
+        message => panic!("expected detections, got {:?}", message),
+    }
+
+    assert_frame(&decoded[3], 3, 101, &fifo_data(&config, 1));
+
+    match &decoded[4].message {
+        Message::Status(status) => {
+            assert_eq!(status.code, StatusCode::RegisterMismatch);
+            assert_eq!(status.details, [0x2C, 0x1234, 0x4321]);
+        }
+        message => panic!("expected a status, got {:?}", message),
+    }
+
+    let statistics = decoder.statistics();
+    assert_eq!((statistics.packets, statistics.corrupted, statistics.lost), (5, 0, 0));
+}
+
+#[test]
+fn frames_need_a_config() {
+    let config = Config::test_preset();
+    let decoder = Decoder::new();
+    assert!(matches!(decoder.frame_to_array(fifo_data(&config, 0)), Err(Error::NoConfigSet)));
+}
+
+#[test]
+fn cobs_group_boundaries() {
+    // Frames without zeros, so that the packets end just before, at and after full COBS groups of 254 bytes
+    let mut encoder = Encoder::new(Vec::new()).unwrap();
+    let lengths = [115, 116, 117, 121, 122, 123, 244, 245, 246, 500];
+    for (index, &len) in lengths.iter().enumerate() {
+        encoder.send_frame(0x0101_0101, 0x0101_0101_0101_0101, &vec![0x0101 + index as u16; len]).unwrap();
+    }
+    // And a payload of zeros only
+    encoder.send_frame(0, 0, &[0; 300]).unwrap();
+
+    let mut decoder = Decoder::new();
+    let decoded = decode(&mut decoder, &encoder.into_inner());
+    assert_eq!(decoded.len(), lengths.len() + 1);
+    for (index, &len) in lengths.iter().enumerate() {
+        assert_frame(decoded[index].as_ref().unwrap(), index as u16, 0x0101_0101, &vec![0x0101 + index as u16; len]);
+    }
+    assert_frame(decoded[lengths.len()].as_ref().unwrap(), lengths.len() as u16, 0, &[0; 300]);
+}
+
+#[test]
+fn flipped_byte_is_detected() {
+    let config = Config::test_preset();
+    let mut packets = packets(&config);
+    // Flip a bit in the middle of the first frame, without creating a delimiter
+    let middle = packets[1].len() / 2;
+    packets[1][middle] ^= 0x10;
+    if packets[1][middle] == 0 {
+        packets[1][middle] = 0xEF;
+    }
+
+    let mut decoder = Decoder::new();
+    let decoded = decode(&mut decoder, &packets.concat());
+    assert_eq!(decoded.len(), 5);
+    assert!(decoded[0].is_ok());
+    assert!(matches!(decoded[1], Err(Error::Io(_))));
+    assert!(decoded[2].is_ok());
+    assert_frame(decoded[3].as_ref().unwrap(), 3, 101, &fifo_data(&config, 1));
+    assert!(decoded[4].is_ok());
+
+    // The corrupted packet is counted, and missing from the sequence numbers
+    let statistics = decoder.statistics();
+    assert_eq!((statistics.packets, statistics.corrupted, statistics.lost), (4, 1, 1));
+}
+
+#[test]
+fn lost_bytes_resynchronise_on_next_delimiter() {
+    let config = Config::test_preset();
+    let packets = packets(&config);
+
+    // Cut the second half of the detections including their delimiter, so that they merge with the next frame
+    let mut stream = Vec::new();
+    stream.extend(packets[..2].concat());
+    stream.extend(&packets[2][..packets[2].len() / 2]);
+    stream.extend(packets[3..].concat());
+
+    let mut decoder = Decoder::new();
+    let decoded = decode(&mut decoder, &stream);
+    assert_eq!(decoded.len(), 4);
+    assert!(decoded[0].is_ok() && decoded[1].is_ok());
+    assert!(decoded[2].is_err());
+    assert_eq!(decoded[3].as_ref().unwrap().sequence, 4);
+
+    let statistics = decoder.statistics();
+    assert_eq!((statistics.packets, statistics.corrupted, statistics.lost), (3, 1, 2));
+}
+
+#[test]
+fn connecting_mid_stream() {
+    let config = Config::test_preset();
+    let packets = packets(&config);
+
+    // The host starts receiving in the middle of the first frame
+    let stream = [&packets[1][packets[1].len() / 3..], &packets[2..].concat()].concat();
+
+    let mut decoder = Decoder::new();
+    let decoded = decode(&mut decoder, &stream);
+    assert_eq!(decoded.len(), 4);
+    assert!(decoded[0].is_err());
+    assert!(decoded[1..].iter().all(Result::is_ok));
+    assert!(decoder.header().is_none());
+}
+
+#[test]
+fn oversized_packets_are_discarded() {
+    let config = Config::high_framerate_preset();
+    let packets = packets(&config);
+
+    // Only the frames exceed the limit
+    let mut decoder = Decoder::with_max_packet_size(1024);
+    let decoded = decode(&mut decoder, &packets.concat());
+    assert_eq!(decoded.len(), 5);
+    assert!(decoded[0].is_ok() && decoded[2].is_ok() && decoded[4].is_ok());
+    assert!(decoded[1].is_err() && decoded[3].is_err());
+    assert_eq!(decoder.statistics().corrupted, 2);
+}
+
+#[test]
+fn random_corruption() {
+    let config = Config::test_preset();
+    let reference = packets(&config);
+    let stream = reference.concat();
+
+    // A deterministic linear congruential generator
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let mut random = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) as usize
+    };
+
+    for _ in 0..200 {
+        let mut corrupted = stream.clone();
+        for _ in 0..1 + random() % 4 {
+            let position = random() % corrupted.len();
+            match random() % 3 {
+                0 => corrupted[position] = random() as u8,
+                1 => {
+                    corrupted.remove(position);
+                }
+                _ => corrupted.insert(position, random() as u8),
+            }
+        }
+
+        // Whatever the corruption, the decoder must not panic, and every packet it accepts must be one that was sent
+        let mut decoder = Decoder::new();
+        for packet in decoder.feed(&corrupted).filter_map(Result::ok).collect::<Vec<_>>() {
+            let expected = &reference[packet.sequence as usize];
+            let mut encoded = Decoder::new();
+            let original = decode(&mut encoded, expected).pop().unwrap().unwrap();
+            assert_eq!(format!("{:?}", packet), format!("{:?}", original));
+        }
+
+        let statistics = decoder.statistics();
+        assert!(statistics.packets + statistics.corrupted >= 1);
+    }
+}
@@ -0,0 +1,66 @@
+//! Range bin labels of the range FFT against synthetic beat tones of targets at known distances.
+
+use bgt60trxx::config::{Config, SPEED_OF_LIGHT};
+use bgt60trxx::dsp::{Complex32, Frame, RangeFft, Window};
+
+mod common;
+
+use common::{beat_phase, synthetic_frame};
+
+/// A frame with the beat tone of a target at the given distance, which has `2 * d * B / c` cycles per chirp.
+fn target_frame(config: &Config, distance_m: f64) -> Vec<u16> {
+    let cycles = 2.0 * distance_m * config.bandwidth_hz() as f64 / SPEED_OF_LIGHT;
+    synthetic_frame(config, |_, _, sample| 1000.0 * beat_phase(config, cycles as f32, sample).cos())
+}
+
+/// The distance of the strongest range bin of the first chirp, skipping the DC bin.
+fn peak_range_m(range_fft: &RangeFft, config: &Config, data: &[u16]) -> f32 {
+    let mut scratch = vec![Complex32::new(0.0, 0.0); range_fft.fft_len()];
+    range_fft.process_chirp(&Frame::new(data, config).unwrap(), 0, 0, &mut scratch).unwrap();
+    let peak = (1..range_fft.num_bins()).max_by(|&a, &b| scratch[a].norm().total_cmp(&scratch[b].norm())).unwrap();
+    range_fft.bin_to_range_m(peak)
+}
+
+#[test]
+fn range_resolution_and_max_range() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+
+    // c / (2 * B) with a bandwidth of 459.8 MHz
+    let expected = SPEED_OF_LIGHT / (2.0 * 459_804_000.0);
+    assert!((range_fft.range_resolution_m() as f64 - expected).abs() < 1e-6, "{}", range_fft.range_resolution_m());
+    assert!((config.range_resolution_m() - expected).abs() < 1e-9);
+
+    // Without zero-padding, the range bins cover the maximum range of the config
+    let labels: Vec<_> = range_fft.range_bins().collect();
+    assert_eq!(labels.len(), 64);
+    assert_eq!(labels[0], 0.0);
+    assert_eq!(labels[10], 10.0 * range_fft.range_resolution_m());
+    let covered = labels.len() as f64 * range_fft.range_resolution_m() as f64;
+    assert!((covered - config.max_range_m()).abs() < 1e-5, "{} {}", covered, config.max_range_m());
+
+    // Zero-padding refines the resolution, but not the maximum range
+    let padded = RangeFft::with_fft_len(&config, Window::Hann, 512).unwrap();
+    assert_eq!(padded.range_resolution_m(), range_fft.range_resolution_m() / 4.0);
+    assert_eq!(padded.num_bins(), 256);
+    let covered = padded.num_bins() as f64 * padded.range_resolution_m() as f64;
+    assert!((covered - config.max_range_m()).abs() < 1e-5);
+}
+
+#[test]
+fn targets_at_known_distances() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let padded = RangeFft::with_fft_len(&config, Window::Hann, 1024).unwrap();
+
+    for distance_m in [0.9, 2.0, 4.75, 12.3] {
+        let data = target_frame(&config, distance_m);
+
+        let range_m = peak_range_m(&range_fft, &config, &data);
+        assert!((range_m as f64 - distance_m).abs() <= 0.5 * range_fft.range_resolution_m() as f64, "{} {}", range_m, distance_m);
+
+        // Zero-padding interpolates the spectrum, so the peak is closer to the target
+        let range_m = peak_range_m(&padded, &config, &data);
+        assert!((range_m as f64 - distance_m).abs() <= 0.5 * padded.range_resolution_m() as f64, "{} {}", range_m, distance_m);
+    }
+}
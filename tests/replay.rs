@@ -0,0 +1,175 @@
+//! Replay of recordings through the acquisition API of the driver.
+
+use std::cell::Cell;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use bgt60trxx::config::Config;
+use bgt60trxx::error::Error;
+use bgt60trxx::recording::{Header, Recorder, RecordingReader};
+use bgt60trxx::register::CHIP_ID;
+use bgt60trxx::replay::{ReplayRadar, Timing};
+use bgt60trxx::{FrameSource, Variant, fifo_samples};
+use embedded_hal_async::delay::DelayNs;
+use ndarray::Array3;
+
+/// Polls a future to completion, the replay never waits for anything but the delay.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+/// A delay that only sums up the requested time, so the tests do not sleep.
+#[derive(Clone, Default)]
+struct SummingDelay {
+    total_ns: Rc<Cell<u64>>,
+}
+
+impl DelayNs for SummingDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.total_ns.set(self.total_ns.get() + ns as u64);
+    }
+}
+
+/// A frame in FIFO layout with a distinct value per sample, offset by the frame index.
+fn fifo_data(config: &Config, index: usize) -> Vec<u16> {
+    (0..config.get_fifo_limit()).map(|sample| ((sample * 7 + index * 13) % 4096) as u16).collect()
+}
+
+/// A recording of four frames, with a gap in the frame timing.
+fn recording(config: &Config) -> Vec<u8> {
+    let header = Header {
+        variant: Variant::BGT60TR13C,
+        chip_id: CHIP_ID::from_bits(0x0303),
+        config: config.clone(),
+    };
+    let mut recorder = Recorder::new(Vec::new(), &header).unwrap();
+    for (index, timestamp_us) in [1_000, 6_000, 16_000, 21_000].into_iter().enumerate() {
+        recorder.write_frame(40 + index as u32, timestamp_us, &fifo_data(config, index)).unwrap();
+    }
+    recorder.into_inner()
+}
+
+/// Application code that only depends on the acquisition API.
+async fn mean_of_frames(radar: &mut impl FrameSource, count: usize) -> Result<f64, Error> {
+    radar.start().await?;
+    let mut sum = 0.0;
+    for _ in 0..count {
+        let frame = radar.get_frames().await?;
+        sum += frame.iter().map(|&sample| sample as f64).sum::<f64>() / frame.len() as f64;
+    }
+    radar.stop().await?;
+    Ok(sum / count as f64)
+}
+
+#[test]
+fn replays_frames_in_order() {
+    let config = Config::high_framerate_preset();
+    let bytes = recording(&config);
+    let reader = RecordingReader::new(&bytes[..]).unwrap();
+    let mut radar = ReplayRadar::from_recording(reader, SummingDelay::default(), Timing::AsFastAsPossible);
+
+    block_on(async {
+        assert_eq!(radar.variant(), Variant::BGT60TR13C);
+        assert_eq!(radar.get_chip_id().await.unwrap().into_bits(), 0x0303);
+        radar.configure(config.clone()).await.unwrap();
+        radar.start().await.unwrap();
+
+        // The raw FIFO data matches the recording, as read by the driver
+        let mut buffer = vec![0u8; config.get_u8_buffer_size()];
+        let mut output = vec![0u16; config.get_fifo_limit()];
+        radar.get_fifo_data(&mut buffer, &mut output).await.unwrap();
+        assert_eq!(output, fifo_data(&config, 0));
+        assert_eq!(radar.frame_counter(), Some(40));
+
+        // And the frames have the same shape as the ones of the driver
+        let frame = radar.get_frames().await.unwrap();
+        let data = fifo_data(&config, 1);
+        let expected = Array3::from_shape_fn((3, 16, 128), |(rx, chirp, sample)| data[chirp * 3 * 128 + sample * 3 + rx]);
+        assert_eq!(frame, expected);
+        assert_eq!(radar.frame_counter(), Some(41));
+
+        // The samples of both in FIFO order are the raw data again
+        assert_eq!(fifo_samples(&frame).collect::<Vec<_>>(), data);
+        assert_eq!(fifo_samples(&expected).collect::<Vec<_>>(), data);
+
+        let mean = mean_of_frames(&mut radar, 2).await.unwrap();
+        assert!(mean > 0.0);
+        assert_eq!(radar.frames_read(), 4);
+
+        assert!(matches!(radar.get_frames().await, Err(Error::EndOfRecording)));
+    });
+}
+
+#[test]
+fn checks_like_the_driver() {
+    let config = Config::high_framerate_preset();
+    let bytes = recording(&config);
+    let reader = RecordingReader::new(&bytes[..]).unwrap();
+    let mut radar = ReplayRadar::from_recording(reader, SummingDelay::default(), Timing::AsFastAsPossible);
+
+    block_on(async {
+        assert!(matches!(radar.get_frames().await, Err(Error::NoConfigSet)));
+        assert!(matches!(radar.configure(Config::test_preset()).await, Err(Error::ShapeMismatch)));
+
+        radar.configure(config.clone()).await.unwrap();
+        let mut buffer = vec![0u8; config.get_u8_buffer_size() - 1];
+        let mut output = vec![0u16; config.get_fifo_limit()];
+        assert!(matches!(
+            radar.get_fifo_data(&mut buffer, &mut output).await,
+            Err(Error::BufferWrongSize(_, _))
+        ));
+
+        // No frame has been consumed by the failed reads
+        radar.get_frames().await.unwrap();
+        assert_eq!(radar.frame_counter(), Some(40));
+    });
+}
+
+#[test]
+fn recorded_timing() {
+    let config = Config::test_preset();
+    let bytes = recording(&config);
+
+    for (timing, expected_us) in [(Timing::Recorded, 20_000), (Timing::AsFastAsPossible, 0)] {
+        let delay = SummingDelay::default();
+        let reader = RecordingReader::new(&bytes[..]).unwrap();
+        let mut radar = ReplayRadar::from_recording(reader, delay.clone(), timing);
+
+        block_on(async {
+            radar.configure(config.clone()).await.unwrap();
+            radar.start().await.unwrap();
+            for _ in 0..4 {
+                radar.get_frames().await.unwrap();
+            }
+        });
+
+        // The first frame is returned immediately, the time spent between the reads is subtracted from the intervals
+        let total_us = delay.total_ns.get() / 1_000;
+        assert!(total_us <= expected_us, "{:?}: {} us", timing, total_us);
+        assert!(total_us + 5_000 >= expected_us, "{:?}: {} us", timing, total_us);
+    }
+}
+
+#[test]
+fn cut_off_recording() {
+    let config = Config::test_preset();
+    let mut bytes = recording(&config);
+    bytes.truncate(bytes.len() - 10);
+    let reader = RecordingReader::new(&bytes[..]).unwrap();
+    let mut radar = ReplayRadar::from_recording(reader, SummingDelay::default(), Timing::AsFastAsPossible);
+
+    block_on(async {
+        radar.configure(config.clone()).await.unwrap();
+        for _ in 0..3 {
+            radar.get_frames().await.unwrap();
+        }
+        assert!(matches!(radar.get_frames().await, Err(Error::Io(_))));
+    });
+}
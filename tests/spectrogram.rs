@@ -0,0 +1,156 @@
+//! Velocity spectra of a synthetic target, and the order of the columns in the ring buffer.
+
+use core::f32::consts::PI;
+
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::window::MAX_WINDOW_LEN;
+use bgt60trxx::dsp::{Complex32, Frame, RangeFft, SlowTime, Spectrogram, SpectrogramSettings, Window};
+use bgt60trxx::error::Error;
+
+mod common;
+
+use common::{beat_phase, synthetic_frame};
+
+const TARGET_BIN: usize = 5;
+
+/// A frame with a static reflector and a target in the same range bin,
+/// whose phase advances by the given number of velocity bins (of 16) from chirp to chirp.
+fn moving_frame(config: &Config, velocity_bins: usize) -> Vec<u16> {
+    synthetic_frame(config, |_, chirp, sample| {
+        let doppler_phase = 2.0 * PI * (velocity_bins * chirp) as f32 / 16.0;
+        let x = beat_phase(config, TARGET_BIN as f32, sample);
+        600.0 * x.cos() + 300.0 * (x + doppler_phase).cos()
+    })
+}
+
+/// The distance of the strongest velocity bin of a column from zero velocity, in bins.
+fn peak_offset(column: &[f32]) -> usize {
+    let peak = (0..column.len()).max_by(|&a, &b| column[a].total_cmp(&column[b])).unwrap();
+    peak.abs_diff(column.len() / 2)
+}
+
+fn settings() -> SpectrogramSettings {
+    SpectrogramSettings { decibel: false, ..Default::default() }
+}
+
+#[test]
+fn ring_buffer_wraps_in_time_order() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let mut samples = [Complex32::new(0.0, 0.0); 16];
+    let mut matrix = [0.0; 4 * 16];
+    let mut spectrogram = Spectrogram::new(&config, &range_fft, settings(), TARGET_BIN, &mut samples, &mut matrix).unwrap();
+    let mut workspace = vec![Complex32::new(0.0, 0.0); spectrogram.workspace_size()];
+    assert_eq!(spectrogram.capacity(), 4);
+
+    // With 16 chirps per frame, each frame fills the whole FFT, so each column holds the velocity of a single frame
+    for velocity_bins in 1..=7 {
+        let data = moving_frame(&config, velocity_bins);
+        let column = spectrogram.process_frame(&Frame::new(&data, &config).unwrap(), &mut workspace).unwrap();
+        assert_eq!(peak_offset(column.unwrap()), velocity_bins);
+        assert_eq!(spectrogram.num_columns(), velocity_bins.min(4));
+    }
+
+    // Only the last 4 frames are kept, oldest first
+    let offsets: Vec<_> = spectrogram.columns().map(peak_offset).collect();
+    assert_eq!(offsets, [4, 5, 6, 7]);
+    assert_eq!(peak_offset(spectrogram.column(0)), 4);
+
+    let mut output = [0.0; 4 * 16];
+    assert_eq!(spectrogram.copy_to(&mut output).unwrap(), 4);
+    let offsets: Vec<_> = output.chunks_exact(16).map(peak_offset).collect();
+    assert_eq!(offsets, [4, 5, 6, 7]);
+    assert!(matches!(spectrogram.copy_to(&mut output[..16]), Err(Error::OutputWrongSize(16, 64))));
+
+    #[cfg(feature = "alloc")]
+    {
+        let array = spectrogram.to_array();
+        assert_eq!(array.shape(), [4, 16]);
+        assert_eq!(array.as_slice().unwrap(), &output[..]);
+    }
+
+    spectrogram.reset();
+    assert_eq!(spectrogram.num_columns(), 0);
+}
+
+#[test]
+fn removes_static_reflections() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let data = moving_frame(&config, 3);
+    let frame = Frame::new(&data, &config).unwrap();
+
+    let powers = |remove_static: bool| {
+        let (mut samples, mut matrix) = ([Complex32::new(0.0, 0.0); 16], [0.0; 16]);
+        let settings = SpectrogramSettings { remove_static, ..settings() };
+        let mut spectrogram = Spectrogram::new(&config, &range_fft, settings, TARGET_BIN, &mut samples, &mut matrix).unwrap();
+        let mut workspace = vec![Complex32::new(0.0, 0.0); spectrogram.workspace_size()];
+        let column = spectrogram.process_frame(&frame, &mut workspace).unwrap().unwrap();
+        (column[8], column[8 + 3].max(column[8 - 3]))
+    };
+
+    // The static reflector is twice as strong as the target, unless it is removed
+    let (static_power, target_power) = powers(false);
+    assert!(static_power > 3.0 * target_power, "{} {}", static_power, target_power);
+    let (static_power, target_power) = powers(true);
+    assert!(static_power < 1e-3 * target_power, "{} {}", static_power, target_power);
+}
+
+#[test]
+fn frames_as_samples() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let settings = SpectrogramSettings { slow_time: SlowTime::Frames, fft_len: 4, ..settings() };
+    let (mut samples, mut matrix) = ([Complex32::new(0.0, 0.0); 4], [0.0; 8]);
+    let mut spectrogram = Spectrogram::new(&config, &range_fft, settings, TARGET_BIN, &mut samples, &mut matrix).unwrap();
+    let mut workspace = vec![Complex32::new(0.0, 0.0); spectrogram.workspace_size()];
+
+    // The velocity resolution is based on the frame time, and the first column needs 4 frames
+    let expected = config.wavelength_m() / (2.0 * 4.0 * config.frame_repetition_time_s);
+    assert!((spectrogram.velocity_resolution_mps() as f64 - expected).abs() < 1e-6 * expected);
+    let data = moving_frame(&config, 0);
+    for frame in 0..4 {
+        let column = spectrogram.process_frame(&Frame::new(&data, &config).unwrap(), &mut workspace).unwrap();
+        assert_eq!(column.is_some(), frame == 3);
+    }
+
+    // The gate follows a target, without losing the collected samples
+    spectrogram.set_range_m(range_fft.bin_to_range_m(7) + 0.4 * range_fft.range_resolution_m()).unwrap();
+    assert_eq!(spectrogram.range_bin(), 7);
+    assert!(matches!(spectrogram.set_range_bin(range_fft.num_bins()), Err(Error::InvalidParameter)));
+    assert!(spectrogram.process_frame(&Frame::new(&data, &config).unwrap(), &mut workspace).unwrap().is_some());
+}
+
+#[test]
+fn rejects_invalid_buffers() {
+    let config = Config::high_framerate_preset();
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let mut samples = [Complex32::new(0.0, 0.0); 16];
+
+    let odd_len = SpectrogramSettings { fft_len: 12, ..settings() };
+    assert!(matches!(
+        Spectrogram::new(&config, &range_fft, odd_len, TARGET_BIN, &mut samples, &mut [0.0; 48]),
+        Err(Error::NotAPowerOfTwo)
+    ));
+    assert!(matches!(
+        Spectrogram::new(&config, &range_fft, settings(), TARGET_BIN, &mut samples, &mut [0.0; 40]),
+        Err(Error::BufferWrongSize(40, 16))
+    ));
+    assert!(matches!(
+        Spectrogram::new(&config, &range_fft, settings(), TARGET_BIN, &mut samples[..8], &mut [0.0; 16]),
+        Err(Error::BufferWrongSize(8, 16))
+    ));
+    assert!(matches!(
+        Spectrogram::new(&config, &range_fft, settings(), range_fft.num_bins(), &mut samples, &mut [0.0; 16]),
+        Err(Error::InvalidParameter)
+    ));
+
+    // The window of the short-time FFT is precomputed, up to its maximum length
+    let too_long = SpectrogramSettings { fft_len: 2 * MAX_WINDOW_LEN, ..settings() };
+    let mut long_samples = vec![Complex32::new(0.0, 0.0); 2 * MAX_WINDOW_LEN];
+    let mut matrix = vec![0.0; 2 * MAX_WINDOW_LEN];
+    assert!(matches!(
+        Spectrogram::new(&config, &range_fft, too_long, TARGET_BIN, &mut long_samples, &mut matrix),
+        Err(Error::InvalidParameter)
+    ));
+}
@@ -0,0 +1,116 @@
+//! Clustering of measurements with DBSCAN, and confirmation and deletion of tracks.
+
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::tracking::{
+    ClusterSettings, Measurement, TrackState, Tracker, TrackerSettings, cluster_centroids, dbscan,
+};
+use bgt60trxx::error::Error;
+
+fn measurement(x_m: f32, y_m: f32, velocity_mps: f32, snr_db: f32) -> Measurement {
+    Measurement { x_m, y_m, velocity_mps, snr_db }
+}
+
+#[test]
+fn clusters_measurements() {
+    let points = [
+        // A person at (0, 2) m
+        measurement(0.0, 2.0, 0.5, 20.0),
+        measurement(0.2, 2.1, 0.5, 10.0),
+        // A chain of detections at (2, 3) m, which only touch their neighbors
+        measurement(2.0, 3.0, 0.0, 10.0),
+        measurement(2.3, 3.0, 0.0, 10.0),
+        measurement(2.6, 3.0, 0.0, 10.0),
+        // A single detection is noise
+        measurement(-2.0, 1.0, 0.0, 30.0),
+        // Detections at the same position as the person, but with a different velocity
+        measurement(0.1, 2.0, -3.0, 10.0),
+        measurement(0.1, 2.1, -3.0, 10.0),
+    ];
+    let settings = ClusterSettings::default();
+    let mut labels = [None; 8];
+
+    let num_clusters = dbscan(&points, &settings, &mut labels).unwrap();
+    assert_eq!(num_clusters, 3);
+    assert_eq!(labels, [Some(0), Some(0), Some(1), Some(1), Some(1), None, Some(2), Some(2)]);
+
+    // Without the velocity, the detections of both velocities are merged
+    let position_only = ClusterSettings { velocity_scale_m: 0.0, ..settings };
+    assert_eq!(dbscan(&points, &position_only, &mut labels).unwrap(), 2);
+    assert_eq!(labels, [Some(0), Some(0), Some(1), Some(1), Some(1), None, Some(0), Some(0)]);
+
+    // The centroids are weighted by the linear SNR, 10 dB stronger is 10 times the weight
+    let num_clusters = dbscan(&points, &settings, &mut labels).unwrap();
+    let mut centroids = [Measurement::default(); 2];
+    assert_eq!(cluster_centroids(&points, &labels, num_clusters, &mut centroids).unwrap(), 2);
+    assert!((centroids[0].x_m - 0.2 / 11.0).abs() < 1e-6, "{:?}", centroids[0]);
+    assert!((centroids[0].y_m - (20.0 + 2.1) / 11.0).abs() < 1e-6, "{:?}", centroids[0]);
+    assert_eq!(centroids[0].snr_db, 20.0);
+    assert!((centroids[1].x_m - 2.3).abs() < 1e-6 && centroids[1].y_m == 3.0, "{:?}", centroids[1]);
+
+    assert!(matches!(dbscan(&points, &settings, &mut labels[..2]), Err(Error::OutputWrongSize(2, 8))));
+}
+
+#[test]
+fn confirms_and_deletes_tracks() {
+    let config = Config::test_preset();
+    let frame_time_s = config.frame_repetition_time_s as f32;
+    let settings = TrackerSettings::default();
+    let mut tracker = Tracker::<4>::new(&config, settings);
+
+    // A target walking sideways at 1 m/s is confirmed after 3 frames
+    let position = |frame: usize| measurement(-1.0 + frame as f32 * frame_time_s, 2.0, 0.0, 20.0);
+    for frame in 0..2 {
+        tracker.update(&[position(frame)]);
+        assert_eq!(tracker.tracks().count(), 0);
+        assert_eq!(tracker.all_tracks().next().unwrap().state(), TrackState::Tentative);
+    }
+    tracker.update(&[position(2)]);
+    let id = tracker.tracks().next().unwrap().id();
+
+    // The Kalman filter converges to the velocity, while the ID stays the same
+    for frame in 3..30 {
+        tracker.update(&[position(frame)]);
+    }
+    let track = tracker.tracks().next().unwrap();
+    assert_eq!(track.id(), id);
+    assert_eq!(track.age(), 29);
+    assert!((track.vx_mps() - 1.0).abs() < 0.1 && track.vy_mps().abs() < 0.1, "{:?}", track);
+    assert!((track.x_m() - position(29).x_m).abs() < 0.05, "{:?}", track);
+
+    // A single detection far away starts a tentative track, which is deleted on its first miss
+    tracker.update(&[position(30), measurement(3.0, 5.0, 0.0, 10.0)]);
+    assert_eq!(tracker.all_tracks().count(), 2);
+    tracker.update(&[position(31)]);
+    assert_eq!(tracker.all_tracks().count(), 1);
+
+    // A confirmed track survives up to `max_misses` frames without a measurement
+    for misses in 1..=settings.max_misses {
+        tracker.update(&[]);
+        assert_eq!(tracker.tracks().next().unwrap().misses(), misses);
+    }
+    tracker.update(&[]);
+    assert_eq!(tracker.all_tracks().count(), 0);
+}
+
+#[test]
+fn associates_any_number_of_measurements() {
+    let config = Config::test_preset();
+    let settings = TrackerSettings { confirm_hits: 2, ..Default::default() };
+
+    // 100 targets on a grid, far enough apart to be associated only to their own track
+    let grid: Vec<_> = (0..100)
+        .map(|index| measurement(2.0 * (index % 10) as f32, 2.0 * (index / 10) as f32, 0.0, 10.0))
+        .collect();
+    let mut tracker = Tracker::<128>::new(&config, settings);
+    tracker.update(&grid);
+    assert_eq!(tracker.all_tracks().count(), 100);
+    tracker.update(&grid);
+    assert_eq!(tracker.tracks().count(), 100);
+
+    // New tracks are dropped once all slots are in use
+    let mut tracker = Tracker::<4>::new(&config, settings);
+    tracker.update(&grid);
+    assert_eq!(tracker.all_tracks().count(), 4);
+    tracker.update(&grid);
+    assert_eq!(tracker.tracks().count(), 4);
+}
@@ -0,0 +1,97 @@
+//! Breathing and heart rate of a synthetic chest motion, and the frame rates that support them.
+
+use core::f32::consts::PI;
+
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::vitals::{MAX_BREATHING_FRAME_TIME_S, MAX_HEART_FRAME_TIME_S};
+use bgt60trxx::dsp::{Complex32, Frame, RangeFft, VitalSigns, VitalSignsSettings, Window};
+use bgt60trxx::error::Error;
+
+mod common;
+
+use common::{beat_phase, synthetic_frame};
+
+const BREATHING_HZ: f32 = 0.25;
+const BREATHING_MM: f32 = 2.0;
+const HEART_HZ: f32 = 1.2;
+const HEART_MM: f32 = 0.1;
+
+/// A frame with a reflector in the range bin, whose phase follows the chest displacement.
+fn chest_frame(config: &Config, bin: usize, displacement_mm: f32) -> Vec<u16> {
+    let phase = 4.0 * PI * displacement_mm / (config.wavelength_m() * 1e3) as f32;
+    synthetic_frame(config, |_, _, sample| 800.0 * (beat_phase(config, bin as f32, sample) + phase).cos())
+}
+
+/// The test preset with another frame time.
+fn with_frame_time(frame_repetition_time_s: f64) -> Config {
+    let mut config = Config::test_preset();
+    config.frame_repetition_time_s = frame_repetition_time_s;
+    config
+}
+
+#[test]
+fn estimates_breathing_and_heart_rate() {
+    let config = with_frame_time(0.05);
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let settings = VitalSignsSettings::default();
+    let bin = (1.0 / range_fft.range_resolution_m()).round() as usize;
+
+    let sample_rate_hz = (1.0 / config.frame_repetition_time_s) as f32;
+    let mut breathing_history = vec![0.0; VitalSigns::min_history_len(sample_rate_hz, settings.breathing_band_hz) + 100];
+    let mut heart_history = vec![0.0; VitalSigns::min_history_len(sample_rate_hz, settings.heart_band_hz) + 100];
+    let mut vitals = VitalSigns::new(&config, &range_fft, settings, &mut breathing_history, &mut heart_history).unwrap();
+    let mut scratch = vec![Complex32::new(0.0, 0.0); range_fft.fft_len()];
+    assert!(vitals.heart_rate_supported());
+
+    // Nothing is estimated until the histories are full
+    let estimate = vitals.estimate();
+    assert_eq!((estimate.breathing, estimate.heart), (None, None));
+    assert!(estimate.heart_rate_supported);
+
+    // One minute of breathing, with a weaker heart beat on top
+    for frame in 0..1200 {
+        let t = frame as f32 * config.frame_repetition_time_s as f32;
+        let expected_mm = BREATHING_MM * (2.0 * PI * BREATHING_HZ * t).sin() + HEART_MM * (2.0 * PI * HEART_HZ * t).sin();
+        let data = chest_frame(&config, bin, expected_mm);
+        let displacement_mm = vitals.process_frame(&Frame::new(&data, &config).unwrap(), &mut scratch).unwrap();
+        assert!((displacement_mm - expected_mm).abs() < 0.05, "frame {}: {} != {}", frame, displacement_mm, expected_mm);
+    }
+    assert_eq!(vitals.target_bin(), Some(bin));
+
+    let estimate = vitals.estimate();
+    let breathing = estimate.breathing.unwrap();
+    assert!((breathing.rate_per_min - 60.0 * BREATHING_HZ).abs() < 0.5, "{:?}", estimate);
+    assert!(breathing.confidence > 0.8, "{:?}", estimate);
+    let heart = estimate.heart.unwrap();
+    assert!((heart.rate_per_min - 60.0 * HEART_HZ).abs() < 2.0, "{:?}", estimate);
+    assert!(heart.confidence > 0.5, "{:?}", estimate);
+
+    // After a reset, the histories are empty again
+    vitals.reset();
+    assert_eq!(vitals.target_bin(), None);
+    assert_eq!(vitals.estimate().breathing, None);
+}
+
+#[test]
+fn heart_rate_requires_high_frame_rate() {
+    let settings = VitalSignsSettings::default();
+    let (mut breathing_history, mut heart_history) = (vec![0.0; 101], Vec::new());
+
+    // Below 10 Hz, only breathing is estimated, which is flagged in the estimate
+    let config = with_frame_time(0.2);
+    assert!(config.frame_repetition_time_s > MAX_HEART_FRAME_TIME_S);
+    let range_fft = RangeFft::new(&config, Window::Hann).unwrap();
+    let vitals = VitalSigns::new(&config, &range_fft, settings, &mut breathing_history, &mut heart_history).unwrap();
+    assert!(!vitals.heart_rate_supported());
+    assert!(!vitals.estimate().heart_rate_supported);
+
+    // Below 2 Hz, breathing is not sampled either
+    let config = with_frame_time(2.0 * MAX_BREATHING_FRAME_TIME_S);
+    let result = VitalSigns::new(&config, &range_fft, settings, &mut breathing_history, &mut heart_history);
+    assert!(matches!(result, Err(Error::InvalidParameter)));
+
+    // The history buffers must cover two periods of the lowest rate
+    let config = with_frame_time(0.05);
+    let result = VitalSigns::new(&config, &range_fft, settings, &mut breathing_history, &mut heart_history);
+    assert!(matches!(result, Err(Error::BufferWrongSize(101, 401))));
+}
@@ -0,0 +1,175 @@
+//! Debounced zone entry and exit, and line-crossing counts of tracks following scripted positions.
+
+use bgt60trxx::config::Config;
+use bgt60trxx::dsp::tracking::{Measurement, Tracker, TrackerSettings};
+use bgt60trxx::dsp::zones::{CrossingDirection, Line, ZoneEvent, ZoneMonitor, ZoneSettings, ZoneShape};
+use bgt60trxx::error::Error;
+
+const SQUARE: [(f32, f32); 4] = [(-1.0, 1.0), (1.0, 1.0), (1.0, 3.0), (-1.0, 3.0)];
+const DOOR: Line = Line { start: (-2.0, 4.0), end: (2.0, 4.0) };
+
+/// A tracker whose tracks follow the measurements almost exactly, and are confirmed right away.
+fn tracker() -> Tracker<4> {
+    let settings = TrackerSettings {
+        acceleration_std_mps2: 1e3,
+        measurement_std_m: 1e-3,
+        gate: f32::INFINITY,
+        confirm_hits: 1,
+        max_misses: 0,
+    };
+    Tracker::new(&Config::test_preset(), settings)
+}
+
+/// Moves the tracks to the positions, and returns the events of the monitor.
+fn step<const T: usize, const Z: usize>(
+    tracker: &mut Tracker<4>,
+    monitor: &mut ZoneMonitor<'_, T, Z>,
+    positions: &[(f32, f32)],
+) -> Vec<ZoneEvent> {
+    let measurements: Vec<_> = positions
+        .iter()
+        .map(|&(x_m, y_m)| Measurement { x_m, y_m, ..Default::default() })
+        .collect();
+    tracker.update(&measurements);
+    let mut events = [ZoneEvent::Entered { zone: 0, track_id: 0 }; 8];
+    let count = monitor.update(tracker.tracks(), &mut events);
+    events[..count].to_vec()
+}
+
+#[test]
+fn enter_and_exit_are_debounced() {
+    let zones = [ZoneShape::Polygon(&SQUARE)];
+    let mut monitor = ZoneMonitor::<4, 2>::new(&zones, &[], ZoneSettings::default()).unwrap();
+    let mut tracker = tracker();
+    let (outside, inside) = ((0.0, 0.5), (0.0, 2.0));
+
+    assert!(step(&mut tracker, &mut monitor, &[outside]).is_empty());
+
+    // Entering takes 3 frames inside
+    for _ in 0..2 {
+        assert!(step(&mut tracker, &mut monitor, &[inside]).is_empty());
+    }
+    assert_eq!(step(&mut tracker, &mut monitor, &[inside]), [ZoneEvent::Entered { zone: 0, track_id: 0 }]);
+    assert_eq!(monitor.occupancy(0), 1);
+
+    // Leaving for fewer than 5 frames is ignored
+    for _ in 0..4 {
+        assert!(step(&mut tracker, &mut monitor, &[outside]).is_empty());
+    }
+    assert!(step(&mut tracker, &mut monitor, &[inside]).is_empty());
+    assert_eq!(monitor.occupancy(0), 1);
+
+    for _ in 0..4 {
+        assert!(step(&mut tracker, &mut monitor, &[outside]).is_empty());
+    }
+    assert_eq!(step(&mut tracker, &mut monitor, &[outside]), [ZoneEvent::Exited { zone: 0, track_id: 0 }]);
+    assert_eq!(monitor.occupancy(0), 0);
+}
+
+#[test]
+fn deleted_tracks_exit_immediately() {
+    let zones = [ZoneShape::Polygon(&SQUARE)];
+    let settings = ZoneSettings { enter_frames: 1, ..Default::default() };
+    let mut monitor = ZoneMonitor::<4, 2>::new(&zones, &[], settings).unwrap();
+    let mut tracker = tracker();
+
+    let events = step(&mut tracker, &mut monitor, &[(0.0, 2.0), (0.5, 1.5)]);
+    assert_eq!(events, [ZoneEvent::Entered { zone: 0, track_id: 0 }, ZoneEvent::Entered { zone: 0, track_id: 1 }]);
+    assert_eq!(monitor.occupancy(0), 2);
+
+    // The tracker deletes both tracks on their first miss
+    assert_eq!(
+        step(&mut tracker, &mut monitor, &[]),
+        [ZoneEvent::Exited { zone: 0, track_id: 0 }, ZoneEvent::Exited { zone: 0, track_id: 1 }]
+    );
+    assert_eq!(monitor.occupancy(0), 0);
+}
+
+#[test]
+fn counts_line_crossings() {
+    let lines = [DOOR];
+    let mut monitor = ZoneMonitor::<4, 1>::new(&[], &lines, ZoneSettings::default()).unwrap();
+    let mut tracker = tracker();
+    let (before, after) = ((0.0, 3.5), (0.0, 4.5));
+
+    assert!(step(&mut tracker, &mut monitor, &[before]).is_empty());
+
+    // Stepping through the door for 2 frames is not a crossing
+    for _ in 0..2 {
+        assert!(step(&mut tracker, &mut monitor, &[after]).is_empty());
+    }
+    assert!(step(&mut tracker, &mut monitor, &[before]).is_empty());
+
+    // Crossing from the right to the left side of the line, looking from its start to its end, is forward
+    for _ in 0..2 {
+        assert!(step(&mut tracker, &mut monitor, &[after]).is_empty());
+    }
+    let forward = ZoneEvent::LineCrossed { line: 0, track_id: 0, direction: CrossingDirection::Forward };
+    assert_eq!(step(&mut tracker, &mut monitor, &[after]), [forward]);
+
+    for _ in 0..2 {
+        assert!(step(&mut tracker, &mut monitor, &[before]).is_empty());
+    }
+    let backward = ZoneEvent::LineCrossed { line: 0, track_id: 0, direction: CrossingDirection::Backward };
+    assert_eq!(step(&mut tracker, &mut monitor, &[before]), [backward]);
+    assert_eq!(monitor.crossings(0), (1, 1));
+
+    // Passing beside the end of the line is not a crossing
+    for position in [(3.0, 3.5), (3.0, 4.5), (3.0, 4.5), (3.0, 4.5), (3.0, 4.5)] {
+        assert!(step(&mut tracker, &mut monitor, &[position]).is_empty());
+    }
+    assert_eq!(monitor.crossings(0), (1, 1));
+
+    monitor.reset_crossings();
+    assert_eq!(monitor.crossings(0), (0, 0));
+}
+
+#[test]
+fn walking_around_the_line_is_not_a_crossing() {
+    let lines = [DOOR];
+    let mut monitor = ZoneMonitor::<4, 1>::new(&[], &lines, ZoneSettings::default()).unwrap();
+    let mut tracker = tracker();
+
+    // In front of the door, around its end and back behind it
+    for position in [(1.5, 3.5), (1.5, 3.5), (2.5, 3.5), (2.5, 4.0), (2.5, 4.5)] {
+        assert!(step(&mut tracker, &mut monitor, &[position]).is_empty());
+    }
+    for _ in 0..5 {
+        assert!(step(&mut tracker, &mut monitor, &[(1.5, 4.5)]).is_empty());
+    }
+    assert_eq!(monitor.crossings(0), (0, 0));
+
+    // Crossing the door from there is counted
+    for _ in 0..2 {
+        assert!(step(&mut tracker, &mut monitor, &[(1.5, 3.5)]).is_empty());
+    }
+    let backward = ZoneEvent::LineCrossed { line: 0, track_id: 0, direction: CrossingDirection::Backward };
+    assert_eq!(step(&mut tracker, &mut monitor, &[(1.5, 3.5)]), [backward]);
+}
+
+#[test]
+fn shapes_and_limits() {
+    // An L-shaped polygon, without its upper right corner
+    let l_shape = [(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)];
+    let polygon = ZoneShape::Polygon(&l_shape);
+    assert!(polygon.contains(0.5, 1.5) && polygon.contains(1.5, 0.5));
+    assert!(!polygon.contains(1.5, 1.5) && !polygon.contains(-0.5, 0.5));
+    assert!(!ZoneShape::Polygon(&[]).contains(0.0, 0.0));
+
+    let sector = ZoneShape::Sector { min_range_m: 1.0, max_range_m: 3.0, min_azimuth_rad: 0.0, max_azimuth_rad: 0.5 };
+    assert!(sector.contains(0.5, 2.0));
+    assert!(!sector.contains(-0.5, 2.0) && !sector.contains(0.1, 0.5) && !sector.contains(2.0, 2.0));
+
+    // Events beyond the output are dropped, but the occupancy is counted regardless
+    let zones = [ZoneShape::Polygon(&SQUARE), sector];
+    let settings = ZoneSettings { enter_frames: 1, ..Default::default() };
+    let mut monitor = ZoneMonitor::<4, 2>::new(&zones, &[], settings).unwrap();
+    let mut tracker = tracker();
+    tracker.update(&[Measurement { x_m: 0.5, y_m: 2.0, ..Default::default() }]);
+    let mut events = [ZoneEvent::Entered { zone: 0, track_id: 0 }; 1];
+    assert_eq!(monitor.update(tracker.tracks(), &mut events), 1);
+    assert_eq!((monitor.occupancy(0), monitor.occupancy(1)), (1, 1));
+
+    let result = ZoneMonitor::<4, 1>::new(&zones, &[], settings);
+    assert!(matches!(result, Err(Error::InvalidParameter)));
+}